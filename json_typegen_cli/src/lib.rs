@@ -0,0 +1,336 @@
+//! The `json_typegen` command line interface, as a library
+//!
+//! This is only useful for making your own build of the CLI, e.g. one with output modes added
+//! with `json_typegen_shared::register_generator` before calling `main`.
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use json_typegen_shared::internal_util::display_error_with_causes;
+use json_typegen_shared::{
+    InputMode, Options, OutputMode, codegen, codegen_from_macro, diff_shapes, infer_shape,
+    merge_rust, parse, registered_generators, validate_sample,
+};
+use std::fs::OpenOptions;
+use std::io::{self, Read, Write};
+
+const OUTPUT_MODES: &[&str] = &[
+    "rust",
+    "typescript",
+    "typescript/typealias",
+    "kotlin",
+    "kotlin/jackson",
+    "kotlin/kotlinx",
+    "kotlin/moshi",
+    "kotlin/gson",
+    "python",
+    "python/pydantic",
+    "python/pydantic_v1",
+    "python/dataclass",
+    "python/typeddict",
+    "python/attrs",
+    "python/msgspec",
+    "json_schema",
+    "valibot",
+    "io_ts",
+    "arktype",
+    "effect_schema",
+    "protobuf",
+    "graphql",
+    "avro",
+    "sql",
+    "shape",
+    "shape_json",
+    "mock",
+    "template",
+    "markdown",
+    "html",
+];
+
+fn main_with_result() -> Result<(), Box<dyn std::error::Error>> {
+    let custom_modes = registered_generators();
+    let mut output_modes = OUTPUT_MODES.to_vec();
+    output_modes.extend(custom_modes.iter().map(String::as_str));
+
+    let matches = App::new("json_typegen CLI")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Generate Rust types from JSON samples")
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .arg(
+            Arg::with_name("input")
+                .help(concat!(
+                    "The input to generate types from. A sample, file, URL, or macro. To read ",
+                    "from standard input, a dash, '-', can be used as the input argument."
+                ))
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("name")
+                .short("n")
+                .long("name")
+                .help("Name for the root generated type. Default: Root.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .help("What file to write the output to. Default: standard output.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("options")
+                .long("options")
+                .help(concat!(
+                    "Options for code generation, in the form of an options block. If input is a ",
+                    "macro, this option is ignored."
+                ))
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("merge")
+                .long("merge")
+                .help(concat!(
+                    "A Rust file with types generated earlier, to update in place instead of ",
+                    "generating new types, keeping any changes made to them. Written to the ",
+                    "output file instead, if given."
+                ))
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("template")
+                .long("template")
+                .help(concat!(
+                    "A template file to render with the inferred types, instead of ",
+                    "generating code for one of the output modes."
+                ))
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("output-mode")
+                .long("output-mode")
+                .short("-O")
+                .possible_values(&output_modes)
+                .help("What to output.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("input-mode")
+                .long("input-mode")
+                .short("-I")
+                .possible_values(&["json", "sql", "shape_json"])
+                .help("What kind of input it is. Default: json.")
+                .takes_value(true),
+        )
+        .subcommand(
+            SubCommand::with_name("diff")
+                .about(concat!(
+                    "Compare the shapes of two inputs, and fail if the new one has changes that ",
+                    "would break deserialization with types generated from the old one."
+                ))
+                .arg(
+                    Arg::with_name("old")
+                        .help("The old sample, file, URL or saved shape.")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("new")
+                        .help("The new sample, file, URL or saved shape.")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("options")
+                        .long("options")
+                        .help(
+                            "Options block with e.g. `unwrap` and inference hints for both inputs.",
+                        )
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("input-mode")
+                        .long("input-mode")
+                        .short("-I")
+                        .possible_values(&["json", "sql", "shape_json"])
+                        .help("What kind of input both inputs are. Default: json.")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("check")
+                .about(concat!(
+                    "Check that a sample fits the shape of a reference, and fail with the ",
+                    "mismatches if it does not."
+                ))
+                .arg(
+                    Arg::with_name("input")
+                        .help("The sample to check. A sample, file or URL.")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("reference")
+                        .help("Saved shape, or samples to infer the shape from.")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("options")
+                        .long("options")
+                        .help("Options block with e.g. `unwrap` and inference hints for the reference.")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("input-mode")
+                        .long("input-mode")
+                        .short("-I")
+                        .possible_values(&["json", "sql", "shape_json"])
+                        .help("What kind of input the reference is. Default: json.")
+                        .takes_value(true),
+                ),
+        )
+        .get_matches();
+
+    if let Some(diff_matches) = matches.subcommand_matches("diff") {
+        return diff(diff_matches);
+    }
+    if let Some(check_matches) = matches.subcommand_matches("check") {
+        return check(check_matches);
+    }
+
+    let source = matches
+        .value_of("input")
+        .ok_or("Input argument is required")?;
+
+    let input = if source == "-" {
+        let mut buffer = String::new();
+        io::stdin().read_to_string(&mut buffer)?;
+        buffer
+    } else {
+        source.to_string()
+    };
+
+    let code = if input.trim().starts_with("json_typegen") {
+        codegen_from_macro(&input)
+    } else {
+        let name = matches.value_of("name").unwrap_or("Root");
+        let mut options = match matches.value_of("options") {
+            Some(block) => parse::options(block)?,
+            None => Options::default(),
+        };
+        if let Some(output_mode) = matches.value_of("output-mode") {
+            options.output_mode = OutputMode::parse(output_mode).ok_or("Invalid output mode")?;
+        }
+        if let Some(input_mode) = matches.value_of("input-mode") {
+            options.input_mode = InputMode::parse(input_mode).ok_or("Invalid input mode")?;
+        }
+        if let Some(path) = matches.value_of("template") {
+            options.output_mode = OutputMode::Template;
+            options.template = Some(std::fs::read_to_string(path)?);
+        }
+        if let Some(path) = matches.value_of("merge") {
+            return merge(path, name, &input, options, matches.value_of("output"));
+        }
+        codegen(name, &input, options)
+    };
+
+    if let Some(filename) = matches.value_of("output") {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(filename)?;
+
+        file.write_all(code?.as_bytes())?;
+    } else {
+        print!("{}", code?);
+    }
+
+    Ok(())
+}
+
+fn merge(
+    path: &str,
+    name: &str,
+    input: &str,
+    options: Options,
+    output: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if options.output_mode != OutputMode::Rust {
+        return Err("Merging is only supported for Rust output".into());
+    }
+    let existing = std::fs::read_to_string(path)?;
+    let merged = merge_rust(&existing, name, input, options)?;
+    for removed in merged.removed_fields.iter() {
+        eprintln!("Warning: {}, but was kept", removed);
+    }
+    std::fs::write(output.unwrap_or(path), merged.code)?;
+    Ok(())
+}
+
+/// Options for inference in subcommands
+fn inference_options(matches: &ArgMatches) -> Result<Options, Box<dyn std::error::Error>> {
+    let mut options = match matches.value_of("options") {
+        Some(block) => parse::options(block)?,
+        None => Options::default(),
+    };
+    if let Some(input_mode) = matches.value_of("input-mode") {
+        options.input_mode = InputMode::parse(input_mode).ok_or("Invalid input mode")?;
+    }
+    Ok(options)
+}
+
+fn diff(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let options = inference_options(matches)?;
+
+    let old = infer_shape(
+        matches.value_of("old").ok_or("Old input is required")?,
+        options.clone(),
+    )?;
+    let new = infer_shape(
+        matches.value_of("new").ok_or("New input is required")?,
+        options,
+    )?;
+
+    let changes = diff_shapes(&old, &new);
+    for change in changes.iter() {
+        println!("{}", change);
+    }
+
+    let breaking = changes.iter().filter(|change| change.breaking).count();
+    if breaking > 0 {
+        return Err(format!("Found {} breaking change(s)", breaking).into());
+    }
+    Ok(())
+}
+
+fn check(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let options = inference_options(matches)?;
+
+    let shape = infer_shape(
+        matches
+            .value_of("reference")
+            .ok_or("Reference is required")?,
+        options,
+    )?;
+    let mismatches = validate_sample(
+        matches.value_of("input").ok_or("Input is required")?,
+        &shape,
+    )?;
+
+    for mismatch in mismatches.iter() {
+        println!("{}", mismatch);
+    }
+
+    if !mismatches.is_empty() {
+        return Err(format!("Found {} mismatch(es)", mismatches.len()).into());
+    }
+    Ok(())
+}
+
+/// Run the CLI with the arguments of the process, exiting on errors
+pub fn main() {
+    let result = main_with_result();
+
+    if let Err(e) = result {
+        eprintln!("Error: {}", display_error_with_causes(&*e));
+        std::process::exit(1);
+    }
+}
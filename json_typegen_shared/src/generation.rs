@@ -1,11 +1,42 @@
+use crate::shape::Shape;
+
+pub mod avro;
+pub mod docs;
+pub mod generator;
+pub mod graphql;
 pub mod json_schema;
 pub mod kotlin;
+pub mod mock;
+pub mod protobuf;
 pub mod python;
 pub mod rust;
+pub mod rust_merge;
 pub mod shape;
+pub mod sql_ddl;
+pub mod template;
 pub mod typescript;
 pub mod typescript_type_alias;
+pub mod validator_schema;
 pub mod zod_schema;
 
 mod serde_case; // used in rust
-mod value; // used in json_schema and shape
+pub(crate) mod value; // used in avro, json_schema, mock, shape and crate::shape_json
+
+/// One of several named values that types are generated for together, sharing types
+pub struct Root<'a> {
+    pub name: &'a str,
+    pub shape: &'a Shape,
+    /// The JSON pointer that observations for this root are recorded under
+    pub pointer: String,
+}
+
+impl<'a> Root<'a> {
+    /// The only root, when generating code for a single value
+    pub fn single(name: &'a str, shape: &'a Shape) -> Self {
+        Root {
+            name,
+            shape,
+            pointer: String::new(),
+        }
+    }
+}
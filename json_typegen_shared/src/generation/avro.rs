@@ -0,0 +1,216 @@
+use linked_hash_map::LinkedHashMap;
+use std::collections::HashSet;
+
+use crate::generation::value::{Value, pretty_print_value};
+use crate::options::Options;
+use crate::shape::{self, Shape};
+use crate::to_singular::to_singular;
+use crate::util::{snake_case, string_hashmap, type_case};
+
+pub struct Ctxt {
+    options: Options,
+    type_names: HashSet<String>,
+    created_records: Vec<(Shape, Ident)>,
+}
+
+pub type Ident = String;
+pub type Code = String;
+
+pub fn avro_schema(name: &str, shape: &Shape, options: Options) -> Code {
+    let mut ctxt = Ctxt {
+        options,
+        type_names: HashSet::new(),
+        created_records: Vec::new(),
+    };
+
+    let value = type_from_shape(&mut ctxt, name, shape);
+
+    pretty_print_value(0, &value)
+}
+
+fn type_from_shape(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> Value {
+    use crate::shape::Shape::*;
+    match shape {
+        Any | Bottom => any_type(),
+        Null => Value::Str("null"),
+        Bool => Value::Str("boolean"),
+        StringT => Value::Str("string"),
+        Integer => Value::Str("long"),
+        Floating => Value::Str("double"),
+        Tuple(shapes, _n) => {
+            let folded = shape::fold_shapes(shapes.clone());
+            if folded == Any && shapes.iter().any(|s| s != &Any) {
+                // Avro arrays are homogeneous, so allow any (primitive) value as item
+                generate_vec_type(ctxt, path, &Any)
+            } else {
+                generate_vec_type(ctxt, path, &folded)
+            }
+        }
+        VecT { elem_type: e } => generate_vec_type(ctxt, path, e),
+        Struct { fields } => generate_record_type(ctxt, path, fields, shape),
+        MapT { val_type: v } => generate_map_type(ctxt, path, v),
+        Opaque(t) => Value::String(t.clone()),
+        SelfRef | Recursive(_) | TypeParam(..) => {
+            unreachable!("recursion and type parameters are removed for this output")
+        }
+        Optional(e) | Nullable(e) => match type_from_shape(ctxt, path, e) {
+            // Unions may not contain other unions. The union for `Any` already includes null.
+            Value::Array(types) => Value::Array(types),
+            Value::Str("null") => Value::Str("null"),
+            inner => Value::Array(vec![Value::Str("null"), inner]),
+        },
+    }
+}
+
+/// Avro has no type for arbitrary values, so use a union of the primitive types instead
+fn any_type() -> Value {
+    Value::Array(vec![
+        Value::Str("null"),
+        Value::Str("boolean"),
+        Value::Str("long"),
+        Value::Str("double"),
+        Value::Str("string"),
+    ])
+}
+
+fn generate_vec_type(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> Value {
+    let singular = to_singular(path);
+    let inner = type_from_shape(ctxt, &singular, shape);
+    Value::Object(string_hashmap! {
+        "type" => Value::Str("array"),
+        "items" => inner
+    })
+}
+
+fn generate_map_type(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> Value {
+    let singular = to_singular(path);
+    let inner = type_from_shape(ctxt, &singular, shape);
+    Value::Object(string_hashmap! {
+        "type" => Value::Str("map"),
+        "values" => inner
+    })
+}
+
+fn field_name(name: &str, used_names: &HashSet<String>) -> Ident {
+    if is_avro_name(name) && !used_names.contains(name) {
+        return name.to_string();
+    }
+    type_or_field_name(name, used_names, "field", snake_case)
+}
+
+fn type_name(name: &str, used_names: &HashSet<String>) -> Ident {
+    type_or_field_name(name, used_names, "GeneratedType", type_case)
+}
+
+fn type_or_field_name(
+    name: &str,
+    used_names: &HashSet<String>,
+    default_name: &str,
+    case_fn: fn(&str) -> String,
+) -> Ident {
+    let name = name.trim();
+    let mut output_name = case_fn(name);
+    if output_name.is_empty() {
+        output_name.push_str(default_name);
+    }
+    if output_name.starts_with(|c: char| c.is_ascii_digit()) {
+        output_name = String::from("n") + &output_name;
+    }
+    if !used_names.contains(&output_name) {
+        return output_name;
+    }
+    for n in 2.. {
+        let temp = format!("{}{}", output_name, n);
+        if !used_names.contains(&temp) {
+            return temp;
+        }
+    }
+    unreachable!()
+}
+
+/// Names matching `[A-Za-z_][A-Za-z0-9_]*`, as required for Avro record and field names
+fn is_avro_name(s: &str) -> bool {
+    match s.as_bytes().split_first() {
+        Some((first, rest)) => {
+            (first.is_ascii_alphabetic() || *first == b'_')
+                && rest.iter().all(|b| b.is_ascii_alphanumeric() || *b == b'_')
+        }
+        None => false,
+    }
+}
+
+fn generate_record_type(
+    ctxt: &mut Ctxt,
+    path: &str,
+    field_shapes: &LinkedHashMap<String, Shape>,
+    containing_shape: &Shape,
+) -> Value {
+    // Named types can only be defined once, and are referred to by name after that
+    for (created_for_shape, ident) in ctxt.created_records.iter() {
+        if created_for_shape.is_acceptable_substitution_for(containing_shape) {
+            return Value::String(ident.clone());
+        }
+    }
+
+    // The namespace is set on the outermost record, and inherited by the nested ones
+    let namespace = if ctxt.created_records.is_empty() {
+        ctxt.options.avro_namespace.clone()
+    } else {
+        None
+    };
+
+    let type_name = type_name(path, &ctxt.type_names);
+    ctxt.type_names.insert(type_name.clone());
+    ctxt.created_records
+        .push((containing_shape.clone(), type_name.clone()));
+
+    let mut field_names = HashSet::new();
+
+    let fields = field_shapes
+        .iter()
+        .map(|(name, typ)| {
+            let field_name = field_name(name, &field_names);
+            field_names.insert(field_name.clone());
+
+            let field_type = type_from_shape(ctxt, name, typ);
+            let is_nullable_union = matches!(
+                field_type,
+                Value::Array(ref types) if matches!(types.first(), Some(Value::Str("null")))
+            );
+
+            let mut field = string_hashmap! {
+                "name" => Value::String(field_name),
+                "type" => field_type,
+            };
+            if is_nullable_union {
+                field.insert("default".into(), Value::Null);
+            }
+            Value::Object(field)
+        })
+        .collect();
+
+    let mut record = string_hashmap! {
+        "type" => Value::Str("record"),
+        "name" => Value::String(type_name),
+    };
+    if let Some(namespace) = namespace {
+        record.insert("namespace".into(), Value::String(namespace));
+    }
+    record.insert("fields".into(), Value::Array(fields));
+
+    Value::Object(record)
+}
+
+#[cfg(test)]
+mod avro_codegen_tests {
+    use super::*;
+
+    #[test]
+    fn is_avro_name_test() {
+        assert!(is_avro_name("foo"));
+        assert!(is_avro_name("_foo_Bar2"));
+        assert!(!is_avro_name("2foo"));
+        assert!(!is_avro_name("foo-bar"));
+        assert!(!is_avro_name(""));
+    }
+}
@@ -0,0 +1,190 @@
+use crate::generation::generator::{Generator, TypeDef};
+
+pub type Ident = String;
+pub type Code = String;
+
+const NULLABLE: &str = "nullable ";
+
+/// Documentation as Markdown, with a table for each type
+pub struct Markdown;
+
+/// Documentation as an HTML fragment, with a table for each type
+pub struct Html;
+
+/// The parts of the documentation that differ between Markdown and HTML
+trait Format: Send + Sync {
+    fn link(name: &str) -> String;
+    fn code(text: &str) -> String;
+    fn heading(name: &str) -> String;
+    fn table(header: &[&str], rows: Vec<Vec<String>>) -> String;
+    fn paragraph(text: &str) -> String;
+}
+
+impl Format for Markdown {
+    fn link(name: &str) -> String {
+        format!("[{}](#{})", name, name.to_lowercase())
+    }
+
+    fn code(text: &str) -> String {
+        // pipes would end the table cell, even inside code
+        format!("`{}`", text.replace('|', "\\|"))
+    }
+
+    fn heading(name: &str) -> String {
+        format!("## {}", name)
+    }
+
+    fn table(header: &[&str], rows: Vec<Vec<String>>) -> String {
+        let mut table = format!("| {} |\n", header.join(" | "));
+        let separators: Vec<&str> = header.iter().map(|_| "---").collect();
+        table += &format!("| {} |", separators.join(" | "));
+        for row in rows {
+            table += &format!("\n| {} |", row.join(" | "));
+        }
+        table
+    }
+
+    fn paragraph(text: &str) -> String {
+        text.to_string()
+    }
+}
+
+impl Format for Html {
+    fn link(name: &str) -> String {
+        format!("<a href=\"#{0}\">{0}</a>", name)
+    }
+
+    fn code(text: &str) -> String {
+        format!("<code>{}</code>", escape_html(text))
+    }
+
+    fn heading(name: &str) -> String {
+        format!("<h2 id=\"{0}\">{0}</h2>", name)
+    }
+
+    fn table(header: &[&str], rows: Vec<Vec<String>>) -> String {
+        let mut table = "<table>\n  <thead>\n    <tr>".to_string();
+        for cell in header {
+            table += &format!("<th>{}</th>", cell);
+        }
+        table += "</tr>\n  </thead>\n  <tbody>\n";
+        for row in rows {
+            table += "    <tr>";
+            for cell in row {
+                table += &format!("<td>{}</td>", cell);
+            }
+            table += "</tr>\n";
+        }
+        table + "  </tbody>\n</table>"
+    }
+
+    fn paragraph(text: &str) -> String {
+        format!("<p>{}</p>", text)
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn type_definition<F: Format>(def: &TypeDef) -> Code {
+    let rows = def
+        .fields
+        .iter()
+        .map(|field| {
+            let typ = if field.nullable {
+                field.typ.strip_prefix(NULLABLE).unwrap_or(&field.typ)
+            } else {
+                &field.typ
+            };
+            // null says less about the values than any other example
+            let example = field
+                .examples
+                .iter()
+                .find(|example| *example != "null")
+                .or(field.examples.first())
+                .map(|example| F::code(example))
+                .unwrap_or_default();
+            vec![
+                F::code(&field.json_name),
+                typ.to_string(),
+                yes_no(!field.optional),
+                yes_no(field.nullable),
+                example,
+                F::code(&field.pointer),
+            ]
+        })
+        .collect();
+    let header = ["Field", "Type", "Required", "Nullable", "Example", "Path"];
+    format!("{}\n\n{}", F::heading(&def.name), F::table(&header, rows))
+}
+
+fn type_alias<F: Format>(name: &str, typ: &str) -> Code {
+    format!(
+        "{}\n\n{}",
+        F::heading(name),
+        F::paragraph(&format!("Type: {}", typ))
+    )
+}
+
+fn yes_no(value: bool) -> String {
+    if value { "yes" } else { "no" }.to_string()
+}
+
+impl<F: Format> Generator for F {
+    fn any_type(&self) -> Ident {
+        "any".to_string()
+    }
+
+    fn bool_type(&self) -> Ident {
+        "boolean".to_string()
+    }
+
+    fn string_type(&self) -> Ident {
+        "string".to_string()
+    }
+
+    fn integer_type(&self) -> Ident {
+        "integer".to_string()
+    }
+
+    fn floating_type(&self) -> Ident {
+        "number".to_string()
+    }
+
+    fn list_type(&self, elem_type: &str) -> Ident {
+        format!("array of {}", elem_type)
+    }
+
+    fn map_type(&self, value_type: &str) -> Ident {
+        format!("map of {}", value_type)
+    }
+
+    fn nullable_type(&self, inner: &str) -> Ident {
+        format!("{}{}", NULLABLE, inner)
+    }
+
+    fn tuple_type(&self, elem_types: &[Ident]) -> Ident {
+        format!("tuple of {}", elem_types.join(", "))
+    }
+
+    fn opaque_type(&self, name: &str) -> Ident {
+        // types from hints are code, and could contain characters used for markup
+        F::code(name)
+    }
+
+    fn type_reference(&self, name: &str, _is_self_reference: bool) -> Ident {
+        F::link(name)
+    }
+
+    fn type_definition(&self, def: &TypeDef) -> Code {
+        type_definition::<F>(def)
+    }
+
+    fn type_alias(&self, name: &str, typ: &str) -> Code {
+        type_alias::<F>(name, typ)
+    }
+}
@@ -0,0 +1,466 @@
+use linked_hash_map::LinkedHashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, LazyLock, RwLock};
+
+use crate::JTError;
+use crate::generation::Root;
+use crate::observations::{Observations, child_pointer};
+use crate::options::{Options, OutputMode};
+use crate::shape::{self, Shape};
+use crate::to_singular::to_singular;
+use crate::util::type_case;
+
+pub type Ident = String;
+pub type Code = String;
+
+/// Code generation for an output mode, like TypeScript or one that is not built in
+///
+/// Each method is a hook for one kind of shape or definition. Walking the shape, naming types,
+/// reusing types for objects of the same shape, and recursive types are all taken care of,
+/// so a generator only has to know how to write types in its language.
+/// Register it with `register_generator` to use it as an output mode.
+pub trait Generator: Send + Sync {
+    /// For values with no known type, like `null` or the elements of empty arrays
+    fn any_type(&self) -> Ident;
+    fn bool_type(&self) -> Ident;
+    fn string_type(&self) -> Ident;
+    fn integer_type(&self) -> Ident;
+    fn floating_type(&self) -> Ident;
+    fn list_type(&self, elem_type: &str) -> Ident;
+    /// For objects used as maps, with string keys
+    fn map_type(&self, value_type: &str) -> Ident;
+    /// For values that can be null
+    fn nullable_type(&self, inner: &str) -> Ident;
+
+    /// For values that can be missing, outside of `TypeDef::fields`. The same as for null by default.
+    fn optional_type(&self, inner: &str) -> Ident {
+        self.nullable_type(inner)
+    }
+
+    /// For short arrays with elements of different types. A list of anything by default.
+    fn tuple_type(&self, elem_types: &[Ident]) -> Ident {
+        let _ = elem_types;
+        self.list_type(&self.any_type())
+    }
+
+    /// For a type set with the `use_type` hint
+    fn opaque_type(&self, name: &str) -> Ident {
+        name.to_string()
+    }
+
+    /// For a reference to a defined type, like the type of a field with an object as its value
+    ///
+    /// `is_self_reference` is set for references from a type to itself, which need to be
+    /// indirect in some languages.
+    fn type_reference(&self, name: &str, is_self_reference: bool) -> Ident {
+        let _ = is_self_reference;
+        name.to_string()
+    }
+
+    /// For a generic type with the type arguments for a use of it. Only used by the built in
+    /// output modes with generic types, since type parameters are replaced by their arguments
+    /// for other generators.
+    fn generic_type(&self, reference: &str, args: &[Ident]) -> Ident {
+        format!("{}<{}>", reference, args.join(", "))
+    }
+
+    /// A name for a type for values found under a field, like `"user"` or `"tags"`.
+    /// Only needs to follow naming conventions, since it is made unique afterwards.
+    fn type_name(&self, name: &str) -> Ident {
+        type_case(name)
+    }
+
+    /// A name for a field, if different from the one in the JSON. Only made unique afterwards,
+    /// so e.g. keys that are not identifiers have to be handled here or when writing the field.
+    fn field_name(&self, json_name: &str) -> Ident {
+        json_name.to_string()
+    }
+
+    /// Names that can not be used for types or fields, which get a `_` added
+    fn reserved_words(&self) -> &[&str] {
+        &[]
+    }
+
+    /// The definition of a type for objects
+    fn type_definition(&self, def: &TypeDef) -> Code;
+
+    /// The definition of a named type for a root value that is not an object
+    fn type_alias(&self, name: &str, typ: &str) -> Code;
+
+    /// The whole output from the definitions, in order. Joined by blank lines by default,
+    /// but this is where e.g. imports would be added.
+    fn file(&self, definitions: Vec<Code>) -> Code {
+        definitions.join("\n\n")
+    }
+}
+
+/// A type for objects, passed to `Generator::type_definition`
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub struct TypeDef {
+    /// The name from `Generator::type_name`, made unique
+    pub name: Ident,
+    pub fields: Vec<FieldDef>,
+    /// Whether any of the fields refer to the type itself
+    pub is_recursive: bool,
+    /// The names of the type parameters, for generic types. See `Generator::generic_type`.
+    pub type_params: Vec<Ident>,
+}
+
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub struct FieldDef {
+    /// The name of the field in the JSON
+    pub json_name: String,
+    /// The name from `Generator::field_name`, made unique within the type
+    pub name: Ident,
+    /// The type of the value, when present
+    pub typ: Ident,
+    /// Whether the field is missing in some values
+    pub optional: bool,
+    /// Whether the value can be null, which is also part of the type
+    pub nullable: bool,
+    /// From the `doc_comments` option, without comment syntax
+    pub doc_lines: Vec<String>,
+    /// JSON pointer to the value from the root it was first found under, with `-` for any element
+    pub pointer: String,
+    /// Some of the values seen, as JSON, or of the elements for lists. Only for the output modes
+    /// that record them.
+    pub examples: Vec<String>,
+}
+
+type Registry = RwLock<HashMap<String, Arc<dyn Generator>>>;
+
+static GENERATORS: LazyLock<Registry> = LazyLock::new(Default::default);
+
+/// Make a generator available as an output mode with the given name
+///
+/// After this, `OutputMode::parse` and the `output_mode` option accept the name. Names of built in
+/// output modes, like `rust`, can not be used. Registering a name again replaces the generator.
+#[allow(clippy::result_large_err)]
+pub fn register_generator(name: &str, generator: impl Generator + 'static) -> Result<(), JTError> {
+    if OutputMode::parse(name).is_some_and(|mode| !matches!(mode, OutputMode::Custom(_))) {
+        return Err(JTError::BuiltInOutputMode(name.to_string()));
+    }
+    GENERATORS
+        .write()
+        .unwrap_or_else(|err| err.into_inner())
+        .insert(name.to_string(), Arc::new(generator));
+    Ok(())
+}
+
+/// The names of the registered generators, sorted
+pub fn registered_generators() -> Vec<String> {
+    let mut names: Vec<String> = GENERATORS
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .keys()
+        .cloned()
+        .collect();
+    names.sort();
+    names
+}
+
+pub(crate) fn registered_generator(name: &str) -> Option<Arc<dyn Generator>> {
+    GENERATORS
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .get(name)
+        .cloned()
+}
+
+struct Ctxt<'a> {
+    generator: &'a dyn Generator,
+    options: &'a Options,
+    observations: &'a Observations,
+    type_names: HashSet<String>,
+    created_types: Vec<(Shape, Ident)>,
+    /// Names of the recursive types being generated, innermost last
+    recursive_types: Vec<Ident>,
+    /// JSON pointer to the value a type is generated for, to look up observations
+    pointer: String,
+    /// The pointer of the root being generated, which `FieldDef::pointer` is relative to
+    root_pointer: String,
+    /// Definitions in the order they are created, with a placeholder until they are done
+    defs: Vec<Option<Definition>>,
+    /// Type parameters of the type being generated, with their type arguments
+    type_params: Option<Vec<(Ident, Ident)>>,
+}
+
+/// A definition to be written by the generator, in the order they are output
+pub(crate) enum Definition {
+    Type(TypeDef),
+    Alias { name: Ident, typ: Ident },
+}
+
+pub(crate) fn generate(
+    generator: &dyn Generator,
+    roots: &[Root],
+    options: &Options,
+    observations: &Observations,
+) -> Code {
+    let defs = definitions(generator, roots, options, observations)
+        .into_iter()
+        .map(|def| match def {
+            Definition::Type(def) => generator.type_definition(&def),
+            Definition::Alias { name, typ } => generator.type_alias(&name, &typ),
+        })
+        .collect();
+    generator.file(defs)
+}
+
+/// The definitions for the roots, with the types referred to written by the generator
+pub(crate) fn definitions(
+    generator: &dyn Generator,
+    roots: &[Root],
+    options: &Options,
+    observations: &Observations,
+) -> Vec<Definition> {
+    let mut ctxt = Ctxt {
+        generator,
+        options,
+        observations,
+        type_names: HashSet::new(),
+        created_types: Vec::new(),
+        recursive_types: Vec::new(),
+        pointer: String::new(),
+        root_pointer: String::new(),
+        defs: Vec::new(),
+        type_params: None,
+    };
+
+    // reserve the requested names, so that no other types can take them
+    for root in roots {
+        ctxt.type_names.insert(root.name.to_string());
+    }
+
+    for root in roots {
+        let name = root.name;
+        if matches!(root.shape, Shape::Struct { .. } | Shape::Recursive(_)) {
+            // let the type itself have the requested name
+            ctxt.type_names.remove(name);
+        }
+
+        ctxt.pointer = root.pointer.clone();
+        ctxt.root_pointer = root.pointer.clone();
+        let index = ctxt.defs.len();
+        let typ = type_from_shape(&mut ctxt, name, root.shape);
+        ctxt.type_names.insert(name.to_string());
+
+        let has_own_type = matches!(
+            ctxt.defs.get(index),
+            Some(Some(Definition::Type(def))) if def.name == name
+        );
+        if !has_own_type {
+            let alias = Definition::Alias {
+                name: name.to_string(),
+                typ,
+            };
+            ctxt.defs.insert(index, Some(alias));
+        }
+    }
+
+    ctxt.defs.into_iter().flatten().collect()
+}
+
+fn type_from_shape(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> Ident {
+    use crate::shape::Shape::*;
+    let generator = ctxt.generator;
+    match shape {
+        Null | Any | Bottom => generator.any_type(),
+        Bool => generator.bool_type(),
+        StringT => generator.string_type(),
+        Integer => generator.integer_type(),
+        Floating => generator.floating_type(),
+        Tuple(shapes, _n) => {
+            let folded = shape::fold_shapes(shapes.clone());
+            let pointer = child_pointer(&ctxt.pointer, "-");
+            let parent_pointer = std::mem::replace(&mut ctxt.pointer, pointer);
+            let typ = if folded == Any && shapes.iter().any(|s| s != &Any) {
+                let types: Vec<Ident> = shapes
+                    .iter()
+                    .map(|shape| type_from_shape(ctxt, path, shape))
+                    .collect();
+                generator.tuple_type(&types)
+            } else {
+                generator.list_type(&type_from_shape(ctxt, &to_singular(path), &folded))
+            };
+            ctxt.pointer = parent_pointer;
+            typ
+        }
+        VecT { elem_type: e } => {
+            let pointer = child_pointer(&ctxt.pointer, "-");
+            let parent_pointer = std::mem::replace(&mut ctxt.pointer, pointer);
+            let typ = generator.list_type(&type_from_shape(ctxt, &to_singular(path), e));
+            ctxt.pointer = parent_pointer;
+            typ
+        }
+        MapT { val_type: v } => generator.map_type(&type_from_shape(ctxt, &to_singular(path), v)),
+        Struct { fields } => generate_type_definition(ctxt, path, fields, shape),
+        Recursive(e) => match &**e {
+            Struct { fields } => generate_type_definition(ctxt, path, fields, shape),
+            _ => type_from_shape(ctxt, path, e),
+        },
+        SelfRef => match ctxt.recursive_types.last() {
+            Some(name) => generator.type_reference(name, true),
+            None => generator.any_type(),
+        },
+        TypeParam(name, arg) => {
+            let arg = type_from_shape(ctxt, path, arg);
+            match ctxt.type_params {
+                Some(ref mut type_params) => {
+                    if !type_params.iter().any(|(param, _)| param == name) {
+                        type_params.push((name.clone(), arg));
+                    }
+                    name.clone()
+                }
+                // Outside of any type there is nothing to be generic
+                None => arg,
+            }
+        }
+        Opaque(t) => generator.opaque_type(t),
+        Optional(e) => {
+            let inner = type_from_shape(ctxt, path, e);
+            if ctxt.options.use_default_for_missing_fields {
+                inner
+            } else {
+                generator.optional_type(&inner)
+            }
+        }
+        Nullable(e) => generator.nullable_type(&type_from_shape(ctxt, path, e)),
+    }
+}
+
+fn generate_type_definition(
+    ctxt: &mut Ctxt,
+    path: &str,
+    field_shapes: &LinkedHashMap<String, Shape>,
+    containing_shape: &Shape,
+) -> Ident {
+    let generator = ctxt.generator;
+    if let Some((_, name)) = ctxt.created_types.iter().find(|(created_for_shape, _)| {
+        created_for_shape.is_acceptable_substitution_for(containing_shape)
+    }) {
+        let reference = generator.type_reference(name, false);
+        return with_type_args(ctxt, reference, containing_shape);
+    }
+
+    let type_name = unique_name(generator, &type_name(generator, path), &ctxt.type_names);
+    ctxt.type_names.insert(type_name.clone());
+    ctxt.created_types
+        .push((containing_shape.clone(), type_name.clone()));
+
+    let is_recursive = matches!(containing_shape, Shape::Recursive(_));
+    if is_recursive {
+        ctxt.recursive_types.push(type_name.clone());
+    }
+
+    // the definition goes before the ones for its fields
+    let index = ctxt.defs.len();
+    ctxt.defs.push(None);
+
+    let mut field_names = HashSet::new();
+    let mut fields = Vec::new();
+    let outer_type_params = ctxt.type_params.replace(Vec::new());
+    for (json_name, typ) in field_shapes.iter() {
+        let name = unique_name(generator, &generator.field_name(json_name), &field_names);
+        field_names.insert(name.clone());
+
+        let (optional, collapsed) = match typ {
+            Shape::Optional(inner) => (true, &**inner),
+            _ => (false, typ),
+        };
+        let nullable = matches!(collapsed, Shape::Nullable(_) | Shape::Null);
+        let pointer = child_pointer(&ctxt.pointer, json_name);
+        let doc_lines = ctxt.observations.doc_lines(&pointer);
+        let observations = ctxt.observations;
+        let examples = [pointer.clone(), child_pointer(&pointer, "-")]
+            .iter()
+            .filter_map(|pointer| observations.get(pointer))
+            .map(|observed| observed.examples.clone())
+            .find(|examples| !examples.is_empty())
+            .unwrap_or_default();
+        let relative_pointer = pointer[ctxt.root_pointer.len()..].to_string();
+
+        let parent_pointer = std::mem::replace(&mut ctxt.pointer, pointer);
+        let typ = type_from_shape(ctxt, json_name, collapsed);
+        ctxt.pointer = parent_pointer;
+
+        fields.push(FieldDef {
+            json_name: json_name.clone(),
+            name,
+            typ,
+            optional,
+            nullable,
+            doc_lines,
+            pointer: relative_pointer,
+            examples,
+        });
+    }
+
+    if is_recursive {
+        ctxt.recursive_types.pop();
+    }
+    let type_params = std::mem::replace(&mut ctxt.type_params, outer_type_params);
+    let (type_params, args): (Vec<Ident>, Vec<Ident>) =
+        type_params.unwrap_or_default().into_iter().unzip();
+
+    let reference = generator.type_reference(&type_name, false);
+    let def = TypeDef {
+        name: type_name,
+        fields,
+        is_recursive,
+        type_params,
+    };
+    ctxt.defs[index] = Some(Definition::Type(def));
+
+    instance(generator, reference, &args)
+}
+
+/// A reference to a type used for another shape, with the type arguments of that shape
+fn with_type_args(ctxt: &mut Ctxt, reference: Ident, shape: &Shape) -> Ident {
+    let mut args = Vec::new();
+    for (key, arg) in shape::type_args(shape) {
+        let pointer = child_pointer(&ctxt.pointer, key);
+        let parent_pointer = std::mem::replace(&mut ctxt.pointer, pointer);
+        args.push(type_from_shape(ctxt, key, arg));
+        ctxt.pointer = parent_pointer;
+    }
+    instance(ctxt.generator, reference, &args)
+}
+
+fn instance(generator: &dyn Generator, reference: Ident, args: &[Ident]) -> Ident {
+    if args.is_empty() {
+        reference
+    } else {
+        generator.generic_type(&reference, args)
+    }
+}
+
+fn type_name(generator: &dyn Generator, path: &str) -> Ident {
+    let mut name = generator.type_name(path).trim().to_string();
+    if name.is_empty() {
+        name.push_str("GeneratedType");
+    }
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, 'n');
+    }
+    name
+}
+
+fn unique_name(generator: &dyn Generator, name: &str, used_names: &HashSet<String>) -> Ident {
+    let mut name = name.to_string();
+    if generator.reserved_words().contains(&&*name) {
+        name.push('_');
+    }
+    if !used_names.contains(&name) {
+        return name;
+    }
+    for n in 2.. {
+        let temp = format!("{}{}", name, n);
+        if !used_names.contains(&temp) {
+            return temp;
+        }
+    }
+    unreachable!()
+}
@@ -0,0 +1,256 @@
+use linked_hash_map::LinkedHashMap;
+use std::collections::{HashMap, HashSet};
+
+use crate::options::Options;
+use crate::shape::{self, Shape};
+use crate::to_singular::to_singular;
+use crate::util::{lower_camel_case, type_case};
+
+struct Ctxt {
+    options: Options,
+    type_names: HashSet<String>,
+    uses_json_scalar: bool,
+    created_types: Vec<(Shape, Ident)>,
+    input_type_names: HashMap<Ident, Ident>,
+}
+
+pub type Ident = String;
+pub type Code = String;
+
+pub fn graphql_types(name: &str, shape: &Shape, options: Options) -> Code {
+    let mut ctxt = Ctxt {
+        options,
+        type_names: HashSet::new(),
+        uses_json_scalar: false,
+        created_types: Vec::new(),
+        input_type_names: HashMap::new(),
+    };
+
+    if !matches!(shape, Shape::Struct { .. }) {
+        // reserve the requested name
+        ctxt.type_names.insert(name.to_string());
+    }
+
+    let (ident, code) = type_from_shape(&mut ctxt, name, shape, false);
+    let mut code = code.unwrap_or_default();
+
+    if ident.trim_end_matches('!') != name {
+        // GraphQL has no type aliases, so just document what the root type would be
+        code = format!("# {} = {}\n\n{}", name, ident, code);
+    }
+
+    if ctxt.uses_json_scalar {
+        code = format!("scalar JSON\n\n{}", code);
+    }
+
+    code
+}
+
+/// The type of a value, including the `!` for non-null values.
+///
+/// If `input` is true, the type refers to the `input` variants of object types.
+fn type_from_shape(
+    ctxt: &mut Ctxt,
+    path: &str,
+    shape: &Shape,
+    input: bool,
+) -> (Ident, Option<Code>) {
+    let (nullable_type, defs) = nullable_type_from_shape(ctxt, path, shape, input);
+    match shape {
+        Shape::Optional(_) | Shape::Nullable(_) | Shape::Null | Shape::Any | Shape::Bottom => {
+            (nullable_type, defs)
+        }
+        _ => (format!("{}!", nullable_type), defs),
+    }
+}
+
+fn nullable_type_from_shape(
+    ctxt: &mut Ctxt,
+    path: &str,
+    shape: &Shape,
+    input: bool,
+) -> (Ident, Option<Code>) {
+    use crate::shape::Shape::*;
+    match shape {
+        Null | Any | Bottom | MapT { .. } => (json_scalar(ctxt), None),
+        Bool => ("Boolean".into(), None),
+        StringT => ("String".into(), None),
+        Integer => ("Int".into(), None),
+        Floating => ("Float".into(), None),
+        Tuple(shapes, _n) => {
+            let folded = shape::fold_shapes(shapes.clone());
+            if folded == Any && shapes.iter().any(|s| s != &Any) {
+                (json_scalar(ctxt), None)
+            } else {
+                generate_list_type(ctxt, path, &folded, input)
+            }
+        }
+        VecT { elem_type: e } => generate_list_type(ctxt, path, e, input),
+        Struct { fields } => {
+            let (ident, defs) = generate_object_type(ctxt, path, fields, shape);
+            if input {
+                (ctxt.input_type_names[&ident].clone(), defs)
+            } else {
+                (ident, defs)
+            }
+        }
+        Opaque(t) => (t.clone(), None),
+        SelfRef | Recursive(_) | TypeParam(..) => {
+            unreachable!("recursion and type parameters are removed for this output")
+        }
+        Optional(e) | Nullable(e) => nullable_type_from_shape(ctxt, path, e, input),
+    }
+}
+
+fn json_scalar(ctxt: &mut Ctxt) -> Ident {
+    ctxt.uses_json_scalar = true;
+    "JSON".into()
+}
+
+fn generate_list_type(
+    ctxt: &mut Ctxt,
+    path: &str,
+    shape: &Shape,
+    input: bool,
+) -> (Ident, Option<Code>) {
+    let singular = to_singular(path);
+    let (inner, defs) = type_from_shape(ctxt, &singular, shape, input);
+    (format!("[{}]", inner), defs)
+}
+
+fn field_name(name: &str, used_names: &HashSet<String>) -> Ident {
+    if is_graphql_name(name) && !used_names.contains(name) {
+        return name.to_string();
+    }
+    type_or_field_name(name, used_names, "field", lower_camel_case)
+}
+
+fn type_name(name: &str, used_names: &HashSet<String>) -> Ident {
+    type_or_field_name(name, used_names, "GeneratedType", type_case)
+}
+
+fn type_or_field_name(
+    name: &str,
+    used_names: &HashSet<String>,
+    default_name: &str,
+    case_fn: fn(&str) -> String,
+) -> Ident {
+    let name = name.trim();
+    let mut output_name = case_fn(name);
+    if output_name.is_empty() {
+        output_name.push_str(default_name);
+    }
+    if output_name.starts_with(|c: char| c.is_ascii_digit()) {
+        output_name = String::from("n") + &output_name;
+    }
+    if !used_names.contains(&output_name) {
+        return output_name;
+    }
+    for n in 2.. {
+        let temp = format!("{}{}", output_name, n);
+        if !used_names.contains(&temp) {
+            return temp;
+        }
+    }
+    unreachable!()
+}
+
+/// Names matching `/[_A-Za-z][_0-9A-Za-z]*/`, excluding the reserved `__` prefix
+fn is_graphql_name(s: &str) -> bool {
+    if s.starts_with("__") {
+        return false;
+    }
+    match s.as_bytes().split_first() {
+        Some((first, rest)) => {
+            (first.is_ascii_alphabetic() || *first == b'_')
+                && rest.iter().all(|b| b.is_ascii_alphanumeric() || *b == b'_')
+        }
+        None => false,
+    }
+}
+
+fn generate_object_type(
+    ctxt: &mut Ctxt,
+    path: &str,
+    field_shapes: &LinkedHashMap<String, Shape>,
+    containing_shape: &Shape,
+) -> (Ident, Option<Code>) {
+    for (created_for_shape, ident) in ctxt.created_types.iter() {
+        if created_for_shape.is_acceptable_substitution_for(containing_shape) {
+            return (ident.into(), None);
+        }
+    }
+
+    let type_name = type_name(path, &ctxt.type_names);
+    ctxt.type_names.insert(type_name.clone());
+    ctxt.created_types
+        .push((containing_shape.clone(), type_name.clone()));
+
+    if ctxt.options.graphql_input_types {
+        let input_type_name = self::type_name(&format!("{}Input", type_name), &ctxt.type_names);
+        ctxt.type_names.insert(input_type_name.clone());
+        ctxt.input_type_names
+            .insert(type_name.clone(), input_type_name);
+    }
+
+    let mut field_names = HashSet::new();
+    let mut defs = Vec::new();
+    let mut fields = Vec::new();
+    let mut input_fields = Vec::new();
+
+    for (name, typ) in field_shapes.iter() {
+        let field_name = field_name(name, &field_names);
+        field_names.insert(field_name.clone());
+
+        let (field_type, child_defs) = type_from_shape(ctxt, name, typ, false);
+
+        if let Some(code) = child_defs.filter(|code| !code.is_empty()) {
+            defs.push(code);
+        }
+
+        fields.push(format!("  {}: {}", field_name, field_type));
+
+        if ctxt.options.graphql_input_types {
+            // All object types are already created at this point, so there are no new defs
+            let (input_field_type, _) = type_from_shape(ctxt, name, typ, true);
+            input_fields.push(format!("  {}: {}", field_name, input_field_type));
+        }
+    }
+
+    let mut code = object_definition("type", &type_name, &fields);
+
+    if ctxt.options.graphql_input_types {
+        code += "\n\n";
+        code += &object_definition("input", &ctxt.input_type_names[&type_name], &input_fields);
+    }
+
+    if !defs.is_empty() {
+        code += "\n\n";
+        code += &defs.join("\n\n");
+    }
+
+    (type_name, Some(code))
+}
+
+fn object_definition(keyword: &str, type_name: &str, fields: &[Code]) -> Code {
+    if fields.is_empty() {
+        // Empty field lists are not allowed, so leave out the braces
+        return format!("{} {}", keyword, type_name);
+    }
+    format!("{} {} {{\n{}\n}}", keyword, type_name, fields.join("\n"))
+}
+
+#[cfg(test)]
+mod graphql_codegen_tests {
+    use super::*;
+
+    #[test]
+    fn is_graphql_name_test() {
+        assert!(is_graphql_name("foo"));
+        assert!(is_graphql_name("_foo_Bar2"));
+        assert!(!is_graphql_name("__typename"));
+        assert!(!is_graphql_name("2foo"));
+        assert!(!is_graphql_name("foo-bar"));
+        assert!(!is_graphql_name(""));
+    }
+}
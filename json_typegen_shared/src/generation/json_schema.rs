@@ -1,25 +1,94 @@
 use linked_hash_map::LinkedHashMap;
+use std::collections::HashSet;
 
 use crate::generation::value::{Value, pretty_print_value};
-use crate::options::Options;
+use crate::observations::{Constraint, Observations, child_pointer};
+use crate::options::{JsonSchemaDraft, Options};
 use crate::shape::{self, Shape};
 use crate::to_singular::to_singular;
-use crate::util::string_hashmap;
+use crate::util::{string_hashmap, type_case};
 
-#[allow(dead_code)]
 pub struct Ctxt {
     options: Options,
+    observations: Observations,
+    type_names: HashSet<String>,
+    /// How many times each object shape is used, to decide which ones to put in the definitions
+    struct_counts: Vec<(Shape, usize)>,
+    created_defs: Vec<(Shape, String)>,
+    defs: LinkedHashMap<String, Value>,
+    /// References to the recursive schemas being generated, innermost last
+    recursive_refs: Vec<String>,
+    /// JSON pointer to the value a schema is generated for, to look up observations
+    pointer: String,
 }
 
 pub type Code = String;
 
-pub fn json_schema(name: &str, shape: &Shape, options: Options) -> Code {
-    let mut ctxt = Ctxt { options };
+pub fn json_schema(
+    name: &str,
+    shape: &Shape,
+    options: Options,
+    observations: Observations,
+) -> Code {
+    let mut ctxt = Ctxt {
+        options,
+        observations,
+        type_names: HashSet::new(),
+        struct_counts: Vec::new(),
+        created_defs: Vec::new(),
+        defs: LinkedHashMap::new(),
+        recursive_refs: Vec::new(),
+        pointer: String::new(),
+    };
+
+    // A recursive root object can refer to the schema itself
+    let shape = match shape {
+        Shape::Recursive(inner) if matches!(**inner, Shape::Struct { .. }) => {
+            let root_ref = match ctxt.options.json_schema_draft {
+                JsonSchemaDraft::OpenApi30 => format!("#/components/schemas/{}", name),
+                _ => "#".to_string(),
+            };
+            ctxt.recursive_refs.push(root_ref);
+            &**inner
+        }
+        _ => shape,
+    };
 
-    let value = type_from_shape(&mut ctxt, name, shape);
+    // The root object is the schema itself, so only the objects inside it are counted
+    match shape {
+        Shape::Struct { fields } => {
+            ctxt.type_names.insert(type_case(name));
+            for field in fields.values() {
+                count_structs(&mut ctxt.struct_counts, field);
+            }
+        }
+        _ => count_structs(&mut ctxt.struct_counts, shape),
+    }
+
+    let value = match shape {
+        Shape::Struct { fields } => generate_struct_from_field_shapes(&mut ctxt, fields),
+        _ => type_from_shape(&mut ctxt, name, shape),
+    };
+
+    if ctxt.options.json_schema_draft == JsonSchemaDraft::OpenApi30 {
+        // OpenAPI has no separate definitions, so output all schemas as components
+        let mut schemas = string_hashmap! { name => value };
+        for (def_name, def) in ctxt.defs.into_iter() {
+            schemas.insert(def_name, def);
+        }
+        let components = string_hashmap! { "schemas" => Value::Object(schemas) };
+        let document = string_hashmap! { "components" => Value::Object(components) };
+        return pretty_print_value(0, &Value::Object(document));
+    }
+
+    let schema_uri = match ctxt.options.json_schema_draft {
+        JsonSchemaDraft::Draft201909 => "https://json-schema.org/draft/2019-09/schema",
+        JsonSchemaDraft::Draft202012 => "https://json-schema.org/draft/2020-12/schema",
+        _ => "http://json-schema.org/draft-07/schema#",
+    };
 
     let mut schema = string_hashmap! {
-        "$schema" => Value::Str("http://json-schema.org/draft-07/schema#"),
+        "$schema" => Value::Str(schema_uri),
         "title" => Value::String(format!("Generated schema for {}", name)),
     };
 
@@ -29,43 +98,175 @@ pub fn json_schema(name: &str, shape: &Shape, options: Options) -> Code {
         }
     }
 
+    if !ctxt.defs.is_empty() {
+        let defs_keyword = defs_keyword(&ctxt).to_string();
+        schema.insert(defs_keyword, Value::Object(ctxt.defs));
+    }
+
     pretty_print_value(0, &Value::Object(schema))
 }
 
+fn defs_keyword(ctxt: &Ctxt) -> &'static str {
+    match ctxt.options.json_schema_draft {
+        JsonSchemaDraft::Draft07 => "definitions",
+        _ => "$defs",
+    }
+}
+
+fn count_structs(counts: &mut Vec<(Shape, usize)>, shape: &Shape) {
+    use crate::shape::Shape::*;
+    match shape {
+        Struct { fields } => {
+            for (counted_shape, count) in counts.iter_mut() {
+                if counted_shape.is_acceptable_substitution_for(shape) {
+                    // The fields are only generated once, so they are not counted again
+                    *count += 1;
+                    return;
+                }
+            }
+            counts.push((shape.clone(), 1));
+            for field in fields.values() {
+                count_structs(counts, field);
+            }
+        }
+        // counted the way the tuple is generated, so elements folded into one are counted once
+        Tuple(shapes, _) => match shape::as_generated(shape) {
+            VecT { elem_type: e } => count_structs(counts, &e),
+            _ => {
+                for shape in shapes {
+                    count_structs(counts, shape);
+                }
+            }
+        },
+        VecT { elem_type: e } => count_structs(counts, e),
+        MapT { val_type: e } => count_structs(counts, e),
+        Optional(e) | Nullable(e) | Recursive(e) => count_structs(counts, e),
+        _ => {}
+    }
+}
+
 fn type_from_shape(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> Value {
     use crate::shape::Shape::*;
     match shape {
         Null | Any | Bottom => Value::Object(LinkedHashMap::new()),
         Bool => Value::Object(string_hashmap! { "type" => Value::Str("boolean") }),
-        StringT => Value::Object(string_hashmap! { "type" => Value::Str("string") }),
-        Integer => Value::Object(string_hashmap! { "type" => Value::Str("number") }),
-        Floating => Value::Object(string_hashmap! { "type" => Value::Str("number") }),
+        StringT => with_constraints(
+            ctxt,
+            shape,
+            string_hashmap! { "type" => Value::Str("string") },
+        ),
+        Integer => with_constraints(
+            ctxt,
+            shape,
+            string_hashmap! { "type" => Value::Str("integer") },
+        ),
+        Floating => with_constraints(
+            ctxt,
+            shape,
+            string_hashmap! { "type" => Value::Str("number") },
+        ),
         Tuple(shapes, _n) => {
             let folded = shape::fold_shapes(shapes.clone());
             if folded == Any && shapes.iter().any(|s| s != &Any) {
                 generate_tuple_type(ctxt, path, shapes)
             } else {
-                generate_vec_type(ctxt, path, &folded)
+                generate_vec_type(ctxt, path, shape, &folded)
             }
         }
-        VecT { elem_type: e } => generate_vec_type(ctxt, path, e),
-        Struct { fields: map } => generate_struct_from_field_shapes(ctxt, path, map),
+        VecT { elem_type: e } => generate_vec_type(ctxt, path, shape, e),
+        Struct { fields: map } => generate_struct_type(ctxt, path, map, shape),
+        Recursive(e) => match &**e {
+            Struct { fields: map } => generate_struct_type(ctxt, path, map, shape),
+            _ => type_from_shape(ctxt, path, e),
+        },
+        TypeParam(..) => unreachable!("type parameters are removed for this output"),
+        SelfRef => match ctxt.recursive_refs.last() {
+            Some(reference) => Value::Object(string_hashmap! {
+                "$ref" => Value::String(reference.clone())
+            }),
+            None => Value::Object(LinkedHashMap::new()),
+        },
         MapT { val_type: v } => generate_map_type(ctxt, path, v),
-        Opaque(t) => Value::Object(string_hashmap! { "type" => Value::String(t.clone()) }),
+        // There is no way to refer to a type by name, so just name the schema
+        Opaque(t) => Value::Object(string_hashmap! { "title" => Value::String(t.clone()) }),
         Optional(e) => type_from_shape(ctxt, path, e),
-        Nullable(e) => type_from_shape(ctxt, path, e),
+        Nullable(e) => {
+            let inner = type_from_shape(ctxt, path, e);
+            make_nullable(ctxt, inner)
+        }
     }
 }
 
-fn generate_vec_type(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> Value {
-    let singular = to_singular(path);
-    let inner = type_from_shape(ctxt, &singular, shape);
+fn make_nullable(ctxt: &Ctxt, value: Value) -> Value {
+    let mut map = match value {
+        // An empty schema already allows null
+        Value::Object(map) if map.is_empty() => return Value::Object(map),
+        Value::Object(map) => map,
+        other => return other,
+    };
+
+    if ctxt.options.json_schema_draft == JsonSchemaDraft::OpenApi30 {
+        if map.contains_key("$ref") {
+            // Siblings of $ref are ignored in OpenAPI 3.0
+            return Value::Object(string_hashmap! {
+                "allOf" => Value::Array(vec![Value::Object(map)]),
+                "nullable" => Value::Bool(true),
+            });
+        }
+        map.insert("nullable".into(), Value::Bool(true));
+        return Value::Object(map);
+    }
+
+    if let Some(typ) = map.get_mut("type") {
+        if let Value::Str(t) = typ {
+            *typ = Value::Array(vec![Value::Str(t), Value::Str("null")]);
+        }
+        return Value::Object(map);
+    }
+
     Value::Object(string_hashmap! {
-        "type" => Value::Str("array"),
-        "items" => inner
+        "anyOf" => Value::Array(vec![
+            Value::Object(map),
+            Value::Object(string_hashmap! { "type" => Value::Str("null") }),
+        ]),
     })
 }
 
+/// Add the constraints that held for all the samples to a schema, if asked for
+fn with_constraints(ctxt: &Ctxt, shape: &Shape, mut map: LinkedHashMap<String, Value>) -> Value {
+    if !ctxt.options.infer_constraints {
+        return Value::Object(map);
+    }
+    for constraint in ctxt.observations.constraints(&ctxt.pointer, shape) {
+        let (keyword, value) = match constraint {
+            Constraint::MinLength(n) => ("minLength", Value::Number(n.to_string())),
+            Constraint::MaxLength(n) => ("maxLength", Value::Number(n.to_string())),
+            Constraint::Minimum(n) => ("minimum", Value::Number(n)),
+            Constraint::Maximum(n) => ("maximum", Value::Number(n)),
+            Constraint::NonEmpty => ("minItems", Value::Number("1".to_string())),
+            Constraint::Format(format) => ("format", Value::Str(format.json_schema_name())),
+        };
+        map.insert(keyword.to_string(), value);
+    }
+    Value::Object(map)
+}
+
+fn generate_vec_type(ctxt: &mut Ctxt, path: &str, shape: &Shape, elem_shape: &Shape) -> Value {
+    let singular = to_singular(path);
+    let pointer = child_pointer(&ctxt.pointer, "-");
+    let parent_pointer = std::mem::replace(&mut ctxt.pointer, pointer);
+    let inner = type_from_shape(ctxt, &singular, elem_shape);
+    ctxt.pointer = parent_pointer;
+    with_constraints(
+        ctxt,
+        shape,
+        string_hashmap! {
+            "type" => Value::Str("array"),
+            "items" => inner
+        },
+    )
+}
+
 fn generate_map_type(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> Value {
     let singular = to_singular(path);
     let inner = type_from_shape(ctxt, &singular, shape);
@@ -78,16 +279,31 @@ fn generate_map_type(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> Value {
 fn generate_tuple_type(ctxt: &mut Ctxt, path: &str, shapes: &[Shape]) -> Value {
     let mut types = Vec::new();
 
+    let pointer = child_pointer(&ctxt.pointer, "-");
+    let parent_pointer = std::mem::replace(&mut ctxt.pointer, pointer);
     for shape in shapes {
         let typ = type_from_shape(ctxt, path, shape);
         types.push(typ);
     }
+    ctxt.pointer = parent_pointer;
 
-    Value::Object(string_hashmap! {
-        "type" => Value::Str("array"),
-        "items" => Value::Array(types),
-        "additionalItems" => Value::Bool(false)
-    })
+    match ctxt.options.json_schema_draft {
+        JsonSchemaDraft::Draft202012 => Value::Object(string_hashmap! {
+            "type" => Value::Str("array"),
+            "prefixItems" => Value::Array(types),
+            "items" => Value::Bool(false)
+        }),
+        // OpenAPI 3.0 has no tuples, so allow any of the types at any position
+        JsonSchemaDraft::OpenApi30 => Value::Object(string_hashmap! {
+            "type" => Value::Str("array"),
+            "items" => Value::Object(string_hashmap! { "anyOf" => Value::Array(types) })
+        }),
+        _ => Value::Object(string_hashmap! {
+            "type" => Value::Str("array"),
+            "items" => Value::Array(types),
+            "additionalItems" => Value::Bool(false)
+        }),
+    }
 }
 
 fn collapse_option(typ: &Shape) -> (bool, &Shape) {
@@ -97,11 +313,71 @@ fn collapse_option(typ: &Shape) -> (bool, &Shape) {
     (false, typ)
 }
 
-fn generate_struct_from_field_shapes(
+/// Objects used more than once, or referring to themselves,
+/// are put in the definitions and referred to with `$ref`
+fn generate_struct_type(
     ctxt: &mut Ctxt,
-    _path: &str,
+    path: &str,
     map: &LinkedHashMap<String, Shape>,
+    containing_shape: &Shape,
 ) -> Value {
+    let is_recursive = matches!(containing_shape, Shape::Recursive(_));
+    let is_repeated = ctxt.struct_counts.iter().any(|(counted_shape, count)| {
+        *count > 1 && counted_shape.is_acceptable_substitution_for(containing_shape)
+    });
+    if !is_repeated && !is_recursive {
+        return generate_struct_from_field_shapes(ctxt, map);
+    }
+
+    let ref_prefix = match ctxt.options.json_schema_draft {
+        JsonSchemaDraft::OpenApi30 => "#/components/schemas".to_string(),
+        _ => format!("#/{}", defs_keyword(ctxt)),
+    };
+
+    let def_name = match ctxt.created_defs.iter().find(|(created_for_shape, _)| {
+        created_for_shape.is_acceptable_substitution_for(containing_shape)
+    }) {
+        Some((_, def_name)) => def_name.clone(),
+        None => {
+            let def_name = type_name(path, &ctxt.type_names);
+            ctxt.type_names.insert(def_name.clone());
+            ctxt.created_defs
+                .push((containing_shape.clone(), def_name.clone()));
+            if is_recursive {
+                ctxt.recursive_refs
+                    .push(format!("{}/{}", ref_prefix, def_name));
+            }
+            let def = generate_struct_from_field_shapes(ctxt, map);
+            if is_recursive {
+                ctxt.recursive_refs.pop();
+            }
+            ctxt.defs.insert(def_name.clone(), def);
+            def_name
+        }
+    };
+    Value::Object(string_hashmap! {
+        "$ref" => Value::String(format!("{}/{}", ref_prefix, def_name))
+    })
+}
+
+fn type_name(name: &str, used_names: &HashSet<String>) -> String {
+    let mut output_name = type_case(name.trim());
+    if output_name.is_empty() {
+        output_name.push_str("GeneratedType");
+    }
+    if !used_names.contains(&output_name) {
+        return output_name;
+    }
+    for n in 2.. {
+        let temp = format!("{}{}", output_name, n);
+        if !used_names.contains(&temp) {
+            return temp;
+        }
+    }
+    unreachable!()
+}
+
+fn generate_struct_from_field_shapes(ctxt: &mut Ctxt, map: &LinkedHashMap<String, Shape>) -> Value {
     let mut required: Vec<Value> = Vec::new();
     let mut properties = LinkedHashMap::new();
 
@@ -112,14 +388,25 @@ fn generate_struct_from_field_shapes(
             required.push(Value::String(name.clone()));
         }
 
+        let pointer = child_pointer(&ctxt.pointer, name);
+        let parent_pointer = std::mem::replace(&mut ctxt.pointer, pointer);
         let field_code = type_from_shape(ctxt, name, collapsed);
+        ctxt.pointer = parent_pointer;
 
         properties.insert(name.to_string(), field_code);
     }
 
-    Value::Object(string_hashmap! {
+    let mut object = string_hashmap! {
         "type" => Value::Str("object"),
-        "properties" => Value::Object(properties),
-        "required" => Value::Array(required)
-    })
+        "properties" => Value::Object(properties)
+    };
+    // an empty list is not allowed by OpenAPI 3.0 and draft-04
+    if !required.is_empty() {
+        object.insert("required".into(), Value::Array(required));
+    }
+    if ctxt.options.deny_unknown_fields {
+        object.insert("additionalProperties".into(), Value::Bool(false));
+    }
+
+    Value::Object(object)
 }
@@ -2,6 +2,8 @@ use linked_hash_map::LinkedHashMap;
 use std::collections::HashSet;
 
 use crate::OutputMode;
+use crate::generation::Root;
+use crate::observations::{Observations, block_doc_comment, child_pointer};
 use crate::options::{ImportStyle, Options, StringTransform};
 use crate::shape::{self, Shape};
 use crate::to_singular::to_singular;
@@ -9,33 +11,57 @@ use crate::util::{kebab_case, lower_camel_case, snake_case, type_case};
 
 struct Ctxt {
     options: Options,
+    observations: Observations,
     type_names: HashSet<String>,
     imports: HashSet<String>,
     created_classes: Vec<(Shape, Ident)>,
+    /// Names of the recursive types being generated, innermost last
+    recursive_types: Vec<Ident>,
+    /// JSON pointer to the value a type is generated for, to look up observations
+    pointer: String,
+    /// Type parameters of the class being generated, with their type arguments
+    type_params: Option<Vec<(Ident, Ident)>>,
 }
 
 pub type Ident = String;
 pub type Code = String;
 
-pub fn kotlin_types(name: &str, shape: &Shape, options: Options) -> Code {
+pub fn kotlin_types(
+    name: &str,
+    shape: &Shape,
+    options: Options,
+    observations: Observations,
+) -> Code {
+    kotlin_types_for_roots(&[Root::single(name, shape)], options, observations)
+}
+
+pub fn kotlin_types_for_roots(
+    roots: &[Root],
+    options: Options,
+    observations: Observations,
+) -> Code {
     let mut ctxt = Ctxt {
         options,
+        observations,
         type_names: HashSet::new(),
         imports: HashSet::new(),
         created_classes: Vec::new(),
+        recursive_types: Vec::new(),
+        pointer: String::new(),
+        type_params: None,
     };
 
-    if !matches!(shape, Shape::Struct { .. }) {
-        // reserve the requested name
-        ctxt.type_names.insert(name.to_string());
+    // reserve the requested names, so that no other types can take them
+    for root in roots {
+        ctxt.type_names.insert(root.name.to_string());
     }
 
-    let (ident, code) = type_from_shape(&mut ctxt, name, shape);
-    let mut code = code.unwrap_or_default();
-
-    if ident != name {
-        code = format!("typealias {} = {};\n\n", name, ident) + &code;
-    }
+    let mut code = roots
+        .iter()
+        .map(|root| root_type(&mut ctxt, root))
+        .filter(|code| !code.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
 
     if !ctxt.imports.is_empty() {
         let mut imports: Vec<_> = ctxt.imports.drain().collect();
@@ -53,6 +79,26 @@ pub fn kotlin_types(name: &str, shape: &Shape, options: Options) -> Code {
     code
 }
 
+/// The types for a root, with an alias if its type did not get the requested name
+fn root_type(ctxt: &mut Ctxt, root: &Root) -> Code {
+    let name = root.name;
+    if matches!(root.shape, Shape::Struct { .. } | Shape::Recursive(_)) {
+        // let the class itself have the requested name
+        ctxt.type_names.remove(name);
+    }
+
+    ctxt.pointer = root.pointer.clone();
+    let (ident, code) = type_from_shape(ctxt, name, root.shape);
+    let mut code = code.unwrap_or_default();
+    ctxt.type_names.insert(name.to_string());
+
+    if ident != name && !ident.starts_with(&format!("{}<", name)) {
+        code = format!("typealias {} = {};\n\n", name, ident) + &code;
+    }
+
+    code
+}
+
 fn type_from_shape(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> (Ident, Option<Code>) {
     use crate::shape::Shape::*;
     match shape {
@@ -71,6 +117,27 @@ fn type_from_shape(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> (Ident, Option
         }
         VecT { elem_type: e } => generate_vec_type(ctxt, path, e),
         Struct { fields } => generate_data_class(ctxt, path, fields, shape),
+        Recursive(e) => match &**e {
+            Struct { fields } => generate_data_class(ctxt, path, fields, shape),
+            _ => type_from_shape(ctxt, path, e),
+        },
+        SelfRef => match ctxt.recursive_types.last() {
+            Some(ident) => (ident.clone(), None),
+            None => ("Any?".into(), None),
+        },
+        TypeParam(name, arg) => {
+            let (arg_ident, arg_code) = type_from_shape(ctxt, path, arg);
+            match ctxt.type_params {
+                Some(ref mut type_params) => {
+                    if !type_params.iter().any(|(param, _)| param == name) {
+                        type_params.push((name.clone(), arg_ident));
+                    }
+                    (name.clone(), arg_code)
+                }
+                // Outside of any class there is nothing to be generic
+                None => (arg_ident, arg_code),
+            }
+        }
         MapT { val_type: v } => generate_map_type(ctxt, path, v),
         Opaque(t) => (t.clone(), None),
         Optional(e) => {
@@ -94,7 +161,10 @@ fn type_from_shape(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> (Ident, Option
 
 fn generate_vec_type(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> (Ident, Option<Code>) {
     let singular = to_singular(path);
+    let pointer = child_pointer(&ctxt.pointer, "-");
+    let parent_pointer = std::mem::replace(&mut ctxt.pointer, pointer);
     let (inner, defs) = type_from_shape(ctxt, &singular, shape);
+    ctxt.pointer = parent_pointer;
     (format!("List<{}>", inner), defs)
 }
 
@@ -108,6 +178,8 @@ fn generate_tuple_type(ctxt: &mut Ctxt, path: &str, shapes: &[Shape]) -> (Ident,
     let mut types = Vec::new();
     let mut defs = Vec::new();
 
+    let pointer = child_pointer(&ctxt.pointer, "-");
+    let parent_pointer = std::mem::replace(&mut ctxt.pointer, pointer);
     for shape in shapes {
         let (typ, def) = type_from_shape(ctxt, path, shape);
         types.push(typ);
@@ -115,6 +187,7 @@ fn generate_tuple_type(ctxt: &mut Ctxt, path: &str, shapes: &[Shape]) -> (Ident,
             defs.push(code)
         }
     }
+    ctxt.pointer = parent_pointer;
 
     (
         format!(
@@ -189,6 +262,28 @@ fn import(ctxt: &mut Ctxt, qualified: &str) -> String {
     }
 }
 
+/// A generic class used for another shape, with the type arguments of that shape
+fn with_type_args(ctxt: &mut Ctxt, ident: &str, shape: &Shape) -> (Ident, Option<Code>) {
+    let type_args = shape::type_args(shape);
+    if type_args.is_empty() {
+        return (ident.to_string(), None);
+    }
+    let name = ident.split('<').next().unwrap();
+    let mut args = Vec::new();
+    let mut defs = Vec::new();
+    for (key, arg) in type_args {
+        let (arg, def) = type_from_shape(ctxt, key, arg);
+        args.push(arg);
+        defs.extend(def);
+    }
+    let code = if defs.is_empty() {
+        None
+    } else {
+        Some(defs.join("\n\n"))
+    };
+    (format!("{}<{}>", name, args.join(", ")), code)
+}
+
 fn generate_data_class(
     ctxt: &mut Ctxt,
     path: &str,
@@ -201,10 +296,11 @@ fn generate_data_class(
         return ("Map<String, Any>".into(), None);
     }
 
-    for (created_for_shape, ident) in ctxt.created_classes.iter() {
-        if created_for_shape.is_acceptable_substitution_for(containing_shape) {
-            return (ident.into(), None);
-        }
+    if let Some((_, ident)) = ctxt.created_classes.iter().find(|(created_for_shape, _)| {
+        created_for_shape.is_acceptable_substitution_for(containing_shape)
+    }) {
+        let ident = ident.clone();
+        return with_type_args(ctxt, &ident, containing_shape);
     }
 
     let type_name = type_name(path, &ctxt.type_names);
@@ -212,8 +308,14 @@ fn generate_data_class(
     ctxt.created_classes
         .push((containing_shape.clone(), type_name.clone()));
 
+    let is_recursive = matches!(containing_shape, Shape::Recursive(_));
+    if is_recursive {
+        ctxt.recursive_types.push(type_name.clone());
+    }
+
     let mut field_names = HashSet::new();
     let mut defs = Vec::new();
+    let outer_type_params = ctxt.type_params.replace(Vec::new());
 
     let fields: Vec<Code> = field_shapes
         .iter()
@@ -221,24 +323,15 @@ fn generate_data_class(
             let field_name = field_name(name, &field_names);
             field_names.insert(field_name.clone());
 
-            let mut field_code = String::new();
+            let pointer = child_pointer(&ctxt.pointer, name);
+            let mut field_code = block_doc_comment(&ctxt.observations.doc_lines(&pointer), "    ");
             if &apply_transform(ctxt, &field_name) != name {
-                if ctxt.options.output_mode == OutputMode::KotlinJackson {
-                    field_code += &format!(
-                        "    @{}(\"{}\")\n",
-                        import(ctxt, "com.fasterxml.jackson.annotation.JsonProperty"),
-                        name
-                    )
-                } else if ctxt.options.output_mode == OutputMode::KotlinKotlinx {
-                    field_code += &format!(
-                        "    @{}(\"{}\")\n",
-                        import(ctxt, "kotlinx.serialization.SerialName"),
-                        name
-                    )
-                }
+                field_code += &rename_annotation(ctxt, name);
             }
 
+            let parent_pointer = std::mem::replace(&mut ctxt.pointer, pointer);
             let (field_type, child_defs) = type_from_shape(ctxt, name, typ);
+            ctxt.pointer = parent_pointer;
 
             if let Some(code) = child_defs {
                 defs.push(code);
@@ -248,20 +341,48 @@ fn generate_data_class(
         })
         .collect();
 
+    if is_recursive {
+        ctxt.recursive_types.pop();
+    }
+    let type_params = std::mem::replace(&mut ctxt.type_params, outer_type_params);
+
+    // Other types refer to a generic class with the type arguments filled in
+    let (declared_name, type_name) = match type_params {
+        Some(type_params) if !type_params.is_empty() => {
+            let (params, args): (Vec<Ident>, Vec<Ident>) = type_params.into_iter().unzip();
+            let instance = format!("{}<{}>", type_name, args.join(", "));
+            if let Some(created) = ctxt
+                .created_classes
+                .iter_mut()
+                .find(|(_, ident)| *ident == type_name)
+            {
+                created.1 = instance.clone();
+            }
+            (format!("{}<{}>", type_name, params.join(", ")), instance)
+        }
+        _ => (type_name.clone(), type_name),
+    };
+
     let mut code = String::new();
 
     code += &transform_annotation(ctxt);
 
     if ctxt.options.output_mode == OutputMode::KotlinKotlinx {
         code += &format!("@{}\n", import(ctxt, "kotlinx.serialization.Serializable"));
+    } else if ctxt.options.output_mode == OutputMode::KotlinMoshi {
+        code += &format!(
+            "@{}(generateAdapter = true)\n",
+            import(ctxt, "com.squareup.moshi.JsonClass")
+        );
     }
-    code += &format!("data class {}(\n", type_name);
+    code += &format!("data class {}(\n", declared_name);
 
     if !fields.is_empty() {
         code += &fields.join(",\n");
         code += ",\n";
     }
-    if ctxt.options.collect_additional {
+    // Only Jackson has a way to collect unknown properties without a custom adapter/serializer
+    if ctxt.options.collect_additional && ctxt.options.output_mode == OutputMode::KotlinJackson {
         code += &format!(
             "    @{}\n    @get:{}\n    val additionalFields: Map<String, Any> = mutableMapOf(),\n",
             import(ctxt, "com.fasterxml.jackson.annotation.JsonAnySetter"),
@@ -278,6 +399,31 @@ fn generate_data_class(
     (type_name, Some(code))
 }
 
+fn rename_annotation(ctxt: &mut Ctxt, name: &str) -> String {
+    match ctxt.options.output_mode {
+        OutputMode::KotlinKotlinx => format!(
+            "    @{}(\"{}\")\n",
+            import(ctxt, "kotlinx.serialization.SerialName"),
+            name
+        ),
+        OutputMode::KotlinMoshi => format!(
+            "    @{}(name = \"{}\")\n",
+            import(ctxt, "com.squareup.moshi.Json"),
+            name
+        ),
+        OutputMode::KotlinGson => format!(
+            "    @{}(\"{}\")\n",
+            import(ctxt, "com.google.gson.annotations.SerializedName"),
+            name
+        ),
+        _ => format!(
+            "    @{}(\"{}\")\n",
+            import(ctxt, "com.fasterxml.jackson.annotation.JsonProperty"),
+            name
+        ),
+    }
+}
+
 fn apply_transform(ctxt: &Ctxt, field_name: &str) -> String {
     match (
         &ctxt.options.property_name_format,
@@ -0,0 +1,293 @@
+use linked_hash_map::LinkedHashMap;
+
+use crate::generation::value::{Value, pretty_print_value};
+use crate::observations::{Observations, PointerObservations, StringFormat, child_pointer};
+use crate::options::Options;
+use crate::shape::{Shape, as_generated};
+
+/// How many levels of a recursive type to generate
+const MAX_DEPTH: usize = 3;
+
+const WORDS: &[&str] = &[
+    "lorem",
+    "ipsum",
+    "dolor",
+    "sit",
+    "amet",
+    "consectetur",
+    "adipiscing",
+    "elit",
+    "sed",
+    "do",
+    "eiusmod",
+    "tempor",
+    "incididunt",
+    "ut",
+    "labore",
+    "magna",
+];
+
+pub struct Ctxt {
+    options: Options,
+    observations: Observations,
+    rng: Rng,
+    pointer: String,
+    /// The shapes `SelfRef` refers to, innermost last
+    recursive: Vec<Shape>,
+    depth: usize,
+}
+
+pub type Code = String;
+
+pub fn mock_data(_name: &str, shape: &Shape, options: Options, observations: Observations) -> Code {
+    let mut ctxt = Ctxt {
+        rng: Rng(options.mock_seed),
+        options,
+        observations,
+        pointer: String::new(),
+        recursive: Vec::new(),
+        depth: 0,
+    };
+
+    let value = if ctxt.options.mock_count == 1 {
+        value_from_shape(&mut ctxt, shape)
+    } else {
+        Value::Array(
+            (0..ctxt.options.mock_count)
+                .map(|_| value_from_shape(&mut ctxt, shape))
+                .collect(),
+        )
+    };
+
+    pretty_print_value(0, &value)
+}
+
+fn value_from_shape(ctxt: &mut Ctxt, shape: &Shape) -> Value {
+    use crate::shape::Shape::*;
+    match shape {
+        Null | Any | Bottom | Opaque(_) => Value::Null,
+        Optional(e) | TypeParam(_, e) => value_from_shape(ctxt, e),
+        Nullable(e) => {
+            if ctxt.rng.chance(ctxt.options.mock_fill_optional) {
+                value_from_shape(ctxt, e)
+            } else {
+                Value::Null
+            }
+        }
+        Bool => example(ctxt).unwrap_or(Value::Bool(ctxt.rng.chance(0.5))),
+        StringT => example(ctxt).unwrap_or_else(|| generate_string(ctxt)),
+        Integer => example(ctxt).unwrap_or_else(|| generate_number(ctxt, false)),
+        Floating => example(ctxt).unwrap_or_else(|| generate_number(ctxt, true)),
+        Tuple(shapes, _) => match as_generated(shape) {
+            VecT { elem_type } => generate_array(ctxt, &elem_type),
+            _ => {
+                let pointer = child_pointer(&ctxt.pointer, "-");
+                let parent_pointer = std::mem::replace(&mut ctxt.pointer, pointer);
+                let values = shapes.iter().map(|s| value_from_shape(ctxt, s)).collect();
+                ctxt.pointer = parent_pointer;
+                Value::Array(values)
+            }
+        },
+        VecT { elem_type } => generate_array(ctxt, elem_type),
+        Struct { fields } => generate_object(ctxt, fields),
+        MapT { val_type } => generate_map(ctxt, val_type),
+        Recursive(e) => {
+            ctxt.recursive.push((**e).clone());
+            let value = value_from_shape(ctxt, e);
+            ctxt.recursive.pop();
+            value
+        }
+        SelfRef => match ctxt.recursive.last().cloned() {
+            Some(e) if ctxt.depth < MAX_DEPTH => {
+                ctxt.depth += 1;
+                let value = value_from_shape(ctxt, &e);
+                ctxt.depth -= 1;
+                value
+            }
+            _ => Value::Null,
+        },
+    }
+}
+
+/// Whether the shape is a reference to a recursive type that is already deep enough
+fn is_exhausted(ctxt: &Ctxt, shape: &Shape) -> bool {
+    matches!(crate::shape::strip_option(shape), Shape::SelfRef) && ctxt.depth >= MAX_DEPTH
+}
+
+fn observed(ctxt: &Ctxt) -> Option<&PointerObservations> {
+    ctxt.observations.get(&ctxt.pointer)
+}
+
+/// One of the values seen at the current pointer, if any were recorded.
+/// Only some of the time if there were more distinct values than the recorded examples.
+fn example(ctxt: &mut Ctxt) -> Option<Value> {
+    let observed = observed(ctxt)?.clone();
+    if !observed.has_all_values() && ctxt.rng.chance(0.5) {
+        return None;
+    }
+    let examples: Vec<String> = observed
+        .examples
+        .iter()
+        // long strings are cut short in examples
+        .filter(|example| !example.ends_with("...\""))
+        .cloned()
+        .collect();
+    if examples.is_empty() {
+        return None;
+    }
+    let i = ctxt.rng.below(examples.len());
+    Some(Value::Literal(examples[i].clone()))
+}
+
+fn generate_string(ctxt: &mut Ctxt) -> Value {
+    let bounds = observed(ctxt).map(|observed| observed.bounds.clone());
+    let n = ctxt.rng.below(10_000);
+    let format = bounds.as_ref().and_then(|bounds| bounds.format);
+    let s = match format {
+        Some(StringFormat::Email) => format!("{}{}@example.com", word(ctxt), n),
+        Some(StringFormat::Url) => format!("https://example.com/{}/{}", word(ctxt), n),
+        Some(StringFormat::Uuid) => {
+            let a = ctxt.rng.next_u64();
+            let b = ctxt.rng.next_u64();
+            format!(
+                "{:08x}-{:04x}-4{:03x}-a{:03x}-{:012x}",
+                a >> 32,
+                (a >> 16) & 0xffff,
+                a & 0xfff,
+                b >> 52,
+                b & 0xffff_ffff_ffff
+            )
+        }
+        Some(StringFormat::Date) => date(ctxt),
+        Some(StringFormat::DateTime) => {
+            let date = date(ctxt);
+            let seconds = ctxt.rng.below(24 * 60 * 60);
+            format!(
+                "{}T{:02}:{:02}:{:02}Z",
+                date,
+                seconds / 3600,
+                seconds / 60 % 60,
+                seconds % 60
+            )
+        }
+        None => {
+            let (min, max) = bounds.and_then(|bounds| bounds.length).unwrap_or((1, 24));
+            let mut s = word(ctxt).to_string();
+            while s.chars().count() < min {
+                s = s + " " + word(ctxt);
+            }
+            s.chars().take(max).collect()
+        }
+    };
+    Value::String(s)
+}
+
+fn word(ctxt: &mut Ctxt) -> &'static str {
+    WORDS[ctxt.rng.below(WORDS.len())]
+}
+
+fn date(ctxt: &mut Ctxt) -> String {
+    format!(
+        "20{:02}-{:02}-{:02}",
+        ctxt.rng.range(15, 25),
+        ctxt.rng.range(1, 12),
+        ctxt.rng.range(1, 28)
+    )
+}
+
+fn generate_number(ctxt: &mut Ctxt, floating: bool) -> Value {
+    let (min, max) = match observed(ctxt).and_then(|observed| observed.bounds.range.as_ref()) {
+        Some(((min, _), (max, _))) => (*min, *max),
+        None => (0.0, 100.0),
+    };
+    if floating {
+        let n = min + ctxt.rng.fraction() * (max - min);
+        Value::Literal(format!("{:.2}", n))
+    } else {
+        // in i128, since the span of two i64 values does not always fit in an i64
+        let (min, max) = (min.ceil() as i64 as i128, max.floor() as i64 as i128);
+        let span = (max - min + 1).clamp(1, u64::MAX as i128) as u64;
+        let n = min + (ctxt.rng.next_u64() % span) as i128;
+        Value::Literal(n.to_string())
+    }
+}
+
+fn array_length(ctxt: &mut Ctxt) -> usize {
+    let (min, max) = ctxt.options.mock_array_length;
+    ctxt.rng.range(min, max)
+}
+
+fn generate_array(ctxt: &mut Ctxt, elem_type: &Shape) -> Value {
+    // there is nothing to generate for the elements of arrays that were always empty
+    let len = if *elem_type == Shape::Bottom || is_exhausted(ctxt, elem_type) {
+        0
+    } else {
+        array_length(ctxt)
+    };
+
+    let pointer = child_pointer(&ctxt.pointer, "-");
+    let parent_pointer = std::mem::replace(&mut ctxt.pointer, pointer);
+    let values = (0..len)
+        .map(|_| value_from_shape(ctxt, elem_type))
+        .collect();
+    ctxt.pointer = parent_pointer;
+    Value::Array(values)
+}
+
+fn generate_map(ctxt: &mut Ctxt, val_type: &Shape) -> Value {
+    let len = array_length(ctxt);
+    let mut map = LinkedHashMap::new();
+    for i in 1..=len {
+        map.insert(format!("key{}", i), value_from_shape(ctxt, val_type));
+    }
+    Value::Object(map)
+}
+
+fn generate_object(ctxt: &mut Ctxt, fields: &LinkedHashMap<String, Shape>) -> Value {
+    let mut map = LinkedHashMap::new();
+    for (name, typ) in fields.iter() {
+        if let Shape::Optional(_) = typ
+            && (is_exhausted(ctxt, typ) || !ctxt.rng.chance(ctxt.options.mock_fill_optional))
+        {
+            continue;
+        }
+        let pointer = child_pointer(&ctxt.pointer, name);
+        let parent_pointer = std::mem::replace(&mut ctxt.pointer, pointer);
+        let value = value_from_shape(ctxt, typ);
+        ctxt.pointer = parent_pointer;
+        map.insert(name.clone(), value);
+    }
+    Value::Object(map)
+}
+
+/// A small, seedable random number generator (SplitMix64), so that output is reproducible
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`, for `n > 0`
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// A number in `min..=max`
+    fn range(&mut self, min: usize, max: usize) -> usize {
+        min + self.below(max.saturating_sub(min) + 1)
+    }
+
+    /// A number in `0.0..1.0`
+    fn fraction(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn chance(&mut self, probability: f64) -> bool {
+        self.fraction() < probability
+    }
+}
@@ -0,0 +1,258 @@
+use linked_hash_map::LinkedHashMap;
+use std::collections::HashSet;
+
+use crate::shape::{self, Shape};
+use crate::to_singular::to_singular;
+use crate::util::{snake_case, type_case};
+
+struct Ctxt {
+    type_names: HashSet<String>,
+    imports: HashSet<&'static str>,
+    created_messages: Vec<(Shape, Ident)>,
+}
+
+pub type Ident = String;
+pub type Code = String;
+
+const STRUCT_PROTO: &str = "google/protobuf/struct.proto";
+const WRAPPERS_PROTO: &str = "google/protobuf/wrappers.proto";
+
+pub fn protobuf_messages(name: &str, shape: &Shape) -> Code {
+    let mut ctxt = Ctxt {
+        type_names: HashSet::new(),
+        imports: HashSet::new(),
+        created_messages: Vec::new(),
+    };
+
+    let code = if let Shape::Struct { fields } = shape {
+        let (_ident, code) = generate_message(&mut ctxt, name, fields, shape);
+        code.unwrap_or_default()
+    } else {
+        // Only messages can be top level in proto3, so other shapes are wrapped in a message
+        ctxt.type_names.insert(name.to_string());
+        let (field_type, defs) = field_type_from_shape(&mut ctxt, name, shape);
+        let mut code = format!("message {} {{\n  {} value = 1;\n}}", name, field_type);
+        if let Some(defs) = defs.filter(|defs| !defs.is_empty()) {
+            code += "\n\n";
+            code += &defs;
+        }
+        code
+    };
+
+    let mut header = "syntax = \"proto3\";\n\n".to_string();
+    if !ctxt.imports.is_empty() {
+        let mut imports: Vec<_> = ctxt.imports.drain().collect();
+        imports.sort();
+        for import in imports {
+            header += &format!("import \"{}\";\n", import);
+        }
+        header += "\n";
+    }
+
+    header + &code
+}
+
+/// The type of a field, including any `repeated`/`optional` label or `map<...>` wrapper
+fn field_type_from_shape(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> (Code, Option<Code>) {
+    use crate::shape::Shape::*;
+    match shape {
+        Optional(e) | Nullable(e) => {
+            let (inner_type, defs) = field_type_from_shape(ctxt, path, e);
+            if inner_type.starts_with("repeated ")
+                || inner_type.starts_with("map<")
+                || inner_type.starts_with("optional ")
+            {
+                // repeated and map fields can not be optional, but are empty when missing
+                (inner_type, defs)
+            } else {
+                (format!("optional {}", inner_type), defs)
+            }
+        }
+        VecT { elem_type: e } => {
+            let (inner, defs) = element_type_from_shape(ctxt, &to_singular(path), e);
+            (format!("repeated {}", inner), defs)
+        }
+        Tuple(shapes, _n) => {
+            let folded = shape::fold_shapes(shapes.clone());
+            if folded == Any && shapes.iter().any(|s| s != &Any) {
+                type_from_shape(ctxt, path, shape)
+            } else {
+                let (inner, defs) = element_type_from_shape(ctxt, &to_singular(path), &folded);
+                (format!("repeated {}", inner), defs)
+            }
+        }
+        MapT { val_type: v } => {
+            let (inner, defs) = element_type_from_shape(ctxt, &to_singular(path), v);
+            (format!("map<string, {}>", inner), defs)
+        }
+        _ => type_from_shape(ctxt, path, shape),
+    }
+}
+
+/// The type of an element in a `repeated` field or a value in a `map`,
+/// where labels are not allowed and null values need a wrapper type
+fn element_type_from_shape(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> (Ident, Option<Code>) {
+    use crate::shape::Shape::*;
+    match shape {
+        Optional(e) | Nullable(e) => match **e {
+            Bool => (wrapper(ctxt, "BoolValue"), None),
+            StringT => (wrapper(ctxt, "StringValue"), None),
+            Integer => (wrapper(ctxt, "Int64Value"), None),
+            Floating => (wrapper(ctxt, "DoubleValue"), None),
+            _ => element_type_from_shape(ctxt, path, e),
+        },
+        _ => type_from_shape(ctxt, path, shape),
+    }
+}
+
+fn type_from_shape(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> (Ident, Option<Code>) {
+    use crate::shape::Shape::*;
+    match shape {
+        Null | Any | Bottom => (well_known(ctxt, "Value"), None),
+        Bool => ("bool".into(), None),
+        StringT => ("string".into(), None),
+        Integer => ("int64".into(), None),
+        Floating => ("double".into(), None),
+        // Lists and maps can not be nested directly, so fall back to the dynamic JSON types
+        VecT { .. } | Tuple(..) => (well_known(ctxt, "ListValue"), None),
+        MapT { .. } => (well_known(ctxt, "Struct"), None),
+        Struct { fields } => generate_message(ctxt, path, fields, shape),
+        Opaque(t) => (t.clone(), None),
+        SelfRef | Recursive(_) | TypeParam(..) => {
+            unreachable!("recursion and type parameters are removed for this output")
+        }
+        Optional(e) | Nullable(e) => type_from_shape(ctxt, path, e),
+    }
+}
+
+fn well_known(ctxt: &mut Ctxt, name: &str) -> Ident {
+    ctxt.imports.insert(STRUCT_PROTO);
+    format!("google.protobuf.{}", name)
+}
+
+fn wrapper(ctxt: &mut Ctxt, name: &str) -> Ident {
+    ctxt.imports.insert(WRAPPERS_PROTO);
+    format!("google.protobuf.{}", name)
+}
+
+fn field_name(name: &str, used_names: &HashSet<String>) -> Ident {
+    type_or_field_name(name, used_names, "field", snake_case)
+}
+
+fn type_name(name: &str, used_names: &HashSet<String>) -> Ident {
+    type_or_field_name(name, used_names, "GeneratedType", type_case)
+}
+
+fn type_or_field_name(
+    name: &str,
+    used_names: &HashSet<String>,
+    default_name: &str,
+    case_fn: fn(&str) -> String,
+) -> Ident {
+    let name = name.trim();
+    let mut output_name = case_fn(name);
+    if output_name.is_empty() {
+        output_name.push_str(default_name);
+    }
+    if output_name.starts_with(|c: char| c.is_ascii_digit()) {
+        output_name = String::from("n") + &output_name;
+    }
+    if !used_names.contains(&output_name) {
+        return output_name;
+    }
+    for n in 2.. {
+        let temp = format!("{}{}", output_name, n);
+        if !used_names.contains(&temp) {
+            return temp;
+        }
+    }
+    unreachable!()
+}
+
+/// The JSON name protoc derives for a field, i.e. the name used if no `json_name` is given
+fn default_json_name(field_name: &str) -> String {
+    let mut json_name = String::new();
+    let mut capitalize_next = false;
+    for c in field_name.chars() {
+        if c == '_' {
+            capitalize_next = true;
+        } else if capitalize_next {
+            json_name.push(c.to_ascii_uppercase());
+            capitalize_next = false;
+        } else {
+            json_name.push(c);
+        }
+    }
+    json_name
+}
+
+fn generate_message(
+    ctxt: &mut Ctxt,
+    path: &str,
+    field_shapes: &LinkedHashMap<String, Shape>,
+    containing_shape: &Shape,
+) -> (Ident, Option<Code>) {
+    for (created_for_shape, ident) in ctxt.created_messages.iter() {
+        if created_for_shape.is_acceptable_substitution_for(containing_shape) {
+            return (ident.into(), None);
+        }
+    }
+
+    let type_name = type_name(path, &ctxt.type_names);
+    ctxt.type_names.insert(type_name.clone());
+    ctxt.created_messages
+        .push((containing_shape.clone(), type_name.clone()));
+
+    let mut field_names = HashSet::new();
+    let mut defs = Vec::new();
+
+    let fields: Vec<Code> = field_shapes
+        .iter()
+        .enumerate()
+        .map(|(i, (name, typ))| {
+            let field_name = field_name(name, &field_names);
+            field_names.insert(field_name.clone());
+
+            let (field_type, child_defs) = field_type_from_shape(ctxt, name, typ);
+
+            if let Some(code) = child_defs.filter(|code| !code.is_empty()) {
+                defs.push(code);
+            }
+
+            let json_name = if &default_json_name(&field_name) != name {
+                format!(" [json_name = \"{}\"]", name)
+            } else {
+                String::new()
+            };
+
+            format!("  {} {} = {}{};", field_type, field_name, i + 1, json_name)
+        })
+        .collect();
+
+    let mut code = format!("message {} {{\n", type_name);
+
+    if !fields.is_empty() {
+        code += &fields.join("\n");
+        code += "\n";
+    }
+    code += "}";
+
+    if !defs.is_empty() {
+        code += "\n\n";
+        code += &defs.join("\n\n");
+    }
+
+    (type_name, Some(code))
+}
+
+#[cfg(test)]
+mod protobuf_codegen_tests {
+    use super::*;
+
+    #[test]
+    fn default_json_name_test() {
+        assert_eq!(default_json_name("foo"), "foo");
+        assert_eq!(default_json_name("foo_bar"), "fooBar");
+        assert_eq!(default_json_name("foo_bar_2"), "fooBar2");
+    }
+}
@@ -1,7 +1,9 @@
 use linked_hash_map::LinkedHashMap;
 use std::collections::HashSet;
 
-use crate::options::{ImportStyle, Options, StringTransform};
+use crate::generation::Root;
+use crate::observations::{Constraint, Observations, child_pointer};
+use crate::options::{ImportStyle, Options, OutputMode, StringTransform};
 use crate::shape::{self, Shape};
 use crate::to_singular::to_singular;
 use crate::util::{kebab_case, lower_camel_case, snake_case, type_case};
@@ -10,8 +12,23 @@ use crate::util::{kebab_case, lower_camel_case, snake_case, type_case};
 enum Import {
     Any,
     Optional,
+    NotRequired,
+    TypedDict,
+    Generic,
+    TypeVar,
+    Dataclass,
+    DataclassField,
     BaseModel,
+    GenericModel,
     Field,
+    ConfigDict,
+    Extra,
+    ToCamel,
+    ToPascal,
+    AttrsDefine,
+    AttrsField,
+    MsgspecStruct,
+    MsgspecField,
 }
 
 impl Import {
@@ -19,8 +36,23 @@ impl Import {
         match self {
             Import::Any => ("typing", "Any"),
             Import::Optional => ("typing", "Optional"),
+            Import::NotRequired => ("typing", "NotRequired"),
+            Import::TypedDict => ("typing", "TypedDict"),
+            Import::Generic => ("typing", "Generic"),
+            Import::TypeVar => ("typing", "TypeVar"),
+            Import::Dataclass => ("dataclasses", "dataclass"),
+            Import::DataclassField => ("dataclasses", "field"),
             Import::BaseModel => ("pydantic", "BaseModel"),
+            Import::GenericModel => ("pydantic.generics", "GenericModel"),
             Import::Field => ("pydantic", "Field"),
+            Import::ConfigDict => ("pydantic", "ConfigDict"),
+            Import::Extra => ("pydantic", "Extra"),
+            Import::ToCamel => ("pydantic.alias_generators", "to_camel"),
+            Import::ToPascal => ("pydantic.alias_generators", "to_pascal"),
+            Import::AttrsDefine => ("attrs", "define"),
+            Import::AttrsField => ("attrs", "field"),
+            Import::MsgspecStruct => ("msgspec", "Struct"),
+            Import::MsgspecField => ("msgspec", "field"),
         }
     }
     fn module(&self) -> &'static str {
@@ -37,29 +69,76 @@ impl Import {
 
 struct Ctxt {
     options: Options,
+    observations: Observations,
     type_names: HashSet<String>,
     imports: HashSet<Import>,
     created_classes: Vec<(Shape, Ident)>,
+    /// Names of the recursive types being generated, innermost last
+    recursive_types: Vec<Ident>,
+    /// JSON pointer to the value a type is generated for, to look up observations
+    pointer: String,
+    /// Type parameters of the class being generated, with their type arguments
+    type_params: Option<Vec<(Ident, Ident)>>,
+    /// Type variables used by any class, declared before the classes
+    type_vars: Vec<Ident>,
 }
 
 pub type Ident = String;
 pub type Code = String;
 
-pub fn python_types(name: &str, shape: &Shape, options: Options) -> Code {
+pub fn python_types(
+    name: &str,
+    shape: &Shape,
+    options: Options,
+    observations: Observations,
+) -> Code {
+    python_types_for_roots(&[Root::single(name, shape)], options, observations)
+}
+
+pub fn python_types_for_roots(
+    roots: &[Root],
+    options: Options,
+    observations: Observations,
+) -> Code {
     let mut ctxt = Ctxt {
         options,
+        observations,
         type_names: HashSet::new(),
         imports: HashSet::new(),
         created_classes: Vec::new(),
+        recursive_types: Vec::new(),
+        pointer: String::new(),
+        type_params: None,
+        type_vars: Vec::new(),
     };
 
-    if !matches!(shape, Shape::Struct { .. }) {
-        // reserve the requested name
-        ctxt.type_names.insert(name.to_string());
+    // reserve the requested names, so that no other types can take them
+    for root in roots {
+        ctxt.type_names.insert(root.name.to_string());
     }
 
-    let (ident, code) = type_from_shape(&mut ctxt, name, shape);
-    let mut code = code.unwrap_or_default();
+    let mut code = String::new();
+    for root in roots {
+        let root_code = root_type(&mut ctxt, root);
+        if root_code.is_empty() {
+            continue;
+        }
+        if !code.is_empty() {
+            // classes are separated by two blank lines
+            code.truncate(code.trim_end().len());
+            code += "\n\n\n";
+        }
+        code += &root_code;
+    }
+
+    if !ctxt.type_vars.is_empty() {
+        let type_var = import(&mut ctxt, Import::TypeVar);
+        let mut declarations = String::new();
+        for var in ctxt.type_vars.iter() {
+            declarations += &format!("{} = {}(\"{}\")\n", var, type_var, var);
+        }
+        code = declarations + "\n\n" + &code;
+    }
 
     if !ctxt.imports.is_empty() {
         let mut imports: Vec<_> = ctxt.imports.drain().collect();
@@ -89,7 +168,23 @@ pub fn python_types(name: &str, shape: &Shape, options: Options) -> Code {
         }
     }
 
-    if ident != name {
+    code
+}
+
+/// The classes for a root, with an alias if its type did not get the requested name
+fn root_type(ctxt: &mut Ctxt, root: &Root) -> Code {
+    let name = root.name;
+    if matches!(root.shape, Shape::Struct { .. } | Shape::Recursive(_)) {
+        // let the class itself have the requested name
+        ctxt.type_names.remove(name);
+    }
+
+    ctxt.pointer = root.pointer.clone();
+    let (ident, code) = type_from_shape(ctxt, name, root.shape);
+    let mut code = code.unwrap_or_default();
+    ctxt.type_names.insert(name.to_string());
+
+    if ident != name && !ident.starts_with(&format!("{}[", name)) {
         if !code.is_empty() {
             code += "\n\n";
         }
@@ -116,6 +211,31 @@ fn type_from_shape(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> (Ident, Option
         }
         VecT { elem_type: e } => generate_vec_type(ctxt, path, e),
         Struct { fields } => generate_data_class(ctxt, path, fields, shape),
+        Recursive(e) => match &**e {
+            Struct { fields } => generate_data_class(ctxt, path, fields, shape),
+            _ => type_from_shape(ctxt, path, e),
+        },
+        SelfRef => match ctxt.recursive_types.last() {
+            // A forward reference, since the class is not defined yet
+            Some(ident) => (format!("\"{}\"", ident), None),
+            None => (import(ctxt, Import::Any), None),
+        },
+        TypeParam(name, arg) => {
+            let (arg_ident, arg_code) = type_from_shape(ctxt, path, arg);
+            match ctxt.type_params {
+                Some(ref mut type_params) => {
+                    if !type_params.iter().any(|(param, _)| param == name) {
+                        type_params.push((name.clone(), arg_ident));
+                    }
+                    if !ctxt.type_vars.contains(name) {
+                        ctxt.type_vars.push(name.clone());
+                    }
+                    (name.clone(), arg_code)
+                }
+                // Outside of any class there is nothing to be generic
+                None => (arg_ident, arg_code),
+            }
+        }
         MapT { val_type: v } => generate_map_type(ctxt, path, v),
         Opaque(t) => (t.clone(), None),
         Optional(e) => {
@@ -141,7 +261,10 @@ fn type_from_shape(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> (Ident, Option
 
 fn generate_vec_type(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> (Ident, Option<Code>) {
     let singular = to_singular(path);
+    let pointer = child_pointer(&ctxt.pointer, "-");
+    let parent_pointer = std::mem::replace(&mut ctxt.pointer, pointer);
     let (inner, defs) = type_from_shape(ctxt, &singular, shape);
+    ctxt.pointer = parent_pointer;
     (format!("list[{}]", inner), defs)
 }
 
@@ -155,6 +278,8 @@ fn generate_tuple_type(ctxt: &mut Ctxt, path: &str, shapes: &[Shape]) -> (Ident,
     let mut types = Vec::new();
     let mut defs = Vec::new();
 
+    let pointer = child_pointer(&ctxt.pointer, "-");
+    let parent_pointer = std::mem::replace(&mut ctxt.pointer, pointer);
     for shape in shapes {
         let (typ, def) = type_from_shape(ctxt, path, shape);
         types.push(typ);
@@ -164,6 +289,7 @@ fn generate_tuple_type(ctxt: &mut Ctxt, path: &str, shapes: &[Shape]) -> (Ident,
             }
         }
     }
+    ctxt.pointer = parent_pointer;
 
     (
         format!("tuple[{}]", types.join(", ")),
@@ -228,16 +354,42 @@ fn import(ctxt: &mut Ctxt, import: Import) -> String {
     }
 }
 
+/// A generic class used for another shape, with the type arguments of that shape
+fn with_type_args(ctxt: &mut Ctxt, ident: &str, shape: &Shape) -> (Ident, Option<Code>) {
+    let type_args = shape::type_args(shape);
+    if type_args.is_empty() {
+        return (ident.to_string(), None);
+    }
+    let name = ident.split('[').next().unwrap();
+    let mut args = Vec::new();
+    let mut defs = Vec::new();
+    for (key, arg) in type_args {
+        let pointer = child_pointer(&ctxt.pointer, key);
+        let parent_pointer = std::mem::replace(&mut ctxt.pointer, pointer);
+        let (arg, def) = type_from_shape(ctxt, key, arg);
+        ctxt.pointer = parent_pointer;
+        args.push(arg);
+        defs.extend(def);
+    }
+    let code = if defs.is_empty() {
+        None
+    } else {
+        Some(defs.join("\n\n"))
+    };
+    (format!("{}[{}]", name, args.join(", ")), code)
+}
+
 fn generate_data_class(
     ctxt: &mut Ctxt,
     path: &str,
     field_shapes: &LinkedHashMap<String, Shape>,
     containing_shape: &Shape,
 ) -> (Ident, Option<Code>) {
-    for (created_for_shape, ident) in ctxt.created_classes.iter() {
-        if created_for_shape.is_acceptable_substitution_for(containing_shape) {
-            return (ident.into(), None);
-        }
+    if let Some((_, ident)) = ctxt.created_classes.iter().find(|(created_for_shape, _)| {
+        created_for_shape.is_acceptable_substitution_for(containing_shape)
+    }) {
+        let ident = ident.clone();
+        return with_type_args(ctxt, &ident, containing_shape);
     }
 
     let type_name = type_name(path, &ctxt.type_names);
@@ -245,16 +397,47 @@ fn generate_data_class(
     ctxt.created_classes
         .push((containing_shape.clone(), type_name.clone()));
 
+    let is_recursive = matches!(containing_shape, Shape::Recursive(_));
+    if is_recursive {
+        ctxt.recursive_types.push(type_name.clone());
+    }
+
+    let is_typed_dict = ctxt.options.output_mode == OutputMode::PythonTypedDict;
+    let is_pydantic = matches!(
+        ctxt.options.output_mode,
+        OutputMode::PythonPydantic | OutputMode::PythonPydanticV1
+    );
+    let uses_alias_generator = is_pydantic && alias_generator(ctxt).is_some();
+    let mut has_aliases = uses_alias_generator;
     let mut field_names = HashSet::new();
     let mut defs = Vec::new();
+    let outer_type_params = ctxt.type_params.replace(Vec::new());
 
-    let fields: Vec<Code> = field_shapes
+    let fields: Vec<(Ident, Code, Vec<String>)> = field_shapes
         .iter()
         .map(|(name, typ)| {
             let field_name = field_name(name, &field_names);
             field_names.insert(field_name.clone());
 
-            let (field_type, child_defs) = type_from_shape(ctxt, name, typ);
+            let pointer = child_pointer(&ctxt.pointer, name);
+            let doc_lines = ctxt.observations.doc_lines(&pointer);
+            let constraints = if is_pydantic && ctxt.options.infer_constraints {
+                ctxt.observations.constraints(&pointer, typ)
+            } else {
+                Vec::new()
+            };
+
+            let parent_pointer = std::mem::replace(&mut ctxt.pointer, pointer);
+            let (field_type, child_defs) = match typ {
+                // A missing key is different from a null value for a TypedDict
+                Shape::Optional(e) if is_typed_dict => {
+                    let (inner, defs) = type_from_shape(ctxt, name, e);
+                    let not_required = import(ctxt, Import::NotRequired);
+                    (format!("{}[{}]", not_required, inner), defs)
+                }
+                _ => type_from_shape(ctxt, name, typ),
+            };
+            ctxt.pointer = parent_pointer;
 
             if let Some(code) = child_defs {
                 if !code.is_empty() {
@@ -262,43 +445,335 @@ fn generate_data_class(
                 }
             }
 
+            if is_typed_dict {
+                // The keys of a TypedDict are the keys of the JSON object
+                return (name.clone(), field_type, doc_lines);
+            }
+
             let mut field_code = String::new();
             let transformed = apply_transform(ctxt, &field_name, name);
-            if transformed != field_name {
-                field_code += &format!(
-                    " = {}(alias=\"{}\")",
-                    import(ctxt, Import::Field),
-                    transformed
-                )
+            let needs_alias = transformed != field_name && !uses_alias_generator;
+            has_aliases |= needs_alias;
+            if is_pydantic {
+                // Aliases and constraints share the same `Field(...)`
+                let mut args = Vec::new();
+                if needs_alias {
+                    args.push(format!("alias=\"{}\"", transformed));
+                }
+                for constraint in constraints.iter() {
+                    args.push(pydantic_constraint(ctxt, constraint));
+                }
+                if !args.is_empty() {
+                    field_code +=
+                        &format!(" = {}({})", import(ctxt, Import::Field), args.join(", "));
+                }
+            } else if needs_alias {
+                field_code += &rename_field(ctxt, &transformed);
             }
 
-            format!("    {}: {}{}", field_name, field_type, field_code)
+            (
+                field_name,
+                format!("{}{}", field_type, field_code),
+                doc_lines,
+            )
         })
         .collect();
 
+    if is_recursive {
+        ctxt.recursive_types.pop();
+    }
+    let type_params = std::mem::replace(&mut ctxt.type_params, outer_type_params);
+
+    // Other types refer to a generic class with the type arguments filled in
+    let (params, instance) = match type_params {
+        Some(type_params) if !type_params.is_empty() => {
+            let (params, args): (Vec<Ident>, Vec<Ident>) = type_params.into_iter().unzip();
+            let instance = format!("{}[{}]", type_name, args.join(", "));
+            if let Some(created) = ctxt
+                .created_classes
+                .iter_mut()
+                .find(|(_, ident)| *ident == type_name)
+            {
+                created.1 = instance.clone();
+            }
+            (params, instance)
+        }
+        _ => (Vec::new(), type_name.clone()),
+    };
+
     let mut code = String::new();
 
-    code += &format!(
-        "class {}({}):\n",
-        type_name,
-        import(ctxt, Import::BaseModel)
-    );
+    if is_typed_dict && !fields.iter().all(|(key, _, _)| is_python_identifier(key)) {
+        // Keys that are not valid identifiers are only possible with the functional syntax
+        code += &format!(
+            "{} = {}(\"{}\", {{\n",
+            type_name,
+            import(ctxt, Import::TypedDict),
+            type_name
+        );
+        for (key, field_type, doc_lines) in fields.iter() {
+            code += &comment(doc_lines);
+            code += &format!("    \"{}\": {},\n", key, field_type);
+        }
+        code += "})\n";
+        return (instance, Some(with_defs(defs, code)));
+    }
 
-    if fields.is_empty() {
-        code += "    pass\n";
+    let fields: Vec<Code> = fields
+        .iter()
+        .map(|(field_name, field_type, doc_lines)| {
+            if is_typed_dict {
+                // Only annotations are allowed in the body of a TypedDict
+                format!("{}    {}: {}", comment(doc_lines), field_name, field_type)
+            } else {
+                format!("    {}: {}{}", field_name, field_type, docstring(doc_lines))
+            }
+        })
+        .collect();
+
+    code += &class_header(ctxt, &type_name, &params);
+
+    let model_config = if is_pydantic {
+        pydantic_config(ctxt, has_aliases)
     } else {
+        Vec::new()
+    };
+
+    if ctxt.options.output_mode == OutputMode::PythonPydantic && !model_config.is_empty() {
+        code += &format!(
+            "    model_config = {}({})\n",
+            import(ctxt, Import::ConfigDict),
+            model_config
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        if !fields.is_empty() {
+            code += "\n";
+        }
+    } else if fields.is_empty() && model_config.is_empty() {
+        code += "    pass\n";
+    }
+
+    if !fields.is_empty() {
         code += &fields.join("\n");
         code += "\n";
     }
 
-    if !defs.is_empty() {
-        let mut d = defs.join("\n\n");
-        d += "\n\n";
-        d += &code;
-        code = d;
+    if ctxt.options.output_mode == OutputMode::PythonPydanticV1 && !model_config.is_empty() {
+        if !fields.is_empty() {
+            code += "\n";
+        }
+        code += "    class Config:\n";
+        for (key, value) in model_config.iter() {
+            code += &format!("        {} = {}\n", key, value);
+        }
+    }
+
+    (instance, Some(with_defs(defs, code)))
+}
+
+/// An attribute docstring, on the line after the attribute
+fn docstring(lines: &[String]) -> Code {
+    match lines {
+        [] => String::new(),
+        // The closing quotes can't directly follow a quote
+        [line] if !line.ends_with('"') => format!("\n    \"\"\"{}\"\"\"", line),
+        _ => format!("\n    \"\"\"{}\n    \"\"\"", lines.join("\n    ")),
+    }
+}
+
+fn comment(lines: &[String]) -> Code {
+    lines
+        .iter()
+        .map(|line| format!("    # {}\n", line))
+        .collect()
+}
+
+/// The model configuration for pydantic, as pairs of setting and value.
+/// The setting names are translated to the pydantic v1 names when needed.
+fn pydantic_config(ctxt: &mut Ctxt, has_aliases: bool) -> Vec<(&'static str, Code)> {
+    let v1 = ctxt.options.output_mode == OutputMode::PythonPydanticV1;
+    let mut config = Vec::new();
+
+    // Forbidding additional fields would make it impossible to collect them
+    let extra = if ctxt.options.collect_additional {
+        Some("allow")
+    } else if ctxt.options.deny_unknown_fields {
+        Some("forbid")
+    } else {
+        None
+    };
+    if let Some(extra) = extra {
+        let value = if v1 {
+            format!("{}.{}", import(ctxt, Import::Extra), extra)
+        } else {
+            format!("\"{}\"", extra)
+        };
+        config.push(("extra", value));
+    }
+
+    if let Some(generator) = alias_generator(ctxt) {
+        config.push(("alias_generator", generator));
+    }
+
+    if has_aliases && !config.is_empty() {
+        // Allow creating instances using the field names as well
+        let key = if v1 {
+            "allow_population_by_field_name"
+        } else {
+            "populate_by_name"
+        };
+        config.insert(0, (key, "True".to_string()));
+    }
+
+    config
+}
+
+/// A pydantic alias generator matching `property_name_format`, if renaming is needed
+fn alias_generator(ctxt: &mut Ctxt) -> Option<Code> {
+    let v1 = ctxt.options.output_mode == OutputMode::PythonPydanticV1;
+    let generator = match ctxt.options.property_name_format {
+        // Field names are already snake case
+        None | Some(StringTransform::SnakeCase) | Some(StringTransform::LowerCase) => return None,
+        Some(StringTransform::PascalCase) if !v1 => import(ctxt, Import::ToPascal),
+        Some(StringTransform::CamelCase) if !v1 => import(ctxt, Import::ToCamel),
+        Some(StringTransform::PascalCase) => {
+            "lambda name: \"\".join(word.capitalize() for word in name.split(\"_\"))".into()
+        }
+        Some(StringTransform::CamelCase) => {
+            "lambda name: name.split(\"_\")[0] + \"\".join(word.capitalize() for word in name.split(\"_\")[1:])".into()
+        }
+        Some(StringTransform::UpperCase) | Some(StringTransform::ScreamingSnakeCase) => {
+            "lambda name: name.upper()".into()
+        }
+        Some(StringTransform::KebabCase) => "lambda name: name.replace(\"_\", \"-\")".into(),
+        Some(StringTransform::ScreamingKebabCase) => {
+            "lambda name: name.replace(\"_\", \"-\").upper()".into()
+        }
+    };
+    Some(generator)
+}
+
+/// Child classes have to be defined before the class that uses them
+fn with_defs(defs: Vec<Code>, code: Code) -> Code {
+    if defs.is_empty() {
+        return code;
+    }
+    let mut d = defs.join("\n\n");
+    d += "\n\n";
+    d += &code;
+    d
+}
+
+fn class_header(ctxt: &mut Ctxt, type_name: &str, type_params: &[Ident]) -> Code {
+    let generic = if type_params.is_empty() {
+        None
+    } else {
+        Some(format!(
+            "{}[{}]",
+            import(ctxt, Import::Generic),
+            type_params.join(", ")
+        ))
+    };
+    let bases = |base: Option<String>| {
+        let bases: Vec<String> = base.into_iter().chain(generic.clone()).collect();
+        if bases.is_empty() {
+            String::new()
+        } else {
+            format!("({})", bases.join(", "))
+        }
+    };
+    match ctxt.options.output_mode {
+        OutputMode::PythonTypedDict => {
+            let base = import(ctxt, Import::TypedDict);
+            format!("class {}{}:\n", type_name, bases(Some(base)))
+        }
+        OutputMode::PythonDataclass => {
+            format!(
+                "@{}\nclass {}{}:\n",
+                import(ctxt, Import::Dataclass),
+                type_name,
+                bases(None)
+            )
+        }
+        OutputMode::PythonAttrs => {
+            format!(
+                "@{}\nclass {}{}:\n",
+                import(ctxt, Import::AttrsDefine),
+                type_name,
+                bases(None)
+            )
+        }
+        OutputMode::PythonMsgspec => {
+            let base = import(ctxt, Import::MsgspecStruct);
+            format!("class {}{}:\n", type_name, bases(Some(base)))
+        }
+        // pydantic v1 can only parametrize generic models that are based on `GenericModel`
+        OutputMode::PythonPydanticV1 if !type_params.is_empty() => {
+            let base = import(ctxt, Import::GenericModel);
+            format!("class {}{}:\n", type_name, bases(Some(base)))
+        }
+        _ => {
+            let base = import(ctxt, Import::BaseModel);
+            format!("class {}{}:\n", type_name, bases(Some(base)))
+        }
+    }
+}
+
+/// The default value for a field that maps it to the given name in the JSON
+fn rename_field(ctxt: &mut Ctxt, json_name: &str) -> Code {
+    match ctxt.options.output_mode {
+        // dataclasses and attrs have no renaming of their own, so the name is put in the
+        // field metadata, where serialization libraries like cattrs can pick it up
+        OutputMode::PythonDataclass => format!(
+            " = {}(metadata={{\"alias\": \"{}\"}})",
+            import(ctxt, Import::DataclassField),
+            json_name
+        ),
+        OutputMode::PythonAttrs => format!(
+            " = {}(metadata={{\"alias\": \"{}\"}})",
+            import(ctxt, Import::AttrsField),
+            json_name
+        ),
+        OutputMode::PythonMsgspec => format!(
+            " = {}(name=\"{}\")",
+            import(ctxt, Import::MsgspecField),
+            json_name
+        ),
+        _ => format!(
+            " = {}(alias=\"{}\")",
+            import(ctxt, Import::Field),
+            json_name
+        ),
     }
+}
 
-    (type_name, Some(code))
+/// A keyword argument to a pydantic `Field`
+fn pydantic_constraint(ctxt: &Ctxt, constraint: &Constraint) -> Code {
+    let is_v1 = ctxt.options.output_mode == OutputMode::PythonPydanticV1;
+    match constraint {
+        Constraint::MinLength(n) => format!("min_length={}", n),
+        Constraint::MaxLength(n) => format!("max_length={}", n),
+        Constraint::NonEmpty if is_v1 => "min_items=1".to_string(),
+        Constraint::NonEmpty => "min_length=1".to_string(),
+        Constraint::Minimum(n) => format!("ge={}", n),
+        Constraint::Maximum(n) => format!("le={}", n),
+        Constraint::Format(format) if is_v1 => format!("regex=r\"{}\"", format.pattern()),
+        Constraint::Format(format) => format!("pattern=r\"{}\"", format.pattern()),
+    }
+}
+
+fn is_python_identifier(s: &str) -> bool {
+    let valid_chars = match s.as_bytes().split_first() {
+        Some((first, rest)) => {
+            (first.is_ascii_alphabetic() || *first == b'_')
+                && rest.iter().all(|b| b.is_ascii_alphanumeric() || *b == b'_')
+        }
+        None => false,
+    };
+    valid_chars && !PYTHON_KEYWORDS.contains(&s)
 }
 
 fn apply_transform(ctxt: &Ctxt, field_name: &str, name: &str) -> String {
@@ -1,30 +1,87 @@
 use linked_hash_map::LinkedHashMap;
 use std::collections::HashSet;
 
+use crate::generation::Root;
 use crate::generation::serde_case::RenameRule;
-use crate::options::{ImportStyle, Options, StringTransform};
+use crate::observations::{Constraint, Observations, StringFormat, child_pointer};
+use crate::options::{BorrowStyle, ImportStyle, Options, StringTransform, ValidationCrate};
 use crate::shape::{self, Shape};
 use crate::to_singular::to_singular;
 use crate::util::{snake_case, type_case};
 
 pub struct Ctxt {
-    options: Options,
+    pub(crate) options: Options,
+    observations: Observations,
     type_names: HashSet<String>,
     imports: HashSet<String>,
     created_structs: Vec<(Shape, Ident)>,
+    /// Names of the recursive types being generated, innermost last
+    pub(crate) recursive_types: Vec<Ident>,
+    /// JSON pointer to the value a type is generated for, to look up observations
+    pub(crate) pointer: String,
+    /// Type parameters of the struct being generated, with their type arguments
+    type_params: Option<Vec<(Ident, Ident)>>,
+    /// Generated structs that contain a `RawValue`, directly or through other structs
+    raw_value_structs: HashSet<Ident>,
 }
 
 pub type Ident = String;
 pub type Code = String;
 
-pub fn rust_types(name: &str, shape: &Shape, options: Options) -> Code {
+pub fn rust_types(name: &str, shape: &Shape, options: Options, observations: Observations) -> Code {
+    rust_types_for_roots(&[Root::single(name, shape)], options, observations)
+}
+
+pub fn rust_types_for_roots(roots: &[Root], options: Options, observations: Observations) -> Code {
+    let mut ctxt = new_ctxt(options, observations);
+
+    // reserve the requested names, so that no other types can take them
+    for root in roots {
+        ctxt.type_names.insert(root.name.to_string());
+    }
+
+    let mut code = roots
+        .iter()
+        .map(|root| root_type(&mut ctxt, root))
+        .filter(|code| !code.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    if !ctxt.imports.is_empty() {
+        let mut import_code = String::new();
+        for import in take_imports(&mut ctxt) {
+            import_code += "use ";
+            import_code += &import;
+            import_code += ";\n";
+        }
+        import_code += "\n";
+        code = import_code + &code;
+    }
+
+    code
+}
+
+pub(crate) fn new_ctxt(options: Options, observations: Observations) -> Ctxt {
     let mut ctxt = Ctxt {
         options,
+        observations,
         type_names: HashSet::new(),
         imports: HashSet::new(),
         created_structs: Vec::new(),
+        recursive_types: Vec::new(),
+        pointer: String::new(),
+        type_params: None,
+        raw_value_structs: HashSet::new(),
     };
 
+    if ctxt.options.infer_constraints {
+        let validate = match ctxt.options.validation_crate {
+            ValidationCrate::Validator => "validator::Validate",
+            ValidationCrate::Garde => "garde::Validate",
+        };
+        ctxt.options.derives = format!("{}, {}", ctxt.options.derives, validate);
+    }
+
     if ctxt.options.import_style != ImportStyle::QualifiedPaths {
         ctxt.options.derives = ctxt
             .options
@@ -36,43 +93,59 @@ pub fn rust_types(name: &str, shape: &Shape, options: Options) -> Code {
             .join(", ");
     };
 
-    if !matches!(shape, Shape::Struct { .. }) {
-        // reserve the requested name
-        ctxt.type_names.insert(name.to_string());
+    ctxt
+}
+
+/// The paths that need to be imported for the code generated so far, sorted
+pub(crate) fn take_imports(ctxt: &mut Ctxt) -> Vec<String> {
+    let mut imports: Vec<_> = ctxt.imports.drain().collect();
+    imports.sort();
+    imports
+}
+
+/// Keep generated types from taking a name that is already in use
+pub(crate) fn reserve_type_name(ctxt: &mut Ctxt, name: &str) {
+    ctxt.type_names.insert(name.to_string());
+}
+
+/// The types for a root, with an alias if its type did not get the requested name
+pub(crate) fn root_type(ctxt: &mut Ctxt, root: &Root) -> Code {
+    let name = root.name;
+    if matches!(root.shape, Shape::Struct { .. } | Shape::Recursive(_)) {
+        // let the struct itself have the requested name
+        ctxt.type_names.remove(name);
     }
 
-    let (ident, code) = type_from_shape(&mut ctxt, name, shape);
+    ctxt.pointer = root.pointer.clone();
+    let (ident, code) = type_from_shape(ctxt, name, root.shape);
     let mut code = code.unwrap_or_default();
-
-    if ident != name {
+    ctxt.type_names.insert(name.to_string());
+
+    if ident != name && !ident.starts_with(&format!("{}<", name)) {
+        let alias_name = if is_borrowed(&ident) {
+            format!("{}<'a>", name)
+        } else {
+            name.to_string()
+        };
         code = format!(
             "{} type {} = {};\n\n{}",
-            ctxt.options.type_visibility, name, ident, code
+            ctxt.options.type_visibility, alias_name, ident, code
         );
     }
 
-    if !ctxt.imports.is_empty() {
-        let mut imports: Vec<_> = ctxt.imports.drain().collect();
-        imports.sort();
-        let mut import_code = String::new();
-        for import in imports {
-            import_code += "use ";
-            import_code += &import;
-            import_code += ";\n";
-        }
-        import_code += "\n";
-        code = import_code + &code;
-    }
-
     code
 }
 
-fn type_from_shape(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> (Ident, Option<Code>) {
+pub(crate) fn type_from_shape(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> (Ident, Option<Code>) {
     use crate::shape::Shape::*;
     match shape {
+        Null | Any | Bottom if ctxt.options.borrow != BorrowStyle::Owned => (
+            format!("&'a {}", import(ctxt, "serde_json::value::RawValue")),
+            None,
+        ),
         Null | Any | Bottom => (import(ctxt, "serde_json::Value"), None),
         Bool => ("bool".into(), None),
-        StringT => ("String".into(), None),
+        StringT => (string_type(ctxt), None),
         Integer => ("i64".into(), None),
         Floating => ("f64".into(), None),
         Tuple(shapes, _n) => {
@@ -85,6 +158,28 @@ fn type_from_shape(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> (Ident, Option
         }
         VecT { elem_type: e } => generate_vec_type(ctxt, path, e),
         Struct { fields } => generate_struct_type(ctxt, path, fields, shape),
+        Recursive(e) => match &**e {
+            Struct { fields } => generate_struct_type(ctxt, path, fields, shape),
+            _ => type_from_shape(ctxt, path, e),
+        },
+        TypeParam(name, arg) => {
+            let (arg_ident, arg_code) = type_from_shape(ctxt, path, arg);
+            match ctxt.type_params {
+                Some(ref mut type_params) => {
+                    if !type_params.iter().any(|(param, _)| param == name) {
+                        type_params.push((name.clone(), arg_ident));
+                    }
+                    (name.clone(), arg_code)
+                }
+                // Outside of any struct there is nothing to be generic
+                None => (arg_ident, arg_code),
+            }
+        }
+        SelfRef => match ctxt.recursive_types.last() {
+            // Boxed, since a struct can not contain itself directly
+            Some(ident) => (format!("Box<{}>", ident), None),
+            None => (import(ctxt, "serde_json::Value"), None),
+        },
         MapT { val_type: v } => generate_map_type(ctxt, path, v),
         Opaque(t) => (t.clone(), None),
         Optional(e) => {
@@ -107,28 +202,63 @@ fn type_from_shape(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> (Ident, Option
 }
 
 fn generate_vec_type(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> (Ident, Option<Code>) {
+    if let (Shape::SelfRef, Some(ident)) = (shape, ctxt.recursive_types.last()) {
+        // The Vec is enough indirection
+        return (format!("Vec<{}>", ident), None);
+    }
     let singular = to_singular(path);
+    let pointer = child_pointer(&ctxt.pointer, "-");
+    let parent_pointer = std::mem::replace(&mut ctxt.pointer, pointer);
     let (inner, defs) = type_from_shape(ctxt, &singular, shape);
+    ctxt.pointer = parent_pointer;
     (format!("Vec<{}>", inner), defs)
 }
 
 fn generate_map_type(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> (Ident, Option<Code>) {
+    if let (Shape::SelfRef, Some(ident)) = (shape, ctxt.recursive_types.last()) {
+        let ident = ident.clone();
+        return (
+            format!(
+                "{}<{}, {}>",
+                import(ctxt, "std::collections::HashMap"),
+                string_type(ctxt),
+                ident
+            ),
+            None,
+        );
+    }
     let singular = to_singular(path);
     let (inner, defs) = type_from_shape(ctxt, &singular, shape);
     (
         format!(
-            "{}<String, {}>",
+            "{}<{}, {}>",
             import(ctxt, "std::collections::HashMap"),
+            string_type(ctxt),
             inner
         ),
         defs,
     )
 }
 
+fn string_type(ctxt: &mut Ctxt) -> Ident {
+    match ctxt.options.borrow {
+        BorrowStyle::Owned => "String".into(),
+        BorrowStyle::Str => "&'a str".into(),
+        BorrowStyle::Cow => format!("{}<'a, str>", import(ctxt, "std::borrow::Cow")),
+    }
+}
+
+/// Whether a type borrows from the input, and so needs the lifetime parameter
+fn is_borrowed(typ: &str) -> bool {
+    typ.contains("'a")
+}
+
 fn generate_tuple_type(ctxt: &mut Ctxt, path: &str, shapes: &[Shape]) -> (Ident, Option<Code>) {
     let mut types = Vec::new();
     let mut defs = Vec::new();
 
+    let pointer = child_pointer(&ctxt.pointer, "-");
+    let parent_pointer = std::mem::replace(&mut ctxt.pointer, pointer);
     for shape in shapes {
         let (typ, def) = type_from_shape(ctxt, path, shape);
         types.push(typ);
@@ -136,6 +266,7 @@ fn generate_tuple_type(ctxt: &mut Ctxt, path: &str, shapes: &[Shape]) -> (Ident,
             defs.push(code)
         }
     }
+    ctxt.pointer = parent_pointer;
 
     (format!("({})", types.join(", ")), Some(defs.join("\n\n")))
 }
@@ -189,7 +320,10 @@ fn type_or_field_name(
 }
 
 fn collapse_option_vec<'a>(ctxt: &mut Ctxt, typ: &'a Shape) -> (bool, &'a Shape) {
-    if !(ctxt.options.allow_option_vec || ctxt.options.use_default_for_missing_fields) {
+    if !(ctxt.options.allow_option_vec
+        || ctxt.options.use_default_for_missing_fields
+        || ctxt.options.round_trip)
+    {
         if let Shape::Optional(inner) = typ {
             if let Shape::VecT { .. } = **inner {
                 return (true, &**inner);
@@ -199,6 +333,87 @@ fn collapse_option_vec<'a>(ctxt: &mut Ctxt, typ: &'a Shape) -> (bool, &'a Shape)
     (false, typ)
 }
 
+/// Keeps missing fields missing when serializing, rather than turning them into nulls
+fn round_trip_attribute(typ: &Shape) -> &'static str {
+    match typ {
+        // Both missing and null, so `Option<Option<T>>`, which needs help to tell them apart
+        Shape::Optional(inner) if matches!(**inner, Shape::Nullable(_)) => {
+            concat!(
+                "    #[serde(default, skip_serializing_if = \"Option::is_none\", ",
+                "with = \"::serde_with::rust::double_option\")]\n"
+            )
+        }
+        Shape::Optional(_) => "    #[serde(skip_serializing_if = \"Option::is_none\")]\n",
+        _ => "",
+    }
+}
+
+/// Validation rules for a field, from the constraints that held for all the samples
+fn validation_attribute(ctxt: &Ctxt, pointer: &str, typ: &Shape, is_collapsed: bool) -> Code {
+    let is_garde = ctxt.options.validation_crate == ValidationCrate::Garde;
+    let mut rules = Vec::new();
+    let mut length = Vec::new();
+    let mut range = Vec::new();
+
+    for constraint in ctxt.observations.constraints(pointer, typ) {
+        match constraint {
+            Constraint::MinLength(n) => length.push(format!("min = {}", n)),
+            Constraint::MaxLength(n) => length.push(format!("max = {}", n)),
+            // A missing list is deserialized as an empty one
+            Constraint::NonEmpty if is_collapsed => {}
+            Constraint::NonEmpty => length.push("min = 1".to_string()),
+            Constraint::Minimum(n) => range.push(format!("min = {}", n)),
+            Constraint::Maximum(n) => range.push(format!("max = {}", n)),
+            Constraint::Format(StringFormat::Email) => rules.push("email".to_string()),
+            Constraint::Format(StringFormat::Url) => rules.push("url".to_string()),
+            // Neither crate has rules for other formats without a custom regex
+            Constraint::Format(_) => {}
+        }
+    }
+
+    if !length.is_empty() {
+        // garde counts bytes unless told otherwise, validator always counts characters
+        if is_garde
+            && !matches!(
+                shape::strip_option(typ),
+                Shape::VecT { .. } | Shape::Tuple(..)
+            )
+        {
+            length.insert(0, "chars".to_string());
+        }
+        rules.push(format!("length({})", length.join(", ")));
+    }
+    if !range.is_empty() {
+        rules.push(format!("range({})", range.join(", ")));
+    }
+    if validates_nested(typ) {
+        rules.push(if is_garde { "dive" } else { "nested" }.to_string());
+    }
+
+    match (rules.is_empty(), is_garde) {
+        (false, false) => format!("    #[validate({})]\n", rules.join(", ")),
+        (false, true) => format!("    #[garde({})]\n", rules.join(", ")),
+        (true, false) => String::new(),
+        // garde requires every field to have a rule
+        (true, true) => "    #[garde(skip)]\n".to_string(),
+    }
+}
+
+/// Whether a field contains generated structs, which have validation rules of their own
+fn validates_nested(typ: &Shape) -> bool {
+    match typ {
+        Shape::Optional(e) | Shape::Nullable(e) => validates_nested(e),
+        // Self-references are only unboxed in lists, and `Box` does not implement the traits
+        Shape::VecT { elem_type: e } => **e == Shape::SelfRef || validates_nested(e),
+        Shape::Tuple(shapes, _) => {
+            let folded = shape::fold_shapes(shapes.clone());
+            folded == Shape::SelfRef || validates_nested(&folded)
+        }
+        Shape::Struct { .. } | Shape::Recursive(_) => true,
+        _ => false,
+    }
+}
+
 fn import(ctxt: &mut Ctxt, qualified: &str) -> String {
     if !qualified.contains("::") {
         return qualified.into();
@@ -213,16 +428,46 @@ fn import(ctxt: &mut Ctxt, qualified: &str) -> String {
     }
 }
 
+/// A generic struct used for another shape, with the type arguments of that shape
+fn with_type_args(ctxt: &mut Ctxt, ident: &str, shape: &Shape) -> (Ident, Option<Code>) {
+    let type_args = shape::type_args(shape);
+    if type_args.is_empty() {
+        return (ident.to_string(), None);
+    }
+    let name = ident.split('<').next().unwrap();
+    let mut args = Vec::new();
+    // the lifetime is not a type argument, but is kept
+    if ident.starts_with(&format!("{}<'a", name)) {
+        args.push("'a".to_string());
+    }
+    let mut defs = Vec::new();
+    for (key, arg) in type_args {
+        let pointer = child_pointer(&ctxt.pointer, key);
+        let parent_pointer = std::mem::replace(&mut ctxt.pointer, pointer);
+        let (arg, def) = type_from_shape(ctxt, key, arg);
+        ctxt.pointer = parent_pointer;
+        args.push(arg);
+        defs.extend(def);
+    }
+    let code = if defs.is_empty() {
+        None
+    } else {
+        Some(defs.join("\n\n"))
+    };
+    (format!("{}<{}>", name, args.join(", ")), code)
+}
+
 fn generate_struct_type(
     ctxt: &mut Ctxt,
     path: &str,
     field_shapes: &LinkedHashMap<String, Shape>,
     containing_shape: &Shape,
 ) -> (Ident, Option<Code>) {
-    for (created_for_shape, ident) in ctxt.created_structs.iter() {
-        if created_for_shape.is_acceptable_substitution_for(containing_shape) {
-            return (ident.into(), None);
-        }
+    if let Some((_, ident)) = ctxt.created_structs.iter().find(|(created_for_shape, _)| {
+        created_for_shape.is_acceptable_substitution_for(containing_shape)
+    }) {
+        let ident = ident.clone();
+        return with_type_args(ctxt, &ident, containing_shape);
     }
 
     let type_name = type_name(path, &ctxt.type_names);
@@ -230,53 +475,51 @@ fn generate_struct_type(
     ctxt.created_structs
         .push((containing_shape.clone(), type_name.clone()));
 
+    let is_recursive = matches!(containing_shape, Shape::Recursive(_));
+
     let visibility = ctxt.options.type_visibility.clone();
     let field_visibility = match ctxt.options.field_visibility {
         None => visibility.clone(),
         Some(ref v) => v.clone(),
     };
 
-    let mut field_names = HashSet::new();
-    let mut defs = Vec::new();
-
-    let fields: Vec<Code> = field_shapes
-        .iter()
-        .map(|(name, typ)| {
-            let field_name = field_name(name, &field_names);
-            field_names.insert(field_name.clone());
-
-            let needs_rename = if let Some(ref transform) = ctxt.options.property_name_format {
-                &to_rename_rule(transform).apply_to_field(&field_name) != name
-            } else {
-                &field_name != name
-            };
-            let mut field_code = String::new();
-            if needs_rename {
-                field_code += &format!("    #[serde(rename = \"{}\")]\n", name)
-            }
-
-            let (is_collapsed, collapsed) = collapse_option_vec(ctxt, typ);
-            if is_collapsed {
-                field_code += "    #[serde(default)]\n";
-            }
-
-            let (field_type, child_defs) = type_from_shape(ctxt, name, collapsed);
-
-            if let Some(code) = child_defs {
-                defs.push(code);
-            }
-
-            field_code += "    ";
-            if !field_visibility.is_empty() {
-                field_code += &field_visibility;
-                field_code += " ";
-            }
-
-            format!("{}{}: {},", field_code, field_name, field_type)
-        })
-        .collect();
+    // References to a recursive type need its lifetime and type parameters, which are only
+    // known once its fields are generated, so the fields are generated again if they differ.
+    let snapshot = (
+        ctxt.type_names.clone(),
+        ctxt.imports.clone(),
+        ctxt.created_structs.len(),
+        ctxt.raw_value_structs.clone(),
+    );
+    let mut self_reference = type_name.clone();
+    let (fields, defs, params, args, has_raw_value) = loop {
+        let generated = generate_fields(
+            ctxt,
+            field_shapes,
+            &field_visibility,
+            is_recursive,
+            &self_reference,
+        );
+        let declared_name = generic_name(&type_name, &generated.2);
+        if !is_recursive || declared_name == self_reference {
+            break generated;
+        }
+        ctxt.type_names = snapshot.0.clone();
+        ctxt.imports = snapshot.1.clone();
+        ctxt.created_structs.truncate(snapshot.2);
+        ctxt.raw_value_structs = snapshot.3.clone();
+        self_reference = declared_name;
+    };
 
-    let mut code = format!("#[derive({})]\n", ctxt.options.derives);
+    let mut code = if has_raw_value {
+        ctxt.raw_value_structs.insert(type_name.clone());
+        format!(
+            "#[derive({})]\n",
+            derives_for_raw_value(&ctxt.options.derives)
+        )
+    } else {
+        format!("#[derive({})]\n", ctxt.options.derives)
+    };
 
     if ctxt.options.deny_unknown_fields {
         code += "#[serde(deny_unknown_fields)]\n";
@@ -297,7 +540,21 @@ fn generate_struct_type(
         code += " ";
     }
 
-    code += &format!("struct {} {{\n", type_name);
+    let (declared_name, type_name) = if params.is_empty() {
+        (type_name.clone(), type_name)
+    } else {
+        let instance = generic_name(&type_name, &args);
+        if let Some(created) = ctxt
+            .created_structs
+            .iter_mut()
+            .find(|(_, ident)| *ident == type_name)
+        {
+            created.1 = instance.clone();
+        }
+        (generic_name(&type_name, &params), instance)
+    };
+
+    code += &format!("struct {} {{\n", declared_name);
 
     if !fields.is_empty() {
         code += &fields.join("\n");
@@ -320,6 +577,138 @@ fn generate_struct_type(
     (type_name, Some(code))
 }
 
+/// The fields of a struct, the code for types created for them, the lifetime and type
+/// parameters of the struct along with their arguments, and whether a field contains a `RawValue`
+fn generate_fields(
+    ctxt: &mut Ctxt,
+    field_shapes: &LinkedHashMap<String, Shape>,
+    field_visibility: &str,
+    is_recursive: bool,
+    self_reference: &str,
+) -> (Vec<Code>, Vec<Code>, Vec<Ident>, Vec<Ident>, bool) {
+    if is_recursive {
+        ctxt.recursive_types.push(self_reference.to_string());
+    }
+
+    let mut field_names = HashSet::new();
+    let mut defs = Vec::new();
+    let mut has_borrowed_fields = false;
+    let mut has_raw_value = false;
+    let outer_type_params = ctxt.type_params.replace(Vec::new());
+
+    let fields: Vec<Code> = field_shapes
+        .iter()
+        .map(|(name, typ)| {
+            let (field_code, field_type, child_defs) =
+                generate_field(ctxt, name, typ, &mut field_names, field_visibility);
+            if let Some(code) = child_defs {
+                defs.push(code);
+            }
+            has_borrowed_fields |= is_borrowed(&field_type);
+            has_raw_value |= contains_raw_value(ctxt, &field_type);
+            field_code
+        })
+        .collect();
+
+    if is_recursive {
+        ctxt.recursive_types.pop();
+    }
+    let type_params = std::mem::replace(&mut ctxt.type_params, outer_type_params);
+
+    // The lifetime and type parameters are part of how other types refer to this one
+    let mut params = Vec::new();
+    let mut args = Vec::new();
+    if has_borrowed_fields {
+        params.push("'a".to_string());
+        args.push("'a".to_string());
+    }
+    for (param, arg) in type_params.unwrap_or_default() {
+        params.push(param);
+        args.push(arg);
+    }
+    (fields, defs, params, args, has_raw_value)
+}
+
+/// Whether a type is or contains a `RawValue`, which only implements some of the common traits
+fn contains_raw_value(ctxt: &Ctxt, typ: &str) -> bool {
+    typ.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .any(|ident| ident == "RawValue" || ctxt.raw_value_structs.contains(ident))
+}
+
+/// The derives without the traits that `RawValue` does not implement
+fn derives_for_raw_value(derives: &str) -> String {
+    const NOT_IMPLEMENTED: &[&str] = &["Default", "PartialEq", "Eq", "Hash", "PartialOrd", "Ord"];
+    derives
+        .split(',')
+        .map(str::trim)
+        .filter(|derive| !NOT_IMPLEMENTED.contains(&derive.rsplit("::").next().unwrap()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn generic_name(name: &str, params: &[Ident]) -> Ident {
+    if params.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}<{}>", name, params.join(", "))
+    }
+}
+
+/// A field with its attributes, along with its type and the code for types created for it
+pub(crate) fn generate_field(
+    ctxt: &mut Ctxt,
+    name: &str,
+    typ: &Shape,
+    field_names: &mut HashSet<String>,
+    field_visibility: &str,
+) -> (Code, Ident, Option<Code>) {
+    let field_name = field_name(name, field_names);
+    field_names.insert(field_name.clone());
+
+    let needs_rename = if let Some(ref transform) = ctxt.options.property_name_format {
+        to_rename_rule(transform).apply_to_field(&field_name) != name
+    } else {
+        field_name != name
+    };
+    let pointer = child_pointer(&ctxt.pointer, name);
+    let mut field_code = String::new();
+    for line in ctxt.observations.doc_lines(&pointer) {
+        field_code += &format!("    /// {}\n", line);
+    }
+    if needs_rename {
+        field_code += &format!("    #[serde(rename = \"{}\")]\n", name)
+    }
+
+    let (is_collapsed, collapsed) = collapse_option_vec(ctxt, typ);
+    if is_collapsed {
+        field_code += "    #[serde(default)]\n";
+    }
+    if ctxt.options.round_trip && !ctxt.options.use_default_for_missing_fields {
+        field_code += round_trip_attribute(collapsed);
+    }
+    if ctxt.options.infer_constraints {
+        field_code += &validation_attribute(ctxt, &pointer, collapsed, is_collapsed);
+    }
+
+    let parent_pointer = std::mem::replace(&mut ctxt.pointer, pointer);
+    let (field_type, child_defs) = type_from_shape(ctxt, name, collapsed);
+    ctxt.pointer = parent_pointer;
+
+    // Serde only borrows `&str` and `&[u8]` without being told to
+    if is_borrowed(&field_type) && field_type != "&'a str" {
+        field_code += "    #[serde(borrow)]\n";
+    }
+
+    field_code += "    ";
+    if !field_visibility.is_empty() {
+        field_code += field_visibility;
+        field_code += " ";
+    }
+
+    let field_code = format!("{}{}: {},", field_code, field_name, field_type);
+    (field_code, field_type, child_defs)
+}
+
 fn to_rename_rule(transform: &StringTransform) -> RenameRule {
     match transform {
         StringTransform::LowerCase => RenameRule::LowerCase,
@@ -0,0 +1,1028 @@
+use linked_hash_map::LinkedHashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::JTError;
+use crate::generation::Root;
+use crate::generation::rust::{self, Code, Ctxt};
+use crate::generation::serde_case::RenameRule;
+use crate::observations::{Observations, child_pointer};
+use crate::options::{Options, StringTransform};
+use crate::shape::{Shape, as_generated, describe, strip_option};
+
+/// Rust code generated earlier, updated to fit new input
+#[derive(Debug, PartialEq, Clone)]
+pub struct Merged {
+    pub code: String,
+    /// Fields that are not in the input. They are kept, since they may still be in use.
+    pub removed_fields: Vec<RemovedField>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct RemovedField {
+    /// Where the field would be in the input, with `-` for any array element or map value
+    pub pointer: String,
+    pub type_name: String,
+    pub field_name: String,
+}
+
+impl fmt::Display for RemovedField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}::{} is not in the input",
+            self.pointer, self.type_name, self.field_name
+        )
+    }
+}
+
+const INTEGER_TYPES: &[&str] = &[
+    "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize",
+];
+const FLOAT_TYPES: &[&str] = &["f32", "f64"];
+const STRING_TYPES: &[&str] = &["String", "str", "Cow"];
+const LIST_TYPES: &[&str] = &["Vec", "VecDeque", "HashSet", "BTreeSet", "IndexSet"];
+const MAP_TYPES: &[&str] = &["HashMap", "BTreeMap", "IndexMap"];
+/// Types that deserialize the same way as the type they wrap
+const WRAPPER_TYPES: &[&str] = &["Box", "Rc", "Arc"];
+const ANY_TYPES: &[&str] = &["Value", "RawValue"];
+
+/// Update existing Rust code to fit a shape, starting from the type with the given name
+///
+/// Structs are matched to objects by the serde names of their fields. New fields are added at
+/// the end of their struct and types that do not fit are replaced, while everything else in the
+/// code is left as it is. Any types that are not standard are assumed to fit, since they are
+/// likely to be types written by hand, like enums.
+#[allow(clippy::result_large_err)]
+pub fn merge_rust(
+    existing: &str,
+    name: &str,
+    shape: &Shape,
+    options: Options,
+    observations: Observations,
+) -> Result<Merged, JTError> {
+    let tokens = tokenize(existing).map_err(JTError::RustParsingError)?;
+    let source = parse_source(existing, &tokens).map_err(JTError::RustParsingError)?;
+
+    let mut rust = rust::new_ctxt(options.clone(), observations);
+    // only needed if structs are added
+    let derive_imports = rust::take_imports(&mut rust);
+    for name in source.names.iter() {
+        rust::reserve_type_name(&mut rust, name);
+    }
+
+    let field_visibility = match options.field_visibility {
+        Some(ref visibility) => visibility.clone(),
+        None => options.type_visibility.clone(),
+    };
+    let mut merge = Merge {
+        source,
+        rust,
+        options,
+        field_visibility,
+        edits: Vec::new(),
+        defs: Vec::new(),
+        merged: HashSet::new(),
+        removed_fields: Vec::new(),
+    };
+
+    if merge.source.structs.contains_key(name) {
+        let typ = RustType::Path {
+            name: name.to_string(),
+            args: Vec::new(),
+        };
+        if !merge.fits("", shape, &typ) {
+            return Err(JTError::RustMergeError(format!(
+                "{} is a struct, but the input is {}",
+                name,
+                describe(shape)
+            )));
+        }
+    } else if let Some(alias) = merge.source.aliases.get(name).cloned() {
+        if !merge.fits("", shape, &alias.typ) {
+            merge.replace_type(alias.start, alias.end, "", name, shape);
+        }
+    } else if merge.source.names.iter().any(|existing| existing == name) {
+        // a new struct would clash with it
+        return Err(JTError::RustMergeError(format!(
+            "{} is neither a struct with named fields nor a type alias",
+            name
+        )));
+    } else {
+        let code = rust::root_type(&mut merge.rust, &Root::single(name, shape));
+        merge.defs.push(code);
+    }
+
+    let mut imports = rust::take_imports(&mut merge.rust);
+    if !merge.defs.is_empty() {
+        imports.extend(derive_imports);
+    }
+    imports.sort();
+    imports.dedup();
+    imports.retain(|import| !is_imported(&merge.source.uses, import));
+    if !imports.is_empty() {
+        let lines: Vec<String> = imports
+            .iter()
+            .map(|import| format!("use {};", import))
+            .collect();
+        let edit = match merge.source.uses_end {
+            Some(end) => (end, end, format!("\n{}", lines.join("\n"))),
+            None => (0, 0, format!("{}\n\n", lines.join("\n"))),
+        };
+        merge.edits.push(edit);
+    }
+
+    let mut code = existing.to_string();
+    merge.edits.sort_by_key(|edit| std::cmp::Reverse(edit.0));
+    for (start, end, text) in merge.edits {
+        code.replace_range(start..end, &text);
+    }
+
+    if !merge.defs.is_empty() {
+        code.truncate(code.trim_end().len());
+        if !code.is_empty() {
+            code += "\n\n";
+        }
+        code += &merge.defs.join("\n\n");
+        code += "\n";
+    }
+
+    Ok(Merged {
+        code,
+        removed_fields: merge.removed_fields,
+    })
+}
+
+struct Merge {
+    source: Source,
+    rust: Ctxt,
+    options: Options,
+    field_visibility: String,
+    /// Replacements of byte ranges in the existing code
+    edits: Vec<(usize, usize, Code)>,
+    /// New types, to add at the end
+    defs: Vec<Code>,
+    merged: HashSet<String>,
+    removed_fields: Vec<RemovedField>,
+}
+
+impl Merge {
+    /// Whether values of a shape can be deserialized as an existing type
+    ///
+    /// Existing structs used for objects are merged with the shape of the object along the way.
+    fn fits(&mut self, pointer: &str, shape: &Shape, typ: &RustType) -> bool {
+        use crate::shape::Shape::*;
+        let (name, args) = match typ {
+            RustType::Path { name, args } => (name.as_str(), args),
+            RustType::Tuple(types) => {
+                return match as_generated(shape) {
+                    Tuple(shapes, _) if shapes.len() == types.len() => {
+                        let pointer = child_pointer(pointer, "-");
+                        shapes
+                            .iter()
+                            .zip(types.iter())
+                            .all(|(shape, typ)| self.fits(&pointer, shape, typ))
+                    }
+                    _ => matches!(shape, Any | Bottom | Null | Opaque(_)),
+                };
+            }
+            RustType::Other => return true,
+        };
+        let arg = |i: usize| args.get(i).unwrap_or(&RustType::Other);
+
+        if WRAPPER_TYPES.contains(&name) {
+            return self.fits(pointer, shape, arg(0));
+        }
+        if ANY_TYPES.contains(&name) {
+            return true;
+        }
+        if let Optional(_) | Nullable(_) = shape {
+            return if name == "Option" {
+                self.fits(pointer, strip_option(shape), strip_option_type(arg(0)))
+            } else {
+                // like the generated code, which relies on `#[serde(default)]` instead
+                self.options.use_default_for_missing_fields
+                    && self.fits(pointer, strip_option(shape), typ)
+            };
+        }
+        if name == "Option" {
+            return self.fits(pointer, shape, arg(0));
+        }
+
+        let is_struct = self.source.structs.contains_key(name);
+        let is_standard = [
+            INTEGER_TYPES,
+            FLOAT_TYPES,
+            STRING_TYPES,
+            LIST_TYPES,
+            MAP_TYPES,
+            &["bool"],
+        ]
+        .iter()
+        .any(|names| names.contains(&name));
+        if !is_struct && !is_standard {
+            return true;
+        }
+
+        match shape {
+            Any | Bottom | Null | Opaque(_) | TypeParam(..) | SelfRef => true,
+            Optional(_) | Nullable(_) => unreachable!("handled above"),
+            Bool => name == "bool",
+            StringT => STRING_TYPES.contains(&name),
+            // an integer is also a valid float
+            Integer => INTEGER_TYPES.contains(&name) || FLOAT_TYPES.contains(&name),
+            Floating => FLOAT_TYPES.contains(&name),
+            VecT { elem_type } => {
+                LIST_TYPES.contains(&name)
+                    && self.fits(&child_pointer(pointer, "-"), elem_type, arg(0))
+            }
+            Tuple(..) => match as_generated(shape) {
+                VecT { elem_type } => {
+                    LIST_TYPES.contains(&name)
+                        && self.fits(&child_pointer(pointer, "-"), &elem_type, arg(0))
+                }
+                _ => false,
+            },
+            MapT { val_type } => {
+                is_struct
+                    || (MAP_TYPES.contains(&name)
+                        && self.fits(&child_pointer(pointer, "-"), val_type, arg(1)))
+            }
+            Struct { fields } if is_struct => {
+                self.merge_struct(name, fields, pointer);
+                true
+            }
+            Recursive(inner) if is_struct => match &**inner {
+                Struct { fields } => {
+                    self.rust.recursive_types.push(name.to_string());
+                    self.merge_struct(name, fields, pointer);
+                    self.rust.recursive_types.pop();
+                    true
+                }
+                _ => self.fits(pointer, inner, typ),
+            },
+            Recursive(inner) => self.fits(pointer, inner, typ),
+            Struct { .. } => false,
+        }
+    }
+
+    fn merge_struct(&mut self, name: &str, fields: &LinkedHashMap<String, Shape>, pointer: &str) {
+        if !self.merged.insert(name.to_string()) {
+            return;
+        }
+        let parsed = self.source.structs[name].clone();
+        let rename_rule = parsed
+            .serde
+            .rename_all
+            .as_deref()
+            .and_then(|rule| rule.parse::<RenameRule>().ok());
+        let existing: Vec<(String, &ParsedField)> = parsed
+            .fields
+            .iter()
+            .filter(|field| !field.serde.flatten && !field.serde.skip)
+            .map(|field| {
+                let serde_name = match (&field.serde.rename, &rename_rule) {
+                    (Some(rename), _) => rename.clone(),
+                    (None, Some(rule)) => rule.apply_to_field(field.name()),
+                    (None, None) => field.name().to_string(),
+                };
+                (serde_name, field)
+            })
+            .collect();
+
+        let mut field_names: HashSet<String> = parsed
+            .fields
+            .iter()
+            .map(|field| field.ident.clone())
+            .collect();
+        let mut new_fields = Vec::new();
+
+        for (key, shape) in fields.iter() {
+            let field_pointer = child_pointer(pointer, key);
+            let Some((_, field)) = existing.iter().find(|(serde_name, _)| serde_name == key) else {
+                self.rust.pointer = pointer.to_string();
+                // named the way the struct renames fields, which may differ from the options
+                let rename_all = parsed
+                    .serde
+                    .rename_all
+                    .as_deref()
+                    .and_then(StringTransform::parse);
+                let property_name_format =
+                    std::mem::replace(&mut self.rust.options.property_name_format, rename_all);
+                let (code, _, defs) = rust::generate_field(
+                    &mut self.rust,
+                    key,
+                    shape,
+                    &mut field_names,
+                    &self.field_visibility,
+                );
+                self.rust.options.property_name_format = property_name_format;
+                new_fields.push(code);
+                self.add_defs(defs);
+                continue;
+            };
+
+            // A field that became optional or nullable keeps its type, wrapped in an `Option`
+            let is_option = matches!(&field.typ, RustType::Path { name, .. } if name == "Option");
+            // Missing fields are fine with a default, and null is not a valid list anyway
+            let has_default = field.serde.default
+                || parsed.serde.default
+                || self.options.use_default_for_missing_fields
+                || matches!(shape, Shape::Optional(inner) if matches!(**inner, Shape::VecT { .. }));
+            let needs_option = !is_option
+                && match shape {
+                    Shape::Optional(_) => !has_default,
+                    Shape::Nullable(_) => !self.options.use_default_for_missing_fields,
+                    _ => false,
+                };
+            let shape_to_fit = if is_option {
+                shape
+            } else {
+                strip_option(shape)
+            };
+
+            if !self.fits(&field_pointer, shape_to_fit, &field.typ) {
+                self.replace_type(field.type_start, field.type_end, &field_pointer, key, shape);
+            } else if needs_option {
+                let existing_type = &self.source.text[field.type_start..field.type_end];
+                let wrapped = format!("Option<{}>", existing_type);
+                self.edits.push((field.type_start, field.type_end, wrapped));
+            }
+        }
+
+        for (serde_name, field) in existing.iter() {
+            if !fields.contains_key(serde_name) {
+                self.removed_fields.push(RemovedField {
+                    pointer: child_pointer(pointer, serde_name),
+                    type_name: name.to_string(),
+                    field_name: field.ident.clone(),
+                });
+            }
+        }
+
+        if !new_fields.is_empty() {
+            let mut text = String::new();
+            let position = match parsed.fields.last() {
+                Some(last) => {
+                    if !last.has_comma {
+                        text.push(',');
+                    }
+                    last.end
+                }
+                None => parsed.body_start,
+            };
+            text += "\n";
+            text += &new_fields.join("\n");
+            if parsed.fields.is_empty() {
+                text += "\n";
+            }
+            self.edits.push((position, position, text));
+        }
+    }
+
+    /// Replace an existing type with a generated one
+    fn replace_type(&mut self, start: usize, end: usize, pointer: &str, path: &str, shape: &Shape) {
+        self.rust.pointer = pointer.to_string();
+        let (typ, defs) = rust::type_from_shape(&mut self.rust, path, shape);
+        self.edits.push((start, end, typ));
+        self.add_defs(defs);
+    }
+
+    fn add_defs(&mut self, defs: Option<Code>) {
+        if let Some(code) = defs
+            && !code.is_empty()
+        {
+            self.defs.push(code);
+        }
+    }
+}
+
+fn strip_option_type(typ: &RustType) -> &RustType {
+    match typ {
+        RustType::Path { name, args } if name == "Option" => {
+            strip_option_type(args.first().unwrap_or(&RustType::Other))
+        }
+        _ => typ,
+    }
+}
+
+/// Whether a path is imported by one of the `use` declarations, given as their identifiers
+fn is_imported(uses: &[Vec<String>], import: &str) -> bool {
+    let first = import.split("::").next().unwrap_or_default();
+    let last = import.rsplit("::").next().unwrap_or_default();
+    uses.iter().any(|idents| {
+        idents.iter().any(|ident| ident == last)
+            || idents.first().is_some_and(|ident| ident == first)
+                && idents.last().is_some_and(|ident| ident == "*")
+    })
+}
+
+/// The parts of existing code that matter for merging
+#[derive(Debug, Default)]
+struct Source {
+    text: String,
+    structs: HashMap<String, ParsedStruct>,
+    aliases: HashMap<String, TypeAlias>,
+    /// The names of all types, to avoid clashes with new types
+    names: Vec<String>,
+    /// The identifiers in each `use` declaration, with `*` for globs
+    uses: Vec<Vec<String>>,
+    /// The end of the last `use` declaration, where new imports go
+    uses_end: Option<usize>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct ParsedStruct {
+    serde: SerdeAttributes,
+    fields: Vec<ParsedField>,
+    /// Byte offset just inside the opening brace
+    body_start: usize,
+}
+
+#[derive(Debug, Clone)]
+struct ParsedField {
+    ident: String,
+    serde: SerdeAttributes,
+    typ: RustType,
+    type_start: usize,
+    type_end: usize,
+    /// Byte offset after the field, including the comma
+    end: usize,
+    has_comma: bool,
+}
+
+impl ParsedField {
+    /// The name without any `r#` prefix
+    fn name(&self) -> &str {
+        self.ident.trim_start_matches("r#")
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TypeAlias {
+    typ: RustType,
+    start: usize,
+    end: usize,
+}
+
+#[derive(Debug, Default, Clone)]
+struct SerdeAttributes {
+    rename: Option<String>,
+    rename_all: Option<String>,
+    flatten: bool,
+    skip: bool,
+    default: bool,
+}
+
+/// Just enough of a type to see if values fit it
+#[derive(Debug, Clone, PartialEq)]
+enum RustType {
+    /// The last segment of a path, with its type arguments
+    Path {
+        name: String,
+        args: Vec<RustType>,
+    },
+    Tuple(Vec<RustType>),
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Ident(String),
+    /// The contents of a string literal, as written
+    Str(String),
+    Lifetime,
+    /// Numbers and characters
+    Literal,
+    Punct(char),
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    tok: Tok,
+    start: usize,
+    end: usize,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let at = |i: usize| chars.get(i).map(|&(_, c)| c);
+    let offset = |i: usize| chars.get(i).map_or(text.len(), |&(offset, _)| offset);
+    let is_ident_char = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while let Some(c) = at(i) {
+        let start = i;
+        let tok = if c.is_whitespace() {
+            i += 1;
+            continue;
+        } else if c == '/' && at(i + 1) == Some('/') {
+            while at(i).is_some_and(|c| c != '\n') {
+                i += 1;
+            }
+            continue;
+        } else if c == '/' && at(i + 1) == Some('*') {
+            let mut depth = 0;
+            loop {
+                match (at(i), at(i + 1)) {
+                    (Some('/'), Some('*')) => {
+                        depth += 1;
+                        i += 2;
+                    }
+                    (Some('*'), Some('/')) => {
+                        depth -= 1;
+                        i += 2;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    (Some(_), _) => i += 1,
+                    (None, _) => return Err("Unterminated block comment".to_string()),
+                }
+            }
+            continue;
+        } else if c == '"' || (c == 'b' && at(i + 1) == Some('"')) {
+            i += if c == 'b' { 2 } else { 1 };
+            let content_start = i;
+            loop {
+                match at(i) {
+                    Some('\\') => i += 2,
+                    Some('"') => break,
+                    Some(_) => i += 1,
+                    None => return Err("Unterminated string literal".to_string()),
+                }
+            }
+            i += 1;
+            Tok::Str(text[offset(content_start)..offset(i - 1)].to_string())
+        } else if c == 'r' || (c == 'b' && at(i + 1) == Some('r')) {
+            let mut j = if c == 'b' { i + 2 } else { i + 1 };
+            let mut hashes = 0;
+            while at(j) == Some('#') {
+                hashes += 1;
+                j += 1;
+            }
+            if at(j) == Some('"') {
+                let content_start = j + 1;
+                let closing: String = std::iter::once('"')
+                    .chain(std::iter::repeat_n('#', hashes))
+                    .collect();
+                let rest = &text[offset(content_start)..];
+                let len = rest
+                    .find(&closing)
+                    .ok_or("Unterminated raw string literal")?;
+                let content = rest[..len].to_string();
+                let end = offset(content_start) + len + closing.len();
+                while offset(i) < end {
+                    i += 1;
+                }
+                Tok::Str(content)
+            } else if hashes == 1 && is_ident_char(at(j)) {
+                i = j;
+                while is_ident_char(at(i)) {
+                    i += 1;
+                }
+                Tok::Ident(format!("r#{}", &text[offset(j)..offset(i)]))
+            } else {
+                while is_ident_char(at(i)) {
+                    i += 1;
+                }
+                Tok::Ident(text[offset(start)..offset(i)].to_string())
+            }
+        } else if c == '\'' {
+            if at(i + 1) == Some('\\') {
+                i += 3;
+                while at(i).is_some_and(|c| c != '\'') {
+                    i += 1;
+                }
+                i += 1;
+                Tok::Literal
+            } else if at(i + 2) == Some('\'') {
+                i += 3;
+                Tok::Literal
+            } else {
+                i += 1;
+                while is_ident_char(at(i)) {
+                    i += 1;
+                }
+                Tok::Lifetime
+            }
+        } else if c.is_ascii_digit() {
+            while is_ident_char(at(i))
+                || at(i) == Some('.') && at(i + 1).is_some_and(|c| c.is_ascii_digit())
+            {
+                i += 1;
+            }
+            Tok::Literal
+        } else if is_ident_char(Some(c)) {
+            while is_ident_char(at(i)) {
+                i += 1;
+            }
+            Tok::Ident(text[offset(start)..offset(i)].to_string())
+        } else {
+            i += 1;
+            Tok::Punct(c)
+        };
+        tokens.push(Token {
+            tok,
+            start: offset(start),
+            end: offset(i),
+        });
+    }
+    Ok(tokens)
+}
+
+fn is_punct(tokens: &[Token], i: usize, c: char) -> bool {
+    tokens
+        .get(i)
+        .is_some_and(|token| token.tok == Tok::Punct(c))
+}
+
+fn ident(tokens: &[Token], i: usize) -> Option<&str> {
+    match tokens.get(i).map(|token| &token.tok) {
+        Some(Tok::Ident(ident)) => Some(ident),
+        _ => None,
+    }
+}
+
+/// The index of the bracket closing the one at `open`
+fn closing(tokens: &[Token], open: usize) -> Result<usize, String> {
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        match token.tok {
+            Tok::Punct('(' | '[' | '{') => depth += 1,
+            Tok::Punct(')' | ']' | '}') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    Err("Unbalanced brackets".to_string())
+}
+
+/// The index after the angle bracket closing the one at `open`
+fn after_generics(tokens: &[Token], open: usize) -> usize {
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        match token.tok {
+            Tok::Punct('<') => depth += 1,
+            Tok::Punct('>') => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    tokens.len()
+}
+
+fn parse_source(text: &str, tokens: &[Token]) -> Result<Source, String> {
+    let mut source = Source {
+        text: text.to_string(),
+        ..Source::default()
+    };
+    let mut serde = SerdeAttributes::default();
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i].tok {
+            Tok::Punct('#') if is_punct(tokens, i + 1, '[') => {
+                let end = closing(tokens, i + 1)?;
+                serde_attributes(&tokens[i + 2..end], &mut serde);
+                i = end + 1;
+            }
+            Tok::Ident(keyword)
+                if matches!(&**keyword, "struct" | "enum" | "union" | "trait" | "type")
+                    && ident(tokens, i + 1).is_some() =>
+            {
+                let name = ident(tokens, i + 1).unwrap_or_default().to_string();
+                source.names.push(name.clone());
+                i = match &**keyword {
+                    "struct" => {
+                        let (parsed, next) = parse_struct(tokens, i + 2)?;
+                        if let Some(mut parsed) = parsed {
+                            parsed.serde = std::mem::take(&mut serde);
+                            source.structs.insert(name, parsed);
+                        }
+                        next
+                    }
+                    "type" => {
+                        let (alias, next) = parse_type_alias(tokens, i + 2);
+                        if let Some(alias) = alias {
+                            source.aliases.insert(name, alias);
+                        }
+                        next
+                    }
+                    _ => i + 2,
+                };
+                serde = SerdeAttributes::default();
+            }
+            Tok::Ident(keyword) if keyword == "use" => {
+                let mut idents = Vec::new();
+                i += 1;
+                while i < tokens.len() && !is_punct(tokens, i, ';') {
+                    match &tokens[i].tok {
+                        Tok::Ident(ident) => idents.push(ident.clone()),
+                        Tok::Punct('*') => idents.push("*".to_string()),
+                        _ => {}
+                    }
+                    i += 1;
+                }
+                source.uses.push(idents);
+                source.uses_end = tokens.get(i).map(|token| token.end);
+            }
+            Tok::Punct(';' | '{' | '}') => {
+                serde = SerdeAttributes::default();
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    Ok(source)
+}
+
+/// Parse a struct from after its name, if it has named fields
+fn parse_struct(tokens: &[Token], mut i: usize) -> Result<(Option<ParsedStruct>, usize), String> {
+    if is_punct(tokens, i, '<') {
+        i = after_generics(tokens, i);
+    }
+    // skip any where clause
+    while i < tokens.len() && !matches!(tokens[i].tok, Tok::Punct('{' | '(' | ';')) {
+        i += 1;
+    }
+    if !is_punct(tokens, i, '{') {
+        return Ok((None, i));
+    }
+
+    let close = closing(tokens, i)?;
+    let mut parsed = ParsedStruct {
+        body_start: tokens[i].end,
+        ..ParsedStruct::default()
+    };
+    i += 1;
+    while i < close {
+        let mut serde = SerdeAttributes::default();
+        while is_punct(tokens, i, '#') && is_punct(tokens, i + 1, '[') {
+            let end = closing(tokens, i + 1)?;
+            serde_attributes(&tokens[i + 2..end], &mut serde);
+            i = end + 1;
+        }
+        if i >= close {
+            break;
+        }
+        if ident(tokens, i) == Some("pub") {
+            i += 1;
+            if is_punct(tokens, i, '(') {
+                i = closing(tokens, i)? + 1;
+            }
+        }
+        let field_ident = ident(tokens, i)
+            .ok_or_else(|| format!("Expected a field name at byte {}", tokens[i].start))?
+            .to_string();
+        if !is_punct(tokens, i + 1, ':') {
+            return Err(format!("Expected `:` after field {}", field_ident));
+        }
+        i += 2;
+
+        let type_start = i;
+        let mut depth = 0;
+        while i < close && !(depth == 0 && is_punct(tokens, i, ',')) {
+            match tokens[i].tok {
+                Tok::Punct('<' | '(' | '[' | '{') => depth += 1,
+                Tok::Punct('>' | ')' | ']' | '}') => depth -= 1,
+                _ => {}
+            }
+            i += 1;
+        }
+        if i == type_start {
+            return Err(format!("Expected a type for field {}", field_ident));
+        }
+        let has_comma = i < close;
+        parsed.fields.push(ParsedField {
+            ident: field_ident,
+            serde,
+            typ: parse_type(&tokens[type_start..i]),
+            type_start: tokens[type_start].start,
+            type_end: tokens[i - 1].end,
+            end: if has_comma {
+                tokens[i].end
+            } else {
+                tokens[i - 1].end
+            },
+            has_comma,
+        });
+        i += 1;
+    }
+    Ok((Some(parsed), close + 1))
+}
+
+/// Parse a type alias from after its name
+fn parse_type_alias(tokens: &[Token], mut i: usize) -> (Option<TypeAlias>, usize) {
+    if is_punct(tokens, i, '<') {
+        i = after_generics(tokens, i);
+    }
+    if !is_punct(tokens, i, '=') {
+        return (None, i);
+    }
+    let start = i + 1;
+    let mut end = start;
+    while end < tokens.len() && !is_punct(tokens, end, ';') {
+        end += 1;
+    }
+    if end == start {
+        return (None, end);
+    }
+    let alias = TypeAlias {
+        typ: parse_type(&tokens[start..end]),
+        start: tokens[start].start,
+        end: tokens[end - 1].end,
+    };
+    (Some(alias), end + 1)
+}
+
+fn serde_attributes(tokens: &[Token], serde: &mut SerdeAttributes) {
+    if ident(tokens, 0) != Some("serde") {
+        return;
+    }
+    for i in 1..tokens.len() {
+        match ident(tokens, i) {
+            Some("rename") => serde.rename = string_value(tokens, i).or(serde.rename.take()),
+            Some("rename_all") => {
+                serde.rename_all = string_value(tokens, i).or(serde.rename_all.take())
+            }
+            Some("flatten") => serde.flatten = true,
+            Some("skip" | "skip_deserializing") => serde.skip = true,
+            Some("default") => serde.default = true,
+            _ => {}
+        }
+    }
+}
+
+/// The value of `name = "value"` or `name(deserialize = "value")` at `i`
+fn string_value(tokens: &[Token], i: usize) -> Option<String> {
+    if is_punct(tokens, i + 1, '=') {
+        return match tokens.get(i + 2).map(|token| &token.tok) {
+            Some(Tok::Str(value)) => Some(value.clone()),
+            _ => None,
+        };
+    }
+    if is_punct(tokens, i + 1, '(') {
+        let close = closing(tokens, i + 1).ok()?;
+        let j = (i + 2..close).find(|&j| ident(tokens, j) == Some("deserialize"))?;
+        return string_value(tokens, j);
+    }
+    None
+}
+
+fn parse_type(tokens: &[Token]) -> RustType {
+    let mut parser = TypeParser { tokens, pos: 0 };
+    let typ = parser.typ();
+    if parser.pos == tokens.len() {
+        typ
+    } else {
+        RustType::Other
+    }
+}
+
+struct TypeParser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+}
+
+impl TypeParser<'_> {
+    fn typ(&mut self) -> RustType {
+        // references are deserialized like what they refer to
+        while is_punct(self.tokens, self.pos, '&')
+            || matches!(self.tok(), Some(Tok::Lifetime))
+            || ident(self.tokens, self.pos) == Some("mut")
+        {
+            self.pos += 1;
+        }
+
+        if is_punct(self.tokens, self.pos, '(') {
+            self.pos += 1;
+            let types = match self.list(')') {
+                Some(types) => types,
+                None => return RustType::Other,
+            };
+            return RustType::Tuple(types);
+        }
+
+        let mut name = match ident(self.tokens, self.pos) {
+            Some("dyn" | "impl") | None => return RustType::Other,
+            Some(name) => name.to_string(),
+        };
+        self.pos += 1;
+        while is_punct(self.tokens, self.pos, ':') && is_punct(self.tokens, self.pos + 1, ':') {
+            match ident(self.tokens, self.pos + 2) {
+                Some(segment) => name = segment.to_string(),
+                None => return RustType::Other,
+            }
+            self.pos += 3;
+        }
+
+        let mut args = Vec::new();
+        if is_punct(self.tokens, self.pos, '<') {
+            self.pos += 1;
+            args = match self.list('>') {
+                Some(args) => args,
+                None => return RustType::Other,
+            };
+        }
+        RustType::Path { name, args }
+    }
+
+    /// Comma separated types up to a closing bracket, skipping lifetimes
+    fn list(&mut self, close: char) -> Option<Vec<RustType>> {
+        let mut types = Vec::new();
+        loop {
+            if is_punct(self.tokens, self.pos, close) {
+                self.pos += 1;
+                return Some(types);
+            }
+            if matches!(self.tok(), Some(Tok::Lifetime)) {
+                self.pos += 1;
+            } else {
+                let start = self.pos;
+                types.push(self.typ());
+                if self.pos == start {
+                    return None;
+                }
+            }
+            if is_punct(self.tokens, self.pos, ',') {
+                self.pos += 1;
+            } else if !is_punct(self.tokens, self.pos, close) {
+                return None;
+            }
+        }
+    }
+
+    fn tok(&self) -> Option<&Tok> {
+        self.tokens.get(self.pos).map(|token| &token.tok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Source {
+        parse_source(text, &tokenize(text).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn parses_fields_and_attributes() {
+        let source = parse(
+            r##"
+            /// A point, with "quotes" and a 'c'
+            #[derive(Debug)]
+            #[serde(rename_all = "camelCase")]
+            pub struct Point<'a> {
+                #[serde(rename(deserialize = "X"))]
+                pub x: Option<&'a str>,
+                pub(crate) r#type: std::collections::HashMap<String, (i64, f64)>,
+                /* no comma */ extra: Vec<Box<Point<'a>>>
+            }
+            pub type Points = Vec<Point<'static>>;
+            "##,
+        );
+        assert_eq!(source.names, vec!["Point", "Points"]);
+        let point = &source.structs["Point"];
+        assert_eq!(point.serde.rename_all.as_deref(), Some("camelCase"));
+        let fields: Vec<_> = point
+            .fields
+            .iter()
+            .map(|field| (field.name(), field.serde.rename.as_deref(), field.has_comma))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("x", Some("X"), true),
+                ("type", None, true),
+                ("extra", None, false)
+            ]
+        );
+        let path = |name: &str, args: Vec<RustType>| RustType::Path {
+            name: name.to_string(),
+            args,
+        };
+        assert_eq!(
+            point.fields[1].typ,
+            path(
+                "HashMap",
+                vec![
+                    path("String", vec![]),
+                    RustType::Tuple(vec![path("i64", vec![]), path("f64", vec![])])
+                ]
+            )
+        );
+        assert_eq!(
+            source.aliases["Points"].typ,
+            path("Vec", vec![path("Point", vec![])])
+        );
+    }
+}
@@ -42,6 +42,16 @@ fn type_from_shape(ctxt: &mut Ctxt, shape: &Shape) -> Value {
         Struct { fields } => generate_struct_from_field_shapes(ctxt, fields),
         MapT { val_type: v } => generate_map_type(ctxt, v),
         Opaque(t) => Value::String(t.to_string()),
+        Recursive(e) => Value::Object(string_hashmap! {
+            "__type__" => Value::Str("recursive"),
+            "item" => type_from_shape(ctxt, e),
+        }),
+        SelfRef => Value::Str("self"),
+        TypeParam(name, arg) => Value::Object(string_hashmap! {
+            "__type__" => Value::Str("type_parameter"),
+            "name" => Value::String(name.clone()),
+            "item" => type_from_shape(ctxt, arg),
+        }),
         Optional(e) => Value::Object(string_hashmap! {
             "__type__" => Value::Str("optional"),
             "item" => type_from_shape(ctxt, e),
@@ -0,0 +1,340 @@
+use linked_hash_map::LinkedHashMap;
+use std::collections::HashSet;
+
+use crate::options::{Options, SqlDialect};
+use crate::shape::{self, Shape};
+use crate::util::snake_case;
+
+struct Ctxt {
+    options: Options,
+    table_names: HashSet<String>,
+    tables: Vec<Code>,
+}
+
+pub type Ident = String;
+pub type Code = String;
+
+/// How many levels of nested objects are flattened into prefixed columns
+/// before falling back to a JSON column
+const MAX_FLATTEN_DEPTH: usize = 2;
+
+struct Table {
+    name: Ident,
+    column_names: HashSet<String>,
+    columns: Vec<Column>,
+    child_tables: Vec<(String, LinkedHashMap<String, Shape>)>,
+}
+
+struct Column {
+    name: Ident,
+    sql_type: String,
+    not_null: bool,
+    constraint: Option<String>,
+}
+
+/// The column a child table refers to with its foreign key
+struct ParentKey {
+    table: Ident,
+    column: Ident,
+    sql_type: String,
+}
+
+pub fn sql_ddl(name: &str, shape: &Shape, options: Options) -> Code {
+    let mut ctxt = Ctxt {
+        options,
+        table_names: HashSet::new(),
+        tables: Vec::new(),
+    };
+
+    let root_fields = match root_fields(shape) {
+        Some(fields) => fields,
+        None => {
+            // Not an object, or list of objects, so just put the whole value in a single column
+            let mut fields = LinkedHashMap::new();
+            fields.insert("value".to_string(), shape.clone());
+            fields
+        }
+    };
+    generate_table(&mut ctxt, name, &root_fields, None);
+
+    ctxt.tables.join("\n\n")
+}
+
+/// The fields of the root object, or of the objects in a root list
+fn root_fields(shape: &Shape) -> Option<LinkedHashMap<String, Shape>> {
+    match shape {
+        Shape::Struct { fields } => Some(fields.clone()),
+        Shape::VecT { elem_type: e } => root_fields(e),
+        Shape::Tuple(shapes, _) => root_fields(&shape::fold_shapes(shapes.clone())),
+        _ => None,
+    }
+}
+
+fn generate_table(
+    ctxt: &mut Ctxt,
+    path: &str,
+    fields: &LinkedHashMap<String, Shape>,
+    parent: Option<ParentKey>,
+) {
+    let table_name = unique_name(&snake_case(path), &ctxt.table_names, "table");
+    ctxt.table_names.insert(table_name.clone());
+
+    let mut table = Table {
+        name: table_name,
+        column_names: HashSet::new(),
+        columns: Vec::new(),
+        child_tables: Vec::new(),
+    };
+
+    if let Some(ref parent) = parent {
+        let column_name = format!("{}_{}", parent.table, parent.column);
+        add_column(
+            &mut table,
+            &column_name,
+            parent.sql_type.clone(),
+            true,
+            Some(format!(
+                "REFERENCES {} ({})",
+                quote_name(&parent.table),
+                quote_name(&parent.column)
+            )),
+        );
+    }
+
+    add_columns(ctxt, &mut table, "", fields, 0, false);
+
+    let key = if table.child_tables.is_empty() {
+        None
+    } else {
+        Some(primary_key(ctxt, &mut table, fields))
+    };
+
+    let columns: Vec<Code> = table
+        .columns
+        .iter()
+        .map(|column| {
+            let mut code = format!("    {} {}", quote_name(&column.name), column.sql_type);
+            if column.not_null {
+                code += " NOT NULL";
+            }
+            if let Some(ref constraint) = column.constraint {
+                code += " ";
+                code += constraint;
+            }
+            code
+        })
+        .collect();
+
+    ctxt.tables.push(format!(
+        "CREATE TABLE {} (\n{}\n);",
+        quote_name(&table.name),
+        columns.join(",\n")
+    ));
+
+    // Child tables are created after the parent table, since they refer to it
+    if let Some(key) = key {
+        for (child_path, child_fields) in table.child_tables.iter() {
+            let parent_key = ParentKey {
+                table: table.name.clone(),
+                column: key.0.clone(),
+                sql_type: key.1.clone(),
+            };
+            generate_table(
+                ctxt,
+                &format!("{}_{}", table.name, child_path),
+                child_fields,
+                Some(parent_key),
+            );
+        }
+    }
+}
+
+/// Use an "id" field of the object as primary key if there is a suitable one,
+/// or add a generated key column if not. Returns the name and type of the key column.
+fn primary_key(
+    ctxt: &Ctxt,
+    table: &mut Table,
+    fields: &LinkedHashMap<String, Shape>,
+) -> (Ident, String) {
+    if let Some(Shape::Integer | Shape::StringT) = fields.get("id")
+        && let Some(column) = table.columns.iter_mut().find(|c| c.name == "id")
+    {
+        column.not_null = false; // implied by the primary key
+        column.constraint = Some("PRIMARY KEY".into());
+        return (column.name.clone(), column.sql_type.clone());
+    }
+
+    let column_name = unique_name("id", &table.column_names, "id");
+    table.column_names.insert(column_name.clone());
+    let (column_type, reference_type) = match ctxt.options.sql_dialect {
+        SqlDialect::Postgres => ("BIGSERIAL", "BIGINT"),
+        SqlDialect::Sqlite => ("INTEGER", "INTEGER"),
+    };
+    table.columns.insert(
+        0,
+        Column {
+            name: column_name.clone(),
+            sql_type: column_type.into(),
+            not_null: false,
+            constraint: Some("PRIMARY KEY".into()),
+        },
+    );
+    (column_name, reference_type.into())
+}
+
+fn add_columns(
+    ctxt: &Ctxt,
+    table: &mut Table,
+    prefix: &str,
+    fields: &LinkedHashMap<String, Shape>,
+    depth: usize,
+    nullable: bool,
+) {
+    for (name, shape) in fields.iter() {
+        let column_path = if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}_{}", prefix, name)
+        };
+
+        let (is_nullable, inner) = strip_nullability(shape);
+        let nullable = nullable || is_nullable || matches!(inner, Shape::Any | Shape::Null);
+
+        match inner {
+            Shape::Bool | Shape::StringT | Shape::Integer | Shape::Floating | Shape::Opaque(_) => {
+                let sql_type = scalar_type(ctxt, inner);
+                add_column(table, &column_path, sql_type, !nullable, None);
+            }
+            Shape::Struct { fields } if depth < MAX_FLATTEN_DEPTH => {
+                add_columns(ctxt, table, &column_path, fields, depth + 1, nullable);
+            }
+            _ => match list_of_objects(inner) {
+                Some(fields) => table.child_tables.push((column_path, fields.clone())),
+                None => {
+                    let sql_type = json_type(ctxt);
+                    add_column(table, &column_path, sql_type, !nullable, None);
+                }
+            },
+        }
+    }
+}
+
+fn add_column(
+    table: &mut Table,
+    path: &str,
+    sql_type: String,
+    not_null: bool,
+    constraint: Option<String>,
+) {
+    let name = unique_name(&snake_case(path), &table.column_names, "column");
+    table.column_names.insert(name.clone());
+    table.columns.push(Column {
+        name,
+        sql_type,
+        not_null,
+        constraint,
+    });
+}
+
+fn strip_nullability(shape: &Shape) -> (bool, &Shape) {
+    match shape {
+        Shape::Optional(inner) | Shape::Nullable(inner) => (true, strip_nullability(inner).1),
+        _ => (false, shape),
+    }
+}
+
+fn list_of_objects(shape: &Shape) -> Option<&LinkedHashMap<String, Shape>> {
+    let elem_type = match shape {
+        Shape::VecT { elem_type } => elem_type.as_ref(),
+        _ => return None,
+    };
+    match strip_nullability(elem_type).1 {
+        Shape::Struct { fields } => Some(fields),
+        _ => None,
+    }
+}
+
+fn scalar_type(ctxt: &Ctxt, shape: &Shape) -> String {
+    let sql_type = match (shape, &ctxt.options.sql_dialect) {
+        (Shape::Bool, SqlDialect::Postgres) => "BOOLEAN",
+        (Shape::Bool, SqlDialect::Sqlite) => "INTEGER",
+        (Shape::StringT, _) => "TEXT",
+        (Shape::Integer, SqlDialect::Postgres) => "BIGINT",
+        (Shape::Integer, SqlDialect::Sqlite) => "INTEGER",
+        (Shape::Floating, SqlDialect::Postgres) => "DOUBLE PRECISION",
+        (Shape::Floating, SqlDialect::Sqlite) => "REAL",
+        (Shape::Opaque(t), _) => return t.clone(),
+        _ => return json_type(ctxt),
+    };
+    sql_type.into()
+}
+
+fn json_type(ctxt: &Ctxt) -> String {
+    match ctxt.options.sql_dialect {
+        SqlDialect::Postgres => "JSONB".into(),
+        // SQLite stores JSON as text, and a "JSON" column would get numeric affinity
+        SqlDialect::Sqlite => "TEXT".into(),
+    }
+}
+
+fn unique_name(name: &str, used_names: &HashSet<String>, default_name: &str) -> Ident {
+    let mut output_name = name.to_string();
+    if output_name.is_empty() {
+        output_name.push_str(default_name);
+    }
+    if output_name.starts_with(|c: char| c.is_ascii_digit()) {
+        output_name = String::from("n") + &output_name;
+    }
+    if !used_names.contains(&output_name) {
+        return output_name;
+    }
+    for n in 2.. {
+        let temp = format!("{}{}", output_name, n);
+        if !used_names.contains(&temp) {
+            return temp;
+        }
+    }
+    unreachable!()
+}
+
+// Words reserved in PostgreSQL or SQLite that are likely to show up as field names
+#[rustfmt::skip]
+const SQL_RESERVED_WORDS: &[&str] = &[
+    "all", "and", "as", "asc", "between", "by", "case", "check", "collate", "column",
+    "constraint", "create", "default", "delete", "desc", "distinct", "drop", "else", "end",
+    "except", "exists", "foreign", "from", "group", "having", "in", "index", "insert", "into",
+    "is", "join", "key", "like", "limit", "not", "null", "offset", "on", "or", "order",
+    "primary", "references", "select", "set", "table", "then", "to", "union", "unique",
+    "update", "user", "using", "values", "when", "where", "with",
+];
+
+fn quote_name(name: &str) -> String {
+    if SQL_RESERVED_WORDS.contains(&name) {
+        format!("\"{}\"", name)
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(test)]
+mod sql_ddl_tests {
+    use super::*;
+
+    #[test]
+    fn quotes_reserved_words() {
+        assert_eq!(quote_name("order"), "\"order\"");
+        assert_eq!(quote_name("order_id"), "order_id");
+    }
+
+    #[test]
+    fn root_fields_of_list() {
+        let fields = LinkedHashMap::new();
+        let shape = Shape::VecT {
+            elem_type: Box::new(Shape::Struct {
+                fields: fields.clone(),
+            }),
+        };
+        assert_eq!(root_fields(&shape), Some(fields));
+        assert_eq!(root_fields(&Shape::Integer), None);
+    }
+}
@@ -0,0 +1,339 @@
+use linked_hash_map::LinkedHashMap;
+
+use crate::generation::Root;
+use crate::generation::generator::{Definition, FieldDef, Generator, TypeDef, definitions};
+use crate::observations::Observations;
+use crate::options::Options;
+use crate::util::{lower_camel_case, snake_case};
+
+pub type Code = String;
+
+/// Type names for the `type` of fields, in a notation that is easy to map to other languages
+struct TemplateTypes;
+
+const PRIMITIVES: &[&str] = &["any", "boolean", "string", "integer", "number"];
+
+impl Generator for TemplateTypes {
+    fn any_type(&self) -> String {
+        "any".to_string()
+    }
+
+    fn bool_type(&self) -> String {
+        "boolean".to_string()
+    }
+
+    fn string_type(&self) -> String {
+        "string".to_string()
+    }
+
+    fn integer_type(&self) -> String {
+        "integer".to_string()
+    }
+
+    fn floating_type(&self) -> String {
+        "number".to_string()
+    }
+
+    fn list_type(&self, elem_type: &str) -> String {
+        format!("{}[]", elem_type)
+    }
+
+    fn map_type(&self, value_type: &str) -> String {
+        format!("map<{}>", value_type)
+    }
+
+    fn nullable_type(&self, inner: &str) -> String {
+        format!("{}?", inner)
+    }
+
+    fn field_name(&self, json_name: &str) -> String {
+        snake_case(json_name)
+    }
+
+    fn type_definition(&self, _def: &TypeDef) -> Code {
+        unreachable!("templates are rendered from the definitions")
+    }
+
+    fn type_alias(&self, _name: &str, _typ: &str) -> Code {
+        unreachable!("templates are rendered from the definitions")
+    }
+}
+
+pub fn template_output(
+    template: &str,
+    roots: &[Root],
+    options: &Options,
+    observations: &Observations,
+) -> Result<Code, String> {
+    let nodes = parse(template)?;
+
+    let mut types = Vec::new();
+    let mut aliases = Vec::new();
+    for def in definitions(&TemplateTypes, roots, options, observations) {
+        match def {
+            Definition::Type(def) => types.push(type_data(def)),
+            Definition::Alias { name, typ } => {
+                let mut alias = type_data_for(&typ);
+                alias.insert("name", Data::Str(name));
+                aliases.push(Data::Map(alias));
+            }
+        }
+    }
+
+    let mut root = LinkedHashMap::new();
+    root.insert("types", list(types));
+    root.insert("aliases", list(aliases));
+
+    let mut output = String::new();
+    render(&nodes, &mut vec![&Data::Map(root)], &mut output)?;
+    Ok(output)
+}
+
+/// The values a template is rendered with
+enum Data {
+    Str(String),
+    Bool(bool),
+    List(Vec<Data>),
+    Map(LinkedHashMap<&'static str, Data>),
+}
+
+fn type_data(def: TypeDef) -> Data {
+    let mut map = LinkedHashMap::new();
+    map.insert("name", Data::Str(def.name));
+    map.insert("recursive", Data::Bool(def.is_recursive));
+    map.insert(
+        "fields",
+        list(def.fields.into_iter().map(field_data).collect()),
+    );
+    Data::Map(map)
+}
+
+fn field_data(field: FieldDef) -> Data {
+    let mut map = type_data_for(&field.typ);
+    map.insert("camel_name", Data::Str(lower_camel_case(&field.name)));
+    map.insert("renamed", Data::Bool(field.name != field.json_name));
+    map.insert("name", Data::Str(field.name));
+    map.insert("json_name", Data::Str(field.json_name));
+    map.insert("optional", Data::Bool(field.optional));
+    map.insert(
+        "doc",
+        Data::List(field.doc_lines.into_iter().map(Data::Str).collect()),
+    );
+    Data::Map(map)
+}
+
+/// The parts of a type in the notation of `TemplateTypes`, for fields and aliases
+fn type_data_for(typ: &str) -> LinkedHashMap<&'static str, Data> {
+    let base_type = typ.strip_suffix('?').unwrap_or(typ);
+    let kind = if base_type.ends_with("[]") {
+        "list"
+    } else if base_type.starts_with("map<") {
+        "map"
+    } else if PRIMITIVES.contains(&base_type) {
+        base_type
+    } else {
+        "object"
+    };
+
+    // the named type used, directly or as the element of lists and maps
+    let mut inner = base_type;
+    loop {
+        inner = inner.strip_suffix('?').unwrap_or(inner);
+        if let Some(elem) = inner.strip_suffix("[]") {
+            inner = elem;
+        } else if let Some(value) = inner.strip_prefix("map<") {
+            inner = value.strip_suffix('>').unwrap_or(value);
+        } else {
+            break;
+        }
+    }
+    let type_ref = if PRIMITIVES.contains(&inner) {
+        ""
+    } else {
+        inner
+    };
+
+    let mut map = LinkedHashMap::new();
+    map.insert("type", Data::Str(typ.to_string()));
+    map.insert("base_type", Data::Str(base_type.to_string()));
+    map.insert("kind", Data::Str(kind.to_string()));
+    map.insert("nullable", Data::Bool(base_type != typ));
+    map.insert("type_ref", Data::Str(type_ref.to_string()));
+    map
+}
+
+/// A list, with `first` and `last` set on each element to help with separators
+fn list(mut items: Vec<Data>) -> Data {
+    let len = items.len();
+    for (i, item) in items.iter_mut().enumerate() {
+        if let Data::Map(map) = item {
+            map.insert("first", Data::Bool(i == 0));
+            map.insert("last", Data::Bool(i + 1 == len));
+        }
+    }
+    Data::List(items)
+}
+
+/// A parsed template, in a subset of Mustache without HTML escaping
+enum Node {
+    Text(String),
+    Var(String),
+    Section {
+        name: String,
+        inverted: bool,
+        children: Vec<Node>,
+    },
+}
+
+fn parse(template: &str) -> Result<Vec<Node>, String> {
+    // each open section, with the nodes parsed so far in it
+    let mut stack: Vec<(String, bool, Vec<Node>)> = vec![(String::new(), false, Vec::new())];
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..]
+            .find("}}")
+            .map(|end| start + end)
+            .ok_or_else(|| format!("Unclosed tag at line {}", line_of(template, rest, start)))?;
+        let tag = rest[start + 2..end].trim();
+        let sigil = tag.chars().next().filter(|c| "#^/!".contains(*c));
+        let name = tag[sigil.map_or(0, |_| 1)..].trim().to_string();
+
+        let mut text = &rest[..start];
+        let mut after = &rest[end + 2..];
+        // tags other than variables on a line of their own do not leave an empty line
+        if sigil.is_some() {
+            let line_start = text.rfind('\n').map_or(0, |i| i + 1);
+            let line_end = after.find('\n').map_or(after.len(), |i| i + 1);
+            let starts_line = line_start > 0 || is_line_start(template, rest);
+            if starts_line
+                && text[line_start..].trim().is_empty()
+                && after[..line_end].trim().is_empty()
+            {
+                text = &text[..line_start];
+                after = &after[line_end..];
+            }
+        }
+
+        let nodes = &mut stack.last_mut().unwrap().2;
+        if !text.is_empty() {
+            nodes.push(Node::Text(text.to_string()));
+        }
+        match sigil {
+            Some('!') => {}
+            Some('#') | Some('^') => stack.push((name, sigil == Some('^'), Vec::new())),
+            Some('/') => {
+                let (open, inverted, children) = stack.pop().unwrap();
+                if stack.is_empty() || open != name {
+                    return Err(format!(
+                        "Unexpected {{{{/{}}}}} at line {}",
+                        name,
+                        line_of(template, rest, start)
+                    ));
+                }
+                stack.last_mut().unwrap().2.push(Node::Section {
+                    name,
+                    inverted,
+                    children,
+                });
+            }
+            _ => nodes.push(Node::Var(name)),
+        }
+        rest = after;
+    }
+
+    if stack.len() > 1 {
+        return Err(format!("Unclosed section {}", stack.pop().unwrap().0));
+    }
+    let mut nodes = stack.pop().unwrap().2;
+    if !rest.is_empty() {
+        nodes.push(Node::Text(rest.to_string()));
+    }
+    Ok(nodes)
+}
+
+/// Whether `rest` starts at the start of a line of the whole template
+fn is_line_start(template: &str, rest: &str) -> bool {
+    let offset = template.len() - rest.len();
+    offset == 0 || template[..offset].ends_with('\n')
+}
+
+fn line_of(template: &str, rest: &str, index: usize) -> usize {
+    let offset = template.len() - rest.len() + index;
+    template[..offset].matches('\n').count() + 1
+}
+
+fn render<'a>(
+    nodes: &'a [Node],
+    context: &mut Vec<&'a Data>,
+    out: &mut String,
+) -> Result<(), String> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Var(name) => match lookup(context, name)? {
+                Data::Str(s) => out.push_str(s),
+                Data::Bool(b) => out.push_str(&b.to_string()),
+                Data::List(_) | Data::Map(_) => {
+                    return Err(format!(
+                        "{} is not text, but can be used as a section",
+                        name
+                    ));
+                }
+            },
+            Node::Section {
+                name,
+                inverted,
+                children,
+            } => {
+                let data = lookup(context, name)?;
+                if *inverted {
+                    if !is_truthy(data) {
+                        render(children, context, out)?;
+                    }
+                    continue;
+                }
+                match data {
+                    Data::List(items) => {
+                        for item in items {
+                            context.push(item);
+                            render(children, context, out)?;
+                            context.pop();
+                        }
+                    }
+                    _ if is_truthy(data) => {
+                        context.push(data);
+                        render(children, context, out)?;
+                        context.pop();
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_truthy(data: &Data) -> bool {
+    match data {
+        Data::Str(s) => !s.is_empty(),
+        Data::Bool(b) => *b,
+        Data::List(items) => !items.is_empty(),
+        Data::Map(_) => true,
+    }
+}
+
+/// Look up a name in the innermost context that has it. `.` is the innermost context itself.
+fn lookup<'a>(context: &[&'a Data], name: &str) -> Result<&'a Data, String> {
+    if name == "." {
+        return Ok(context.last().unwrap());
+    }
+    context
+        .iter()
+        .rev()
+        .find_map(|data| match data {
+            Data::Map(map) => map.get(name),
+            _ => None,
+        })
+        .ok_or_else(|| format!("Unknown name {}", name))
+}
@@ -1,116 +1,114 @@
-use linked_hash_map::LinkedHashMap;
 use std::collections::HashSet;
 
+use crate::generation::Root;
+use crate::generation::generator::{self, Generator, TypeDef};
+use crate::observations::{Observations, block_doc_comment};
 use crate::options::Options;
-use crate::shape::{self, Shape};
-use crate::to_singular::to_singular;
+use crate::shape::Shape;
 use crate::util::type_case;
 
-pub struct Ctxt {
+pub type Ident = String;
+pub type Code = String;
+
+/// Interfaces, with type aliases for roots that are not objects
+pub struct TypeScript;
+
+pub fn typescript_types(
+    name: &str,
+    shape: &Shape,
     options: Options,
-    type_names: HashSet<String>,
-    created_interfaces: Vec<(Shape, Ident)>,
+    observations: Observations,
+) -> Code {
+    typescript_types_for_roots(&[Root::single(name, shape)], options, observations)
 }
 
-pub type Ident = String;
-pub type Code = String;
+pub fn typescript_types_for_roots(
+    roots: &[Root],
+    options: Options,
+    observations: Observations,
+) -> Code {
+    generator::generate(&TypeScript, roots, &options, &observations)
+}
 
-pub fn typescript_types(name: &str, shape: &Shape, options: Options) -> Code {
-    let mut ctxt = Ctxt {
-        options,
-        type_names: HashSet::new(),
-        created_interfaces: Vec::new(),
-    };
+impl Generator for TypeScript {
+    fn any_type(&self) -> Ident {
+        "any".into()
+    }
 
-    if !matches!(shape, Shape::Struct { .. }) {
-        // reserve the requested name
-        ctxt.type_names.insert(name.to_string());
+    fn bool_type(&self) -> Ident {
+        "boolean".into()
     }
 
-    let (ident, code) = type_from_shape(&mut ctxt, name, shape);
-    let mut code = code.unwrap_or_default();
+    fn string_type(&self) -> Ident {
+        "string".into()
+    }
 
-    if ident != name {
-        code = format!("export type {} = {};\n\n", name, ident) + &code;
+    fn integer_type(&self) -> Ident {
+        "number".into()
     }
 
-    code
-}
+    fn floating_type(&self) -> Ident {
+        "number".into()
+    }
 
-fn type_from_shape(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> (Ident, Option<Code>) {
-    use crate::shape::Shape::*;
-    match shape {
-        Null | Any | Bottom => ("any".into(), None),
-        Bool => ("boolean".into(), None),
-        StringT => ("string".into(), None),
-        Integer => ("number".into(), None),
-        Floating => ("number".into(), None),
-        Tuple(shapes, _n) => {
-            let folded = shape::fold_shapes(shapes.clone());
-            if folded == Any && shapes.iter().any(|s| s != &Any) {
-                generate_tuple_type(ctxt, path, shapes)
-            } else {
-                generate_vec_type(ctxt, path, &folded)
-            }
-        }
-        VecT { elem_type: e } => generate_vec_type(ctxt, path, e),
-        Struct { fields } => generate_interface_type(ctxt, path, fields, shape),
-        MapT { val_type: v } => generate_map_type(ctxt, path, v),
-        Opaque(t) => (t.clone(), None),
-        Optional(e) => {
-            let (inner, defs) = type_from_shape(ctxt, path, e);
-            if ctxt.options.use_default_for_missing_fields {
-                (inner, defs)
-            } else {
-                (format!("{} | undefined", inner), defs)
-            }
-        }
-        Nullable(e) => {
-            let (inner, defs) = type_from_shape(ctxt, path, e);
-            (format!("{} | null", inner), defs)
-        }
+    fn list_type(&self, elem_type: &str) -> Ident {
+        format!("{}[]", elem_type)
     }
-}
 
-fn generate_vec_type(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> (Ident, Option<Code>) {
-    let singular = to_singular(path);
-    let (inner, defs) = type_from_shape(ctxt, &singular, shape);
-    (format!("{}[]", inner), defs)
-}
+    fn map_type(&self, value_type: &str) -> Ident {
+        format!("{{ [key: string]: {} }}", value_type)
+    }
 
-fn generate_map_type(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> (Ident, Option<Code>) {
-    let singular = to_singular(path);
-    let (inner, defs) = type_from_shape(ctxt, &singular, shape);
-    (format!("{{ [key: string]: {} }}", inner), defs)
-}
+    fn nullable_type(&self, inner: &str) -> Ident {
+        format!("{} | null", inner)
+    }
 
-fn generate_tuple_type(ctxt: &mut Ctxt, path: &str, shapes: &[Shape]) -> (Ident, Option<Code>) {
-    let mut types = Vec::new();
-    let mut defs = Vec::new();
+    fn optional_type(&self, inner: &str) -> Ident {
+        format!("{} | undefined", inner)
+    }
 
-    for shape in shapes {
-        let (typ, def) = type_from_shape(ctxt, path, shape);
-        types.push(typ);
-        if let Some(code) = def {
-            defs.push(code)
-        }
+    fn tuple_type(&self, elem_types: &[Ident]) -> Ident {
+        format!("[{}]", elem_types.join(", "))
     }
 
-    (format!("[{}]", types.join(", ")), Some(defs.join("\n\n")))
-}
+    fn type_name(&self, name: &str) -> Ident {
+        base_type_name(name)
+    }
 
-fn type_name(name: &str, used_names: &HashSet<String>) -> Ident {
-    let name = name.trim();
-    let mut output_name = if let Some(c) = name.chars().next() {
-        if c.is_ascii_digit() {
-            let temp = String::from("n") + name;
-            type_case(&temp)
+    fn type_definition(&self, def: &TypeDef) -> Code {
+        let declared_name = if def.type_params.is_empty() {
+            def.name.clone()
         } else {
-            type_case(name)
+            format!("{}<{}>", def.name, def.type_params.join(", "))
+        };
+
+        let mut code = format!("export interface {} {{\n", declared_name);
+        for field in &def.fields {
+            let quote = if is_ts_identifier(&field.name) {
+                ""
+            } else {
+                "\""
+            };
+            code += &format!(
+                "{}    {}{}{}{}: {};\n",
+                block_doc_comment(&field.doc_lines, "    "),
+                quote,
+                field.name,
+                quote,
+                if field.optional { "?" } else { "" },
+                field.typ
+            );
         }
-    } else {
-        type_case(name)
-    };
+        code + "}"
+    }
+
+    fn type_alias(&self, name: &str, typ: &str) -> Code {
+        format!("export type {} = {};", name, typ)
+    }
+}
+
+pub(crate) fn type_name(name: &str, used_names: &HashSet<String>) -> Ident {
+    let mut output_name = base_type_name(name);
     if output_name.is_empty() {
         output_name.push_str("GeneratedType");
     }
@@ -126,6 +124,16 @@ fn type_name(name: &str, used_names: &HashSet<String>) -> Ident {
     unreachable!()
 }
 
+/// A type name in the naming conventions, which may already be in use
+fn base_type_name(name: &str) -> Ident {
+    let name = name.trim();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        type_case(&format!("n{}", name))
+    } else {
+        type_case(name)
+    }
+}
+
 pub fn collapse_option(typ: &Shape) -> (bool, &Shape) {
     if let Shape::Optional(inner) = typ {
         return (true, &**inner);
@@ -163,65 +171,6 @@ pub fn is_ts_identifier(s: &str) -> bool {
     false
 }
 
-fn generate_interface_type(
-    ctxt: &mut Ctxt,
-    path: &str,
-    field_shapes: &LinkedHashMap<String, Shape>,
-    containing_shape: &Shape,
-) -> (Ident, Option<Code>) {
-    for (created_for_shape, ident) in ctxt.created_interfaces.iter() {
-        if created_for_shape.is_acceptable_substitution_for(containing_shape) {
-            return (ident.into(), None);
-        }
-    }
-
-    let type_name = type_name(path, &ctxt.type_names);
-    ctxt.type_names.insert(type_name.clone());
-    ctxt.created_interfaces
-        .push((containing_shape.clone(), type_name.clone()));
-
-    let mut defs = Vec::new();
-
-    let fields: Vec<Code> = field_shapes
-        .iter()
-        .map(|(name, typ)| {
-            let (was_optional, collapsed) = collapse_option(typ);
-
-            let (field_type, child_defs) = type_from_shape(ctxt, name, collapsed);
-
-            if let Some(code) = child_defs {
-                defs.push(code);
-            }
-
-            let escape_name = !is_ts_identifier(name);
-
-            format!(
-                "    {}{}{}{}: {};",
-                if escape_name { "\"" } else { "" },
-                name,
-                if escape_name { "\"" } else { "" },
-                if was_optional { "?" } else { "" },
-                field_type
-            )
-        })
-        .collect();
-
-    let mut code = format!("export interface {} {{\n", type_name);
-
-    if !fields.is_empty() {
-        code += &fields.join("\n");
-        code += "\n";
-    }
-    code += "}";
-
-    if !defs.is_empty() {
-        code += "\n\n";
-        code += &defs.join("\n\n");
-    }
-
-    (type_name, Some(code))
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -56,6 +56,9 @@ fn type_from_shape(ctxt: &mut Ctxt, shape: &Shape) -> Code {
         }
         MapT { val_type: v } => generate_map_type(ctxt, v),
         Opaque(t) => t.clone(),
+        SelfRef | Recursive(_) | TypeParam(..) => {
+            unreachable!("recursion and type parameters are removed for this output")
+        }
         Optional(e) => {
             let inner = type_from_shape(ctxt, e);
             if ctxt.options.use_default_for_missing_fields {
@@ -0,0 +1,269 @@
+use linked_hash_map::LinkedHashMap;
+
+use crate::generation::typescript::{collapse_option, is_ts_identifier};
+use crate::options::{Options, OutputMode};
+use crate::shape::{self, Shape, common_shape};
+use crate::util::lower_camel_case;
+
+/// Schemas for the TypeScript runtime validation libraries other than Zod:
+/// Valibot, io-ts, ArkType and Effect Schema
+pub struct Ctxt {
+    options: Options,
+    indent_level: usize,
+}
+
+pub type Code = String;
+
+pub fn validator_schema(name: &str, shape: &Shape, options: Options) -> Code {
+    let mut ctxt = Ctxt {
+        options,
+        indent_level: 1,
+    };
+
+    let mut code = type_from_shape(&mut ctxt, shape);
+    let mut schema_name = lower_camel_case(name);
+
+    let static_type = match ctxt.options.output_mode {
+        OutputMode::IoTs => {
+            schema_name.push_str("Codec");
+            format!("t.TypeOf<typeof {}>", schema_name)
+        }
+        OutputMode::ArkType => {
+            schema_name.push_str("Schema");
+            if is_arktype_string(&code) {
+                code = format!("type({})", code);
+            }
+            format!("typeof {}.infer", schema_name)
+        }
+        OutputMode::EffectSchema => {
+            schema_name.push_str("Schema");
+            format!("typeof {}.Type", schema_name)
+        }
+        _ => {
+            schema_name.push_str("Schema");
+            format!("v.InferOutput<typeof {}>", schema_name)
+        }
+    };
+
+    format!(
+        "export const {} = {};\n\nexport type {} = {};\n\n",
+        schema_name, code, name, static_type
+    )
+}
+
+fn type_from_shape(ctxt: &mut Ctxt, shape: &Shape) -> Code {
+    use crate::shape::Shape::*;
+    match shape {
+        Null => primitive(ctxt, "null"),
+        Any | Bottom => primitive(ctxt, "unknown"),
+        Bool => primitive(ctxt, "boolean"),
+        StringT => primitive(ctxt, "string"),
+        Integer | Floating => primitive(ctxt, "number"),
+        Tuple(shapes, _n) => {
+            let folded = shape::fold_shapes(shapes.clone());
+            if folded == Any && shapes.iter().any(|s| s != &Any) {
+                generate_tuple_type(ctxt, shapes)
+            } else {
+                generate_vec_type(ctxt, &folded)
+            }
+        }
+        VecT { elem_type: e } => generate_vec_type(ctxt, e),
+        Struct { fields } => {
+            if ctxt
+                .options
+                .infer_map_threshold
+                .is_some_and(|lim| fields.len() > lim)
+            {
+                let inner = fields
+                    .into_iter()
+                    .map(|(_, value)| value.clone())
+                    .fold(Shape::Bottom, common_shape);
+                generate_map_type(ctxt, &inner)
+            } else {
+                generate_struct_from_field_shapes(ctxt, fields)
+            }
+        }
+        MapT { val_type: v } => generate_map_type(ctxt, v),
+        Opaque(t) => t.clone(),
+        SelfRef | Recursive(_) | TypeParam(..) => {
+            unreachable!("recursion and type parameters are removed for this output")
+        }
+        Optional(e) => {
+            let inner = type_from_shape(ctxt, e);
+            if ctxt.options.use_default_for_missing_fields {
+                inner
+            } else {
+                // Optional fields are handled with the field, so this is only for other positions
+                match ctxt.options.output_mode {
+                    OutputMode::IoTs => format!("t.union([{}, t.undefined])", inner),
+                    OutputMode::ArkType => arktype_union(&inner, "undefined"),
+                    OutputMode::EffectSchema => format!("Schema.UndefinedOr({})", inner),
+                    _ => format!("v.optional({})", inner),
+                }
+            }
+        }
+        Nullable(e) => {
+            let inner = type_from_shape(ctxt, e);
+            if ctxt.options.use_default_for_missing_fields {
+                inner
+            } else {
+                match ctxt.options.output_mode {
+                    OutputMode::IoTs => format!("t.union([{}, t.null])", inner),
+                    OutputMode::ArkType => arktype_union(&inner, "null"),
+                    OutputMode::EffectSchema => format!("Schema.NullOr({})", inner),
+                    _ => format!("v.nullable({})", inner),
+                }
+            }
+        }
+    }
+}
+
+fn primitive(ctxt: &Ctxt, name: &str) -> Code {
+    match ctxt.options.output_mode {
+        OutputMode::IoTs => format!("t.{}", name),
+        // ArkType definitions for primitives are just strings
+        OutputMode::ArkType => format!("\"{}\"", name),
+        OutputMode::EffectSchema => {
+            let mut capitalized = name[..1].to_ascii_uppercase();
+            capitalized.push_str(&name[1..]);
+            format!("Schema.{}", capitalized)
+        }
+        _ => format!("v.{}()", name),
+    }
+}
+
+/// Whether ArkType code is a string definition, rather than a `type` expression
+fn is_arktype_string(code: &str) -> bool {
+    code.starts_with('"')
+}
+
+fn arktype_union(inner: &str, other: &str) -> Code {
+    if is_arktype_string(inner) {
+        format!("\"{} | {}\"", inner.trim_matches('"'), other)
+    } else {
+        format!("{}.or(\"{}\")", inner, other)
+    }
+}
+
+fn generate_vec_type(ctxt: &mut Ctxt, shape: &Shape) -> Code {
+    let inner = type_from_shape(ctxt, shape);
+    match ctxt.options.output_mode {
+        OutputMode::IoTs => format!("t.array({})", inner),
+        OutputMode::ArkType if is_arktype_string(&inner) => {
+            let inner = inner.trim_matches('"');
+            if inner.contains(' ') {
+                format!("\"({})[]\"", inner)
+            } else {
+                format!("\"{}[]\"", inner)
+            }
+        }
+        OutputMode::ArkType => format!("{}.array()", inner),
+        OutputMode::EffectSchema => format!("Schema.Array({})", inner),
+        _ => format!("v.array({})", inner),
+    }
+}
+
+fn generate_map_type(ctxt: &mut Ctxt, shape: &Shape) -> Code {
+    let (_was_optional, collapsed) = collapse_option(shape);
+    let inner = type_from_shape(ctxt, collapsed);
+    match ctxt.options.output_mode {
+        OutputMode::IoTs => format!("t.record(t.string, {})", inner),
+        OutputMode::ArkType => format!("type.Record(\"string\", {})", inner),
+        OutputMode::EffectSchema => {
+            format!("Schema.Record({{ key: Schema.String, value: {} }})", inner)
+        }
+        _ => format!("v.record(v.string(), {})", inner),
+    }
+}
+
+fn generate_tuple_type(ctxt: &mut Ctxt, shapes: &[Shape]) -> Code {
+    let mut types = Vec::new();
+
+    for shape in shapes {
+        let typ = type_from_shape(ctxt, shape);
+        types.push(typ);
+    }
+
+    match ctxt.options.output_mode {
+        OutputMode::IoTs => format!("t.tuple([{}])", types.join(", ")),
+        OutputMode::ArkType => format!("type([{}])", types.join(", ")),
+        OutputMode::EffectSchema => format!("Schema.Tuple({})", types.join(", ")),
+        _ => format!("v.tuple([{}])", types.join(", ")),
+    }
+}
+
+fn generate_struct_from_field_shapes(ctxt: &mut Ctxt, map: &LinkedHashMap<String, Shape>) -> Code {
+    let mut required_fields = Vec::new();
+    let mut optional_fields = Vec::new();
+
+    for (name, typ) in map.iter() {
+        let (is_optional, typ) = if ctxt.options.use_default_for_missing_fields {
+            (false, typ)
+        } else {
+            collapse_option(typ)
+        };
+
+        ctxt.indent_level += 1;
+        let mut field_type = type_from_shape(ctxt, typ);
+        ctxt.indent_level -= 1;
+
+        let mut key = name.clone();
+        if is_optional {
+            match ctxt.options.output_mode {
+                OutputMode::ArkType => key.push('?'),
+                OutputMode::EffectSchema => field_type = format!("Schema.optional({})", field_type),
+                OutputMode::Valibot => field_type = format!("v.optional({})", field_type),
+                _ => {}
+            }
+        }
+
+        let escape_name = !is_ts_identifier(&key);
+        let field = format!(
+            "{}{}{}{}: {},",
+            "    ".repeat(ctxt.indent_level),
+            if escape_name { "\"" } else { "" },
+            key,
+            if escape_name { "\"" } else { "" },
+            field_type
+        );
+
+        // io-ts needs optional fields in a separate partial type
+        if is_optional && ctxt.options.output_mode == OutputMode::IoTs {
+            optional_fields.push(field);
+        } else {
+            required_fields.push(field);
+        }
+    }
+
+    match ctxt.options.output_mode {
+        OutputMode::IoTs if optional_fields.is_empty() => {
+            format!("t.type({})", object_literal(ctxt, &required_fields))
+        }
+        OutputMode::IoTs if required_fields.is_empty() => {
+            format!("t.partial({})", object_literal(ctxt, &optional_fields))
+        }
+        OutputMode::IoTs => format!(
+            "t.intersection([t.type({}), t.partial({})])",
+            object_literal(ctxt, &required_fields),
+            object_literal(ctxt, &optional_fields)
+        ),
+        OutputMode::ArkType => format!("type({})", object_literal(ctxt, &required_fields)),
+        OutputMode::EffectSchema => {
+            format!("Schema.Struct({})", object_literal(ctxt, &required_fields))
+        }
+        _ => format!("v.object({})", object_literal(ctxt, &required_fields)),
+    }
+}
+
+fn object_literal(ctxt: &Ctxt, fields: &[Code]) -> Code {
+    let mut code = "{\n".to_string();
+
+    if !fields.is_empty() {
+        code += &fields.join("\n");
+        code += "\n";
+    }
+    code += &"    ".repeat(ctxt.indent_level - 1);
+    code += "}";
+
+    code
+}
@@ -3,7 +3,10 @@ use linked_hash_map::LinkedHashMap;
 pub enum Value {
     Null,
     Bool(bool),
-    // Number(f64),
+    /// A number, as its JSON literal
+    Number(String),
+    /// Any JSON literal, printed as is
+    Literal(String),
     Str(&'static str),
     String(String),
     Array(Vec<Value>),
@@ -14,8 +17,11 @@ pub fn pretty_print_value(indent: usize, value: &Value) -> String {
     match value {
         Value::Null => "null".to_string(),
         Value::Bool(b) => format!("{}", b),
-        Value::Str(s) => format!("\"{}\"", s),
-        Value::String(s) => format!("\"{}\"", s),
+        Value::Number(n) | Value::Literal(n) => n.clone(),
+        Value::Str(s) => string_literal(s),
+        Value::String(s) => string_literal(s),
+        Value::Array(values) if values.is_empty() => "[]".to_string(),
+        Value::Object(map) if map.is_empty() => "{}".to_string(),
         Value::Array(values) => {
             let mut code = "[\n".to_string();
             let len = values.len();
@@ -36,7 +42,7 @@ pub fn pretty_print_value(indent: usize, value: &Value) -> String {
             let len = map.len();
             for (i, (key, val)) in map.iter().enumerate() {
                 code += &"  ".repeat(indent + 1);
-                code += &format!("\"{}\": ", key);
+                code += &format!("{}: ", string_literal(key));
                 code += &pretty_print_value(indent + 1, val);
                 if i != len - 1 {
                     code += ",";
@@ -49,3 +55,21 @@ pub fn pretty_print_value(indent: usize, value: &Value) -> String {
         }
     }
 }
+
+fn string_literal(s: &str) -> String {
+    let mut literal = String::with_capacity(s.len() + 2);
+    literal.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => literal += "\\\"",
+            '\\' => literal += "\\\\",
+            '\n' => literal += "\\n",
+            '\r' => literal += "\\r",
+            '\t' => literal += "\\t",
+            c if c.is_control() => literal += &format!("\\u{:04x}", c as u32),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
@@ -1,66 +1,155 @@
 use linked_hash_map::LinkedHashMap;
+use std::collections::HashSet;
 
-use crate::generation::typescript::{collapse_option, is_ts_identifier};
+use crate::generation::typescript::{collapse_option, is_ts_identifier, type_name};
+use crate::observations::{Constraint, Observations, StringFormat, child_pointer};
 use crate::options::Options;
 use crate::shape::{self, Shape, common_shape};
+use crate::to_singular::to_singular;
 use crate::util::lower_camel_case;
 
 pub struct Ctxt {
     options: Options,
+    observations: Observations,
     indent_level: usize,
+    type_names: HashSet<String>,
+    /// Shapes with a schema, along with the schema and type names
+    created_schemas: Vec<(Shape, Ident, Ident)>,
+    declarations: Vec<Code>,
+    /// Schema and type names of the recursive schemas being generated, innermost last
+    recursive_schemas: Vec<(Ident, Ident)>,
+    /// JSON pointer to the value a schema is generated for, to look up observations
+    pointer: String,
 }
 
+pub type Ident = String;
 pub type Code = String;
 
-pub fn zod_schema(name: &str, shape: &Shape, options: Options) -> Code {
+pub fn zod_schema(name: &str, shape: &Shape, options: Options, observations: Observations) -> Code {
     let mut ctxt = Ctxt {
         options,
+        observations,
         indent_level: 1,
+        type_names: HashSet::new(),
+        created_schemas: Vec::new(),
+        declarations: Vec::new(),
+        recursive_schemas: Vec::new(),
+        pointer: String::new(),
     };
 
-    let code = type_from_shape(&mut ctxt, shape);
+    // reserve the requested name for the type of the root schema
+    ctxt.type_names.insert(name.to_string());
+
     let mut schema_name = lower_camel_case(name);
     schema_name.push_str("Schema");
 
-    format!("export const {} = {};\n\n", schema_name, code)
+    let shape = match shape {
+        Shape::Recursive(inner) if matches!(&**inner, Shape::Struct { fields } if !is_map_like(&ctxt, fields)) =>
+        {
+            ctxt.recursive_schemas
+                .push((schema_name.clone(), name.to_string()));
+            &**inner
+        }
+        _ => shape,
+    };
+
+    let code = match shape {
+        Shape::Struct { fields } if !is_map_like(&ctxt, fields) => {
+            generate_struct_from_field_shapes(&mut ctxt, fields)
+        }
+        _ => type_from_shape(&mut ctxt, name, shape),
+    };
+
+    let mut output = String::new();
+    for declaration in ctxt.declarations.iter() {
+        output += declaration;
+        output += "\n\n";
+    }
+    match shape {
+        Shape::Struct { fields } if !ctxt.recursive_schemas.is_empty() => {
+            let static_type = object_type(&ctxt, fields);
+            output += &recursive_declaration(&schema_name, name, &static_type, &code);
+        }
+        _ => output += &declaration(&schema_name, name, &code),
+    }
+    output += "\n\n";
+    output
+}
+
+fn declaration(schema_name: &str, type_name: &str, code: &str) -> Code {
+    format!(
+        "export const {} = {};\n\nexport type {} = z.infer<typeof {}>;",
+        schema_name, code, type_name, schema_name
+    )
+}
+
+/// A schema that refers to itself needs an explicit type, since it can not be inferred
+fn recursive_declaration(
+    schema_name: &str,
+    type_name: &str,
+    static_type: &str,
+    code: &str,
+) -> Code {
+    format!(
+        "export type {} = {};\n\nexport const {}: z.ZodType<{}> = {};",
+        type_name, static_type, schema_name, type_name, code
+    )
+}
+
+fn is_map_like(ctxt: &Ctxt, fields: &LinkedHashMap<String, Shape>) -> bool {
+    ctxt.options
+        .infer_map_threshold
+        .is_some_and(|lim| fields.len() > lim)
 }
 
-fn type_from_shape(ctxt: &mut Ctxt, shape: &Shape) -> Code {
+fn type_from_shape(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> Code {
     use crate::shape::Shape::*;
     match shape {
         Null | Any | Bottom => "z.unknown()".into(),
         Bool => "z.boolean()".into(),
-        StringT => "z.string()".into(),
-        Integer => "z.number()".into(),
-        Floating => "z.number()".into(),
+        StringT => with_constraints(ctxt, shape, "z.string()".into()),
+        Integer => with_constraints(ctxt, shape, "z.number()".into()),
+        Floating => with_constraints(ctxt, shape, "z.number()".into()),
         Tuple(shapes, _n) => {
             let folded = shape::fold_shapes(shapes.clone());
             if folded == Any && shapes.iter().any(|s| s != &Any) {
-                generate_tuple_type(ctxt, shapes)
+                generate_tuple_type(ctxt, path, shapes)
             } else {
-                generate_vec_type(ctxt, &folded)
+                let code = generate_vec_type(ctxt, path, &folded);
+                with_constraints(ctxt, shape, code)
             }
         }
-        VecT { elem_type: e } => generate_vec_type(ctxt, e),
+        VecT { elem_type: e } => {
+            let code = generate_vec_type(ctxt, path, e);
+            with_constraints(ctxt, shape, code)
+        }
         Struct { fields } => {
-            if ctxt
-                .options
-                .infer_map_threshold
-                .is_some_and(|lim| fields.len() > lim)
-            {
+            if is_map_like(ctxt, fields) {
                 let inner = fields
                     .into_iter()
                     .map(|(_, value)| value.clone())
                     .fold(Shape::Bottom, common_shape);
-                generate_map_type(ctxt, &inner)
+                generate_map_type(ctxt, path, &inner)
             } else {
-                generate_struct_from_field_shapes(ctxt, fields)
+                generate_named_schema(ctxt, path, fields, shape)
             }
         }
-        MapT { val_type: v } => generate_map_type(ctxt, v),
+        MapT { val_type: v } => generate_map_type(ctxt, path, v),
         Opaque(t) => t.clone(),
+        Recursive(e) => match &**e {
+            Struct { fields } if !is_map_like(ctxt, fields) => {
+                generate_named_schema(ctxt, path, fields, shape)
+            }
+            _ => type_from_shape(ctxt, path, e),
+        },
+        TypeParam(..) => unreachable!("type parameters are removed for this output"),
+        // The schema is not defined yet where it refers to itself
+        SelfRef => match ctxt.recursive_schemas.last() {
+            Some((schema_name, _)) => format!("z.lazy(() => {})", schema_name),
+            None => "z.unknown()".into(),
+        },
         Optional(e) => {
-            let inner = type_from_shape(ctxt, e);
+            let inner = type_from_shape(ctxt, path, e);
             if ctxt.options.use_default_for_missing_fields {
                 inner
             } else {
@@ -68,7 +157,7 @@ fn type_from_shape(ctxt: &mut Ctxt, shape: &Shape) -> Code {
             }
         }
         Nullable(e) => {
-            let inner = type_from_shape(ctxt, e);
+            let inner = type_from_shape(ctxt, path, e);
             if ctxt.options.use_default_for_missing_fields {
                 inner
             } else {
@@ -78,40 +167,121 @@ fn type_from_shape(ctxt: &mut Ctxt, shape: &Shape) -> Code {
     }
 }
 
-fn generate_vec_type(ctxt: &mut Ctxt, shape: &Shape) -> Code {
-    let inner = type_from_shape(ctxt, shape);
+/// Refine a schema with the constraints that held for all the samples, if asked for
+fn with_constraints(ctxt: &Ctxt, shape: &Shape, code: Code) -> Code {
+    if !ctxt.options.infer_constraints {
+        return code;
+    }
+    let mut code = code;
+    for constraint in ctxt.observations.constraints(&ctxt.pointer, shape) {
+        code += &match constraint {
+            Constraint::MinLength(n) => format!(".min({})", n),
+            Constraint::MaxLength(n) => format!(".max({})", n),
+            Constraint::Minimum(n) => format!(".min({})", n),
+            Constraint::Maximum(n) => format!(".max({})", n),
+            Constraint::NonEmpty => ".min(1)".to_string(),
+            Constraint::Format(StringFormat::Email) => ".email()".to_string(),
+            Constraint::Format(StringFormat::Url) => ".url()".to_string(),
+            Constraint::Format(StringFormat::Uuid) => ".uuid()".to_string(),
+            Constraint::Format(StringFormat::Date) => ".date()".to_string(),
+            Constraint::Format(StringFormat::DateTime) => ".datetime({ offset: true })".to_string(),
+        };
+    }
+    code
+}
+
+fn generate_vec_type(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> Code {
+    let singular = to_singular(path);
+    let pointer = child_pointer(&ctxt.pointer, "-");
+    let parent_pointer = std::mem::replace(&mut ctxt.pointer, pointer);
+    let inner = type_from_shape(ctxt, &singular, shape);
+    ctxt.pointer = parent_pointer;
     format!("{}.array()", inner)
 }
 
-fn generate_map_type(ctxt: &mut Ctxt, shape: &Shape) -> Code {
+fn generate_map_type(ctxt: &mut Ctxt, path: &str, shape: &Shape) -> Code {
+    let singular = to_singular(path);
     let (_was_optional, collapsed) = collapse_option(shape);
-    let inner = type_from_shape(ctxt, collapsed);
+    let inner = type_from_shape(ctxt, &singular, collapsed);
     format!("z.record(z.string(), {})", inner)
 }
 
-fn generate_tuple_type(ctxt: &mut Ctxt, shapes: &[Shape]) -> Code {
+fn generate_tuple_type(ctxt: &mut Ctxt, path: &str, shapes: &[Shape]) -> Code {
     let mut types = Vec::new();
 
+    let pointer = child_pointer(&ctxt.pointer, "-");
+    let parent_pointer = std::mem::replace(&mut ctxt.pointer, pointer);
     for shape in shapes {
-        let typ = type_from_shape(ctxt, shape);
+        let typ = type_from_shape(ctxt, path, shape);
         types.push(typ);
     }
+    ctxt.pointer = parent_pointer;
 
     format!("z.tuple([{}])", types.join(", "))
 }
 
+/// Objects are declared as separate schemas, which are reused for objects of the same shape
+fn generate_named_schema(
+    ctxt: &mut Ctxt,
+    path: &str,
+    field_shapes: &LinkedHashMap<String, Shape>,
+    containing_shape: &Shape,
+) -> Ident {
+    for (created_for_shape, ident, _) in ctxt.created_schemas.iter() {
+        if created_for_shape.is_acceptable_substitution_for(containing_shape) {
+            return ident.into();
+        }
+    }
+
+    let type_name = type_name(path, &ctxt.type_names);
+    ctxt.type_names.insert(type_name.clone());
+    let mut schema_name = lower_camel_case(&type_name);
+    schema_name.push_str("Schema");
+    ctxt.created_schemas.push((
+        containing_shape.clone(),
+        schema_name.clone(),
+        type_name.clone(),
+    ));
+
+    let is_recursive = matches!(containing_shape, Shape::Recursive(_));
+    if is_recursive {
+        ctxt.recursive_schemas
+            .push((schema_name.clone(), type_name.clone()));
+    }
+
+    let indent_level = ctxt.indent_level;
+    ctxt.indent_level = 1;
+    let code = generate_struct_from_field_shapes(ctxt, field_shapes);
+    ctxt.indent_level = indent_level;
+
+    // Pushed after the fields are generated, so that the schemas used are declared first
+    let declaration = if is_recursive {
+        let static_type = object_type(ctxt, field_shapes);
+        ctxt.recursive_schemas.pop();
+        recursive_declaration(&schema_name, &type_name, &static_type, &code)
+    } else {
+        declaration(&schema_name, &type_name, &code)
+    };
+    ctxt.declarations.push(declaration);
+
+    schema_name
+}
+
 fn generate_struct_from_field_shapes(ctxt: &mut Ctxt, map: &LinkedHashMap<String, Shape>) -> Code {
     let fields: Vec<Code> = map
         .iter()
         .map(|(name, typ)| {
+            let pointer = child_pointer(&ctxt.pointer, name);
+            let parent_pointer = std::mem::replace(&mut ctxt.pointer, pointer);
             ctxt.indent_level += 1;
-            let field_type = type_from_shape(ctxt, typ);
+            let field_type = type_from_shape(ctxt, name, typ);
             ctxt.indent_level -= 1;
+            ctxt.pointer = parent_pointer;
 
             let escape_name = !is_ts_identifier(name);
 
             format!(
-                "{}{}{}{}: {};",
+                "{}{}{}{}: {},",
                 "    ".repeat(ctxt.indent_level),
                 if escape_name { "\"" } else { "" },
                 name,
@@ -132,3 +302,81 @@ fn generate_struct_from_field_shapes(ctxt: &mut Ctxt, map: &LinkedHashMap<String
 
     code
 }
+
+/// The TypeScript type matching the schema for a shape,
+/// referring to the types of the declared schemas by name
+fn static_type(ctxt: &Ctxt, shape: &Shape) -> Code {
+    use crate::shape::Shape::*;
+    match shape {
+        Null | Any | Bottom => "unknown".into(),
+        Bool => "boolean".into(),
+        StringT => "string".into(),
+        Integer | Floating => "number".into(),
+        Tuple(shapes, _n) => {
+            let folded = shape::fold_shapes(shapes.clone());
+            if folded == Any && shapes.iter().any(|s| s != &Any) {
+                let types: Vec<Code> = shapes.iter().map(|s| static_type(ctxt, s)).collect();
+                format!("[{}]", types.join(", "))
+            } else {
+                array_type(&static_type(ctxt, &folded))
+            }
+        }
+        VecT { elem_type: e } => array_type(&static_type(ctxt, e)),
+        Struct { fields } if is_map_like(ctxt, fields) => {
+            let inner = fields.values().cloned().fold(Shape::Bottom, common_shape);
+            format!("Record<string, {}>", static_type(ctxt, &inner))
+        }
+        Struct { .. } | Recursive(_) => ctxt
+            .created_schemas
+            .iter()
+            .find(|(created_for_shape, _, _)| {
+                created_for_shape.is_acceptable_substitution_for(shape)
+            })
+            .map(|(_, _, type_name)| type_name.clone())
+            .unwrap_or_else(|| "unknown".into()),
+        MapT { val_type: v } => {
+            let (_was_optional, collapsed) = collapse_option(v);
+            format!("Record<string, {}>", static_type(ctxt, collapsed))
+        }
+        Opaque(t) => format!("z.infer<typeof {}>", t),
+        TypeParam(..) => unreachable!("type parameters are removed for this output"),
+        SelfRef => match ctxt.recursive_schemas.last() {
+            Some((_, type_name)) => type_name.clone(),
+            None => "unknown".into(),
+        },
+        Optional(e) if ctxt.options.use_default_for_missing_fields => static_type(ctxt, e),
+        Optional(e) => format!("{} | undefined", static_type(ctxt, e)),
+        Nullable(e) if ctxt.options.use_default_for_missing_fields => static_type(ctxt, e),
+        Nullable(e) => format!("{} | null", static_type(ctxt, e)),
+    }
+}
+
+fn array_type(inner: &str) -> Code {
+    if inner.contains(' ') {
+        format!("({})[]", inner)
+    } else {
+        format!("{}[]", inner)
+    }
+}
+
+fn object_type(ctxt: &Ctxt, map: &LinkedHashMap<String, Shape>) -> Code {
+    let mut code = "{\n".to_string();
+    for (name, typ) in map.iter() {
+        let (was_optional, collapsed) = if ctxt.options.use_default_for_missing_fields {
+            (false, typ)
+        } else {
+            collapse_option(typ)
+        };
+        let escape_name = !is_ts_identifier(name);
+        code += &format!(
+            "    {}{}{}{}: {};\n",
+            if escape_name { "\"" } else { "" },
+            name,
+            if escape_name { "\"" } else { "" },
+            if was_optional { "?" } else { "" },
+            static_type(ctxt, collapsed)
+        );
+    }
+    code += "}";
+    code
+}
@@ -8,6 +8,7 @@ pub enum HintType {
     OpaqueType(String),
     MapType(String),
     TypeName(String),
+    TypeParameter(String),
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -35,6 +36,10 @@ impl Hint {
     pub fn type_name(name: impl Into<String>) -> Self {
         Hint::new(HintType::TypeName(name.into()))
     }
+
+    pub fn type_parameter(name: impl Into<String>) -> Self {
+        Hint::new(HintType::TypeParameter(name.into()))
+    }
 }
 
 pub struct Hints<'a> {
@@ -91,6 +96,19 @@ impl<'a> Hints<'a> {
         self.step(|first| first == "-" || first == i_str)
     }
 
+    /// The same hints, except type parameters for the current node, to infer its type argument
+    pub fn without_type_parameters(&self) -> Hints<'a> {
+        Hints {
+            hints: self.hints.clone(),
+            applicable: self
+                .applicable
+                .iter()
+                .filter(|hint| !matches!(hint.hint_type, HintType::TypeParameter(_)))
+                .cloned()
+                .collect(),
+        }
+    }
+
     fn step<F: Fn(&str) -> bool>(&self, pred: F) -> Hints {
         let mut filtered = Vec::new();
         let mut applicable = Vec::new();
@@ -4,3 +4,4 @@ mod jsonlex;
 
 pub use jsoninfer::shape_from_json;
 pub use jsoninputerr::JsonInputErr;
+pub(crate) use jsonlex::{JsonLexer, JsonToken};
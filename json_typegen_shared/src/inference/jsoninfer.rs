@@ -2,6 +2,7 @@ use crate::Options;
 use crate::hints::{HintType, Hints};
 use crate::inference::jsoninputerr::JsonInputErr;
 use crate::inference::jsonlex::{JsonLexer, JsonToken};
+use crate::observations::{Observations, child_pointer, string_example};
 use crate::shape::{Shape, common_shape};
 use linked_hash_map::LinkedHashMap;
 use std::io::Read;
@@ -11,7 +12,7 @@ pub fn shape_from_json<R: Read>(
     read: R,
     options: &Options,
     hints: &Hints,
-) -> Result<Shape, JsonInputErr> {
+) -> Result<(Shape, Observations), JsonInputErr> {
     let pointer = options.unwrap.clone();
     let pointer_tokens: Vec<&str> = if pointer.is_empty() || pointer == "/" {
         vec![]
@@ -21,19 +22,29 @@ pub fn shape_from_json<R: Read>(
         pointer.split('/').collect()
     };
 
-    Inference::new(read)
+    let mut inference = Inference::new(read);
+    if options.collect_examples() || options.collect_bounds() {
+        inference.observations = Some(Observations::default());
+    }
+    let shape = inference
         .unwrap(&pointer_tokens, options, hints)?
-        .ok_or(JsonInputErr::NoMatchForUnwrap)
+        .ok_or(JsonInputErr::NoMatchForUnwrap)?;
+    Ok((shape, inference.observations.unwrap_or_default()))
 }
 
 struct Inference<T: Iterator<Item = Result<JsonToken, JsonInputErr>>> {
     tokens: Peekable<T>,
+    /// Pointer to the current value, relative to the unwrapped root(s)
+    pointer: String,
+    observations: Option<Observations>,
 }
 
 impl<R: Read> Inference<JsonLexer<R>> {
     fn new(source: R) -> Self {
         Inference {
             tokens: JsonLexer::new(source).peekable(),
+            pointer: String::new(),
+            observations: None,
         }
     }
 }
@@ -68,14 +79,42 @@ impl<T: Iterator<Item = Result<JsonToken, JsonInputErr>>> Inference<T> {
                 }
                 HintType::OpaqueType(ref t) => {
                     hint.used.set(true);
-                    let _ = self.infer_shape(options, &Hints::new()); // parse and discard the actual value
+                    let _ = self.skip_value(options); // parse and discard the actual value
                     return Ok(Shape::Opaque(t.clone()));
                 }
+                HintType::TypeParameter(ref name) => {
+                    hint.used.set(true);
+                    let arg = self.infer_shape(options, &hints.without_type_parameters())?;
+                    return Ok(Shape::TypeParam(name.clone(), Box::new(arg)));
+                }
                 _ => {}
             }
         }
 
-        match self.next_token()? {
+        let token = self.next_token()?;
+
+        if let Some(observations) = &mut self.observations {
+            if options.collect_examples() {
+                let example = match token {
+                    JsonToken::True => Some("true".to_string()),
+                    JsonToken::False => Some("false".to_string()),
+                    JsonToken::Null => Some("null".to_string()),
+                    JsonToken::Number(ref s) => Some(s.clone()),
+                    JsonToken::String(ref s) => Some(string_example(s)),
+                    _ => None,
+                };
+                observations.record(&self.pointer, example);
+            }
+            if options.collect_bounds() {
+                match token {
+                    JsonToken::Number(ref s) => observations.record_number(&self.pointer, s),
+                    JsonToken::String(ref s) => observations.record_string(&self.pointer, s),
+                    _ => {}
+                }
+            }
+        }
+
+        match token {
             JsonToken::True | JsonToken::False => Ok(Shape::Bool),
             JsonToken::Null => Ok(Shape::Null),
             JsonToken::Number(s) => {
@@ -131,7 +170,12 @@ impl<T: Iterator<Item = Result<JsonToken, JsonInputErr>>> Inference<T> {
 
             self.expect_token(JsonToken::Colon)?;
 
+            let parent_len = self.pointer.len();
+            if self.observations.is_some() {
+                self.pointer = child_pointer(&self.pointer, &key);
+            }
             let value = self.infer_shape(options, &hints.step_field(&key))?;
+            self.pointer.truncate(parent_len);
             fields.insert(key, value);
 
             if let Some(&Ok(JsonToken::ObjectEnd)) = self.tokens.peek() {
@@ -161,6 +205,7 @@ impl<T: Iterator<Item = Result<JsonToken, JsonInputErr>>> Inference<T> {
     fn infer_array(&mut self, options: &Options, hints: &Hints) -> Result<Shape, JsonInputErr> {
         if let Some(&Ok(JsonToken::ArrayEnd)) = self.tokens.peek() {
             self.tokens.next();
+            self.record_items(options, 0);
             return Ok(Shape::VecT {
                 elem_type: Box::new(Shape::Bottom),
             });
@@ -170,6 +215,11 @@ impl<T: Iterator<Item = Result<JsonToken, JsonInputErr>>> Inference<T> {
         let mut shapes: Vec<Shape> = vec![];
         let mut folded = Shape::Bottom;
 
+        let parent_len = self.pointer.len();
+        if self.observations.is_some() {
+            self.pointer = child_pointer(&self.pointer, "-");
+        }
+
         loop {
             if len < 12 {
                 let shape = self.infer_shape(options, &hints.step_index(len))?;
@@ -188,6 +238,9 @@ impl<T: Iterator<Item = Result<JsonToken, JsonInputErr>>> Inference<T> {
             self.expect_token(JsonToken::Comma)?;
         }
 
+        self.pointer.truncate(parent_len);
+        self.record_items(options, len);
+
         if len > 1 && len <= 12 {
             return Ok(Shape::Tuple(shapes, 1));
         }
@@ -199,6 +252,22 @@ impl<T: Iterator<Item = Result<JsonToken, JsonInputErr>>> Inference<T> {
         })
     }
 
+    fn record_items(&mut self, options: &Options, len: usize) {
+        if let Some(observations) = &mut self.observations
+            && options.collect_bounds()
+        {
+            observations.record_items(&self.pointer, len);
+        }
+    }
+
+    /// Parse a value without inferring anything from it
+    fn skip_value(&mut self, options: &Options) -> Result<(), JsonInputErr> {
+        let observations = self.observations.take();
+        let result = self.infer_shape(options, &Hints::new());
+        self.observations = observations;
+        result.map(|_| ())
+    }
+
     /// "Unwrap" JSON nodes before doing inference
     ///
     /// The node(s) specified by the pointer is the new root(s) that we do inference on
@@ -243,7 +312,7 @@ impl<T: Iterator<Item = Result<JsonToken, JsonInputErr>>> Inference<T> {
                         folded = optional_common_shape(folded, result);
                     } else {
                         // parse and discard non-matched element (could use non-inference code)
-                        let _ = self.infer_shape(options, hints)?;
+                        self.skip_value(options)?;
                     }
 
                     if let Some(&Ok(JsonToken::ObjectEnd)) = self.tokens.peek() {
@@ -273,7 +342,7 @@ impl<T: Iterator<Item = Result<JsonToken, JsonInputErr>>> Inference<T> {
                         folded = optional_common_shape(folded, result);
                     } else {
                         // parse and discard non-matched element (could use non-inference code)
-                        let _ = self.infer_shape(options, hints)?;
+                        self.skip_value(options)?;
                     }
 
                     if let Some(&Ok(JsonToken::ArrayEnd)) = self.tokens.peek() {
@@ -423,7 +492,8 @@ mod tests {
                 ..Options::default()
             },
             &Hints::new(),
-        );
+        )
+        .map(|(shape, _observations)| shape);
 
         assert_eq!(shape, result);
     }
@@ -460,4 +530,47 @@ mod tests {
     fn unwrap_array_index() {
         unwrap_test(r#"["foo", 6]"#, "/1", Ok(Shape::Integer));
     }
+
+    #[test]
+    fn observations() {
+        let (_shape, observations) = shape_from_json(
+            r#"{ "meta": { "id": 1 }, "data": [{ "id": 2, "a/b": "x" }, { "id": 3 }] }"#.as_bytes(),
+            &Options {
+                unwrap: "/data".into(),
+                doc_comments: true,
+                ..Options::default()
+            },
+            &Hints::new(),
+        )
+        .unwrap();
+
+        let ids = observations.get("/-/id").unwrap();
+        assert_eq!(ids.count, 2);
+        assert_eq!(ids.examples, vec!["2".to_string(), "3".to_string()]);
+        assert_eq!(observations.get("/-/a~1b").unwrap().count, 1);
+        assert_eq!(observations.get("/id"), None);
+    }
+
+    #[test]
+    fn observed_bounds() {
+        let (_shape, observations) = shape_from_json(
+            r#"[{ "n": 2, "s": "abc", "l": [1] }, { "n": -0.5, "s": "a", "l": [1, 2] }]"#
+                .as_bytes(),
+            &Options {
+                infer_constraints: true,
+                ..Options::default()
+            },
+            &Hints::new(),
+        )
+        .unwrap();
+
+        let n = &observations.get("/-/n").unwrap().bounds;
+        assert_eq!(n.range, Some(((-0.5, "-0.5".into()), (2.0, "2".into()))));
+        assert_eq!(
+            observations.get("/-/s").unwrap().bounds.length,
+            Some((1, 3))
+        );
+        assert_eq!(observations.get("/-/l").unwrap().bounds.items, Some((1, 2)));
+        assert_eq!(observations.get("/-"), None);
+    }
 }
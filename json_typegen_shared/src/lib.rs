@@ -15,20 +15,34 @@ use thiserror::Error;
 mod generation;
 mod hints;
 mod inference;
+mod observations;
 mod options;
 #[cfg(feature = "option-parsing")]
 pub mod parse;
 #[cfg(feature = "progress")]
 mod progress;
 mod shape;
+mod shape_diff;
+mod shape_json;
 mod sql;
 mod to_singular;
 mod util;
+mod validation;
 
+pub use crate::generation::generator::{
+    FieldDef, Generator, TypeDef, register_generator, registered_generators,
+};
+pub use crate::generation::rust_merge::{Merged, RemovedField};
 use crate::hints::Hints;
 use crate::inference::shape_from_json;
-pub use crate::options::{ImportStyle, Options, OutputMode, StringTransform};
+use crate::observations::Observations;
+pub use crate::options::{
+    BorrowStyle, ImportStyle, InputMode, JsonSchemaDraft, Options, OutputMode, SqlDialect,
+    StringTransform, ValidationCrate,
+};
 pub use crate::shape::Shape;
+pub use crate::shape_diff::{ChangeKind, ShapeChange, diff_shapes};
+pub use crate::validation::Mismatch;
 
 /// The errors that json_typegen_shared may produce
 ///
@@ -47,8 +61,22 @@ pub enum JTError {
     JsonParsingError(#[from] inference::JsonInputErr),
     #[error("An error occurred while parsing SQL: {0}")]
     SqlParsingError(String),
+    #[error("An error occurred while parsing a shape: {0}")]
+    ShapeParsingError(String),
+    #[error("An error occurred while parsing existing Rust code: {0}")]
+    RustParsingError(String),
+    #[error("The existing Rust code can not be updated to fit the input: {0}")]
+    RustMergeError(String),
     #[error("An error occurred while parsing a macro or macro input: {0}")]
     MacroParsingError(String),
+    #[error("An error occurred while rendering a template: {0}")]
+    TemplateError(String),
+    #[error("Unsupported options: {0}")]
+    UnsupportedOption(String),
+    #[error("Unknown output mode: {0}")]
+    UnknownOutputMode(String),
+    #[error("The name of a built in output mode can not be used for a generator: {0}")]
+    BuiltInOutputMode(String),
 }
 
 /// Utilities exposed only to be available inside the `json_typegen` workspace. Internal API.
@@ -98,7 +126,6 @@ pub fn codegen_from_macro_input(input: &str) -> Result<String, JTError> {
 
 /// The main code generation function for `json_typegen`
 pub fn codegen(name: &str, input: &str, mut options: Options) -> Result<String, JTError> {
-    let source = infer_source_type(input);
     let name = handle_pub_in_name(name, &mut options);
 
     let mut hints_vec = Vec::new();
@@ -109,33 +136,300 @@ pub fn codegen(name: &str, input: &str, mut options: Options) -> Result<String,
         hints.add(pointer, hint);
     }
 
-    let shape = match options.input_mode {
+    let (shape, observations) = shape_from_input(input, &options, &hints)?;
+
+    generate_code(name, &shape, options, observations)
+}
+
+/// Generate code for several named samples at once, sharing types between them
+///
+/// Objects with the same shape in different samples get a single type, and type names are
+/// unique across the whole output, with earlier roots getting the first pick of names.
+/// Types are shared for Rust, TypeScript, Kotlin and Python output. For other output modes
+/// the code for each root is generated separately, one after the other.
+#[allow(clippy::result_large_err)]
+pub fn codegen_multiple(roots: &[(&str, &str)], mut options: Options) -> Result<String, JTError> {
+    let mut hints_vec = Vec::new();
+    std::mem::swap(&mut options.hints, &mut hints_vec);
+
+    let mut hints = Hints::new();
+    for (pointer, hint) in hints_vec.iter() {
+        hints.add(pointer, hint);
+    }
+
+    let mut inferred = Vec::new();
+    let mut visibilities = Vec::new();
+    for (name, input) in roots {
+        let (visibility, name) = split_visibility(name);
+        let (shape, observations) = shape_from_input(input, &options, &hints)?;
+        inferred.push((name, shape, observations));
+        visibilities.push(visibility.unwrap_or_else(|| options.type_visibility.clone()));
+    }
+
+    if !supports_shared_types(&options.output_mode) {
+        let mut generated_code = String::new();
+        for ((name, shape, observations), visibility) in inferred.into_iter().zip(visibilities) {
+            let mut root_options = options.clone();
+            root_options.type_visibility = visibility;
+            if !generated_code.is_empty() {
+                generated_code.push('\n');
+            }
+            generated_code += &generate_code(name, &shape, root_options, observations)?;
+        }
+        return Ok(generated_code);
+    }
+
+    // types shared between roots can only have one visibility
+    if let Some(visibility) = visibilities.first()
+        && visibilities.iter().any(|other| other != visibility)
+    {
+        return Err(JTError::UnsupportedOption(
+            "roots with different visibilities, like `pub Foo` and `pub(crate) Bar`".to_string(),
+        ));
+    }
+    if let Some(visibility) = visibilities.into_iter().next() {
+        options.type_visibility = visibility;
+    }
+
+    // The observations for each root are kept apart by putting them under the name of the root
+    let inferred: Vec<_> = inferred
+        .into_iter()
+        .map(|(name, shape, observations)| {
+            (
+                name,
+                shape_for_output(shape, &options.output_mode),
+                observations,
+            )
+        })
+        .collect();
+    let mut observations = Observations::default();
+    let mut generation_roots = Vec::new();
+    for (name, shape, root_observations) in inferred.iter() {
+        let pointer = observations::child_pointer("", name);
+        observations.extend_prefixed(&pointer, root_observations.clone());
+        generation_roots.push(generation::Root {
+            name,
+            shape,
+            pointer,
+        });
+    }
+
+    let mut generated_code = match options.output_mode {
+        OutputMode::Rust => {
+            generation::rust::rust_types_for_roots(&generation_roots, options, observations)
+        }
+        OutputMode::Typescript => generation::typescript::typescript_types_for_roots(
+            &generation_roots,
+            options,
+            observations,
+        ),
+        OutputMode::PythonPydantic
+        | OutputMode::PythonPydanticV1
+        | OutputMode::PythonDataclass
+        | OutputMode::PythonTypedDict
+        | OutputMode::PythonAttrs
+        | OutputMode::PythonMsgspec => {
+            generation::python::python_types_for_roots(&generation_roots, options, observations)
+        }
+        OutputMode::KotlinJackson
+        | OutputMode::KotlinKotlinx
+        | OutputMode::KotlinMoshi
+        | OutputMode::KotlinGson => {
+            generation::kotlin::kotlin_types_for_roots(&generation_roots, options, observations)
+        }
+        OutputMode::Custom(ref name) => generation::generator::generate(
+            &*custom_generator(name)?,
+            &generation_roots,
+            &options,
+            &observations,
+        ),
+        OutputMode::Template => template_output(&generation_roots, &options, &observations)?,
+        OutputMode::Markdown => generation::generator::generate(
+            &generation::docs::Markdown,
+            &generation_roots,
+            &options,
+            &observations,
+        ),
+        OutputMode::Html => generation::generator::generate(
+            &generation::docs::Html,
+            &generation_roots,
+            &options,
+            &observations,
+        ),
+        _ => unreachable!("checked by supports_shared_types"),
+    };
+
+    // Ensure generated code ends with exactly one newline
+    generated_code.truncate(generated_code.trim_end().len());
+    generated_code.push('\n');
+
+    Ok(generated_code)
+}
+
+/// Update Rust code generated earlier to fit the input, keeping the changes made to it by hand
+///
+/// Takes the same input as `codegen`, along with the existing code. Starting from the type with
+/// the given name, structs are matched to objects by the serde names of their fields. New fields
+/// are added and types that no longer fit are replaced, while attributes, comments, field order
+/// and type names are kept. Fields that are not in the input are kept as well, and returned
+/// in `Merged::removed_fields`.
+#[allow(clippy::result_large_err)]
+pub fn merge_rust(
+    existing: &str,
+    name: &str,
+    input: &str,
+    mut options: Options,
+) -> Result<Merged, JTError> {
+    let name = handle_pub_in_name(name, &mut options);
+
+    let mut hints_vec = Vec::new();
+    std::mem::swap(&mut options.hints, &mut hints_vec);
+
+    let mut hints = Hints::new();
+    for (pointer, hint) in hints_vec.iter() {
+        hints.add(pointer, hint);
+    }
+
+    let (shape, observations) = shape_from_input(input, &options, &hints)?;
+
+    let shape = shape::fold_recursive_structs(shape);
+    generation::rust_merge::merge_rust(existing, name, &shape, options, observations)
+}
+
+/// Infer the shape of an input, along with observations if asked for
+#[allow(clippy::result_large_err)]
+fn shape_from_input(
+    input: &str,
+    options: &Options,
+    hints: &Hints,
+) -> Result<(Shape, Observations), JTError> {
+    Ok(match options.input_mode {
         options::InputMode::Sql => {
             let shapes = sql::sql_to_shape(input).map_err(JTError::SqlParsingError)?;
             let (_name, shap) = shapes.get(0).unwrap();
-            shap.clone()
+            (shap.clone(), Observations::default())
         }
-        options::InputMode::Json => infer_from_sample(&source, &options, &hints)?,
-    };
+        options::InputMode::ShapeJson => {
+            let text = read_source(&infer_source_type(input))?;
+            let shape = Shape::from_json(&text)?;
+            (shape, Observations::default())
+        }
+        options::InputMode::Json => {
+            let source = infer_source_type(input);
+            infer_from_sample(&source, options, hints)?
+        }
+    })
+}
+
+/// Just inference, no code generation
+///
+/// Takes the same input as `codegen`, and uses the input mode, unwrap pointer and
+/// inference hints from the options.
+#[allow(clippy::result_large_err)]
+pub fn infer_shape(input: &str, mut options: Options) -> Result<Shape, JTError> {
+    let mut hints_vec = Vec::new();
+    std::mem::swap(&mut options.hints, &mut hints_vec);
+
+    let mut hints = Hints::new();
+    for (pointer, hint) in hints_vec.iter() {
+        hints.add(pointer, hint);
+    }
+
+    let (shape, _observations) = shape_from_input(input, &options, &hints)?;
+    Ok(shape)
+}
 
-    codegen_from_shape(name, &shape, options)
+/// Check whether a sample fits a shape, e.g. one saved earlier or inferred from other samples
+///
+/// The sample can be given the same ways as for `codegen`. An empty result means that it fits.
+#[allow(clippy::result_large_err)]
+pub fn validate_sample(input: &str, shape: &Shape) -> Result<Vec<Mismatch>, JTError> {
+    let mismatches = match infer_source_type(input) {
+        #[cfg(feature = "remote-samples")]
+        SampleSource::Url(url) => validation::validate(ureq::get(url).call()?.into_reader(), shape),
+        #[cfg(feature = "local-samples")]
+        SampleSource::File(path) => validation::validate(std::fs::File::open(path)?, shape),
+        SampleSource::Text(text) => validation::validate(text.as_bytes(), shape),
+    };
+    Ok(mismatches?)
 }
 
 /// Just code generation, no inference
 pub fn codegen_from_shape(name: &str, shape: &Shape, options: Options) -> Result<String, JTError> {
+    generate_code(name, shape, options, Observations::default())
+}
+
+/// Code generation, with the observations made during inference if there was any
+#[allow(clippy::result_large_err)]
+fn generate_code(
+    name: &str,
+    shape: &Shape,
+    options: Options,
+    observations: Observations,
+) -> Result<String, JTError> {
+    let shape = &shape_for_output(shape.clone(), &options.output_mode);
+
     let mut generated_code = match options.output_mode {
-        OutputMode::Rust => generation::rust::rust_types(name, shape, options),
-        OutputMode::JsonSchema => generation::json_schema::json_schema(name, shape, options),
-        OutputMode::ZodSchema => generation::zod_schema::zod_schema(name, shape, options),
-        OutputMode::KotlinJackson | OutputMode::KotlinKotlinx => {
-            generation::kotlin::kotlin_types(name, shape, options)
+        OutputMode::Rust => generation::rust::rust_types(name, shape, options, observations),
+        OutputMode::JsonSchema => {
+            generation::json_schema::json_schema(name, shape, options, observations)
         }
+        OutputMode::ZodSchema => {
+            generation::zod_schema::zod_schema(name, shape, options, observations)
+        }
+        OutputMode::Valibot | OutputMode::IoTs | OutputMode::ArkType | OutputMode::EffectSchema => {
+            generation::validator_schema::validator_schema(name, shape, options)
+        }
+        OutputMode::KotlinJackson
+        | OutputMode::KotlinKotlinx
+        | OutputMode::KotlinMoshi
+        | OutputMode::KotlinGson => {
+            generation::kotlin::kotlin_types(name, shape, options, observations)
+        }
+        OutputMode::Protobuf => generation::protobuf::protobuf_messages(name, shape),
+        OutputMode::GraphQL => generation::graphql::graphql_types(name, shape, options),
+        OutputMode::AvroSchema => generation::avro::avro_schema(name, shape, options),
+        OutputMode::SqlDdl => generation::sql_ddl::sql_ddl(name, shape, options),
         OutputMode::Shape => generation::shape::shape_string(name, shape, options),
-        OutputMode::Typescript => generation::typescript::typescript_types(name, shape, options),
+        OutputMode::ShapeJson => shape.to_json(),
+        OutputMode::MockData => generation::mock::mock_data(name, shape, options, observations),
+        OutputMode::Typescript => {
+            generation::typescript::typescript_types(name, shape, options, observations)
+        }
         OutputMode::TypescriptTypeAlias => {
             generation::typescript_type_alias::typescript_type_alias(name, shape, options)
         }
-        OutputMode::PythonPydantic => generation::python::python_types(name, shape, options),
+        OutputMode::PythonPydantic
+        | OutputMode::PythonPydanticV1
+        | OutputMode::PythonDataclass
+        | OutputMode::PythonTypedDict
+        | OutputMode::PythonAttrs
+        | OutputMode::PythonMsgspec => {
+            generation::python::python_types(name, shape, options, observations)
+        }
+        OutputMode::Template => template_output(
+            &[generation::Root::single(name, shape)],
+            &options,
+            &observations,
+        )?,
+        OutputMode::Markdown => generation::generator::generate(
+            &generation::docs::Markdown,
+            &[generation::Root::single(name, shape)],
+            &options,
+            &observations,
+        ),
+        OutputMode::Html => generation::generator::generate(
+            &generation::docs::Html,
+            &[generation::Root::single(name, shape)],
+            &options,
+            &observations,
+        ),
+        OutputMode::Custom(ref custom) => generation::generator::generate(
+            &*custom_generator(custom)?,
+            &[generation::Root::single(name, shape)],
+            &options,
+            &observations,
+        ),
     };
 
     // Ensure generated code ends with exactly one newline
@@ -145,21 +439,130 @@ pub fn codegen_from_shape(name: &str, shape: &Shape, options: Options) -> Result
     Ok(generated_code)
 }
 
+/// The shape in the form the generator for an output mode takes
+fn shape_for_output(shape: Shape, output_mode: &OutputMode) -> Shape {
+    let shape = match output_mode {
+        // recursive types given as input are used as they are, but not inferred
+        OutputMode::ShapeJson | OutputMode::MockData => shape,
+        _ if supports_recursive_types(output_mode) => shape::fold_recursive_structs(shape),
+        _ => shape::without_recursion(shape),
+    };
+    if supports_generic_types(output_mode) {
+        shape
+    } else {
+        shape::without_type_params(shape)
+    }
+}
+
+fn supports_generic_types(output_mode: &OutputMode) -> bool {
+    matches!(
+        output_mode,
+        OutputMode::Rust
+            | OutputMode::Typescript
+            | OutputMode::KotlinJackson
+            | OutputMode::KotlinKotlinx
+            | OutputMode::KotlinMoshi
+            | OutputMode::KotlinGson
+            | OutputMode::PythonPydantic
+            | OutputMode::PythonPydanticV1
+            | OutputMode::PythonDataclass
+            | OutputMode::PythonTypedDict
+            | OutputMode::PythonAttrs
+            | OutputMode::PythonMsgspec
+            | OutputMode::Shape
+            | OutputMode::ShapeJson
+    )
+}
+
+fn supports_recursive_types(output_mode: &OutputMode) -> bool {
+    matches!(
+        output_mode,
+        OutputMode::Rust
+            | OutputMode::Typescript
+            | OutputMode::KotlinJackson
+            | OutputMode::KotlinKotlinx
+            | OutputMode::KotlinMoshi
+            | OutputMode::KotlinGson
+            | OutputMode::PythonPydantic
+            | OutputMode::PythonPydanticV1
+            | OutputMode::PythonDataclass
+            | OutputMode::PythonTypedDict
+            | OutputMode::PythonAttrs
+            | OutputMode::PythonMsgspec
+            | OutputMode::ZodSchema
+            | OutputMode::JsonSchema
+            | OutputMode::Shape
+            | OutputMode::Template
+            | OutputMode::Markdown
+            | OutputMode::Html
+            | OutputMode::Custom(_)
+    )
+}
+
+fn supports_shared_types(output_mode: &OutputMode) -> bool {
+    matches!(
+        output_mode,
+        OutputMode::Rust
+            | OutputMode::Typescript
+            | OutputMode::KotlinJackson
+            | OutputMode::KotlinKotlinx
+            | OutputMode::KotlinMoshi
+            | OutputMode::KotlinGson
+            | OutputMode::PythonPydantic
+            | OutputMode::PythonPydanticV1
+            | OutputMode::PythonDataclass
+            | OutputMode::PythonTypedDict
+            | OutputMode::PythonAttrs
+            | OutputMode::PythonMsgspec
+            | OutputMode::Template
+            | OutputMode::Markdown
+            | OutputMode::Html
+            | OutputMode::Custom(_)
+    )
+}
+
+#[allow(clippy::result_large_err)]
+fn template_output(
+    roots: &[generation::Root],
+    options: &Options,
+    observations: &Observations,
+) -> Result<String, JTError> {
+    let template = options
+        .template
+        .as_deref()
+        .ok_or_else(|| JTError::TemplateError("No template given".to_string()))?;
+    generation::template::template_output(template, roots, options, observations)
+        .map_err(JTError::TemplateError)
+}
+
+#[allow(clippy::result_large_err)]
+fn custom_generator(name: &str) -> Result<std::sync::Arc<dyn Generator>, JTError> {
+    generation::generator::registered_generator(name)
+        .ok_or_else(|| JTError::UnknownOutputMode(name.to_string()))
+}
+
 /// Parse "names" like `pub(crate) Foo` into a name and a visibility option
 fn handle_pub_in_name<'a>(name: &'a str, options: &mut Options) -> &'a str {
+    let (visibility, name) = split_visibility(name);
+    if let Some(visibility) = visibility {
+        options.type_visibility = visibility;
+    }
+    name
+}
+
+/// Split "names" like `pub(crate) Foo` into the visibility, if any, and the name
+fn split_visibility(name: &str) -> (Option<String>, &str) {
     if let Some(suffix) = name.strip_prefix("pub ") {
-        options.type_visibility = "pub".to_string();
-        return suffix;
+        return (Some("pub".to_string()), suffix);
     }
     if name.starts_with("pub(") {
         // MSRV: after 1.52 use split_once
         let split = name.splitn(2, ") ").collect::<Vec<_>>();
         if split.len() == 2 {
-            options.type_visibility = format!("{})", split[0]);
-            return split[1];
+            return (Some(format!("{})", split[0])), split[1]);
         }
     }
-    name
+    (None, name)
 }
 
 fn infer_source_type(s: &str) -> SampleSource {
@@ -181,7 +584,7 @@ fn infer_from_sample(
     source: &SampleSource,
     options: &Options,
     hints: &Hints,
-) -> Result<Shape, JTError> {
+) -> Result<(Shape, Observations), JTError> {
     let parse_result = match *source {
         #[cfg(feature = "remote-samples")]
         SampleSource::Url(url) => {
@@ -202,6 +605,20 @@ fn infer_from_sample(
     Ok(parse_result?)
 }
 
+#[allow(clippy::result_large_err)]
+fn read_source(source: &SampleSource) -> Result<String, JTError> {
+    Ok(match *source {
+        #[cfg(feature = "remote-samples")]
+        SampleSource::Url(url) => ureq::get(url)
+            .call()?
+            .into_string()
+            .map_err(|_| inference::JsonInputErr::IoErr)?,
+        #[cfg(feature = "local-samples")]
+        SampleSource::File(path) => std::fs::read_to_string(path)?,
+        SampleSource::Text(text) => text.to_string(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,434 @@
+use std::collections::HashMap;
+
+use crate::shape::{Shape, strip_option};
+
+const MAX_EXAMPLES: usize = 3;
+const MAX_EXAMPLE_LENGTH: usize = 32;
+
+/// Statistics about the values seen at each JSON pointer during inference
+///
+/// Only collected when asked for, since it is not needed for the inference itself.
+/// As with hints, `-` is used in pointers for any array element.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Observations {
+    pointers: HashMap<String, PointerObservations>,
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct PointerObservations {
+    /// How many values were seen at this pointer
+    pub count: usize,
+    /// Up to `MAX_EXAMPLES` distinct primitive values, as JSON
+    pub examples: Vec<String>,
+    /// Bounds that held for every value seen, if constraints are inferred
+    pub bounds: Bounds,
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Bounds {
+    /// Shortest and longest string, in characters
+    pub length: Option<(usize, usize)>,
+    /// Smallest and largest number, with the JSON literal for each
+    pub range: Option<((f64, String), (f64, String))>,
+    /// Fewest and most array elements
+    pub items: Option<(usize, usize)>,
+    /// A format that every string matched
+    pub format: Option<StringFormat>,
+}
+
+/// Well-known formats strings are checked for
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum StringFormat {
+    Email,
+    Url,
+    Uuid,
+    Date,
+    DateTime,
+}
+
+impl StringFormat {
+    fn detect(s: &str) -> Option<Self> {
+        if is_uuid(s) {
+            Some(StringFormat::Uuid)
+        } else if is_date(s) {
+            Some(StringFormat::Date)
+        } else if let (Some(date), Some(time)) = (s.get(..10), s.get(10..))
+            && is_date(date)
+            && is_time(time)
+        {
+            Some(StringFormat::DateTime)
+        } else if s.starts_with("http://") || s.starts_with("https://") {
+            Some(StringFormat::Url)
+        } else if is_email(s) {
+            Some(StringFormat::Email)
+        } else {
+            None
+        }
+    }
+
+    /// The name of the format in JSON Schema
+    pub fn json_schema_name(self) -> &'static str {
+        match self {
+            StringFormat::Email => "email",
+            StringFormat::Url => "uri",
+            StringFormat::Uuid => "uuid",
+            StringFormat::Date => "date",
+            StringFormat::DateTime => "date-time",
+        }
+    }
+
+    /// A regular expression matching the format, loosely
+    pub fn pattern(self) -> &'static str {
+        match self {
+            StringFormat::Email => r"^[^@\s]+@[^@\s]+\.[^@\s]+$",
+            StringFormat::Url => r"^https?://\S+$",
+            StringFormat::Uuid => {
+                r"^[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}$"
+            }
+            StringFormat::Date => r"^\d{4}-\d{2}-\d{2}$",
+            StringFormat::DateTime => r"^\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}",
+        }
+    }
+}
+
+/// A constraint that held for every value seen at a pointer
+#[derive(Debug, PartialEq, Clone)]
+pub enum Constraint {
+    MinLength(usize),
+    MaxLength(usize),
+    /// Inclusive, as a literal of the right kind of number
+    Minimum(String),
+    /// Inclusive, as a literal of the right kind of number
+    Maximum(String),
+    /// Only inferred as non-empty, since the exact count is rarely meaningful
+    NonEmpty,
+    Format(StringFormat),
+}
+
+impl PointerObservations {
+    /// Whether the examples are all the distinct values that were seen, like for an enum
+    pub fn has_all_values(&self) -> bool {
+        self.examples.len() < MAX_EXAMPLES
+    }
+}
+
+impl Observations {
+    pub fn record(&mut self, pointer: &str, example: Option<String>) {
+        let observed = self.pointers.entry(pointer.to_string()).or_default();
+        observed.count += 1;
+        if let Some(example) = example
+            && observed.examples.len() < MAX_EXAMPLES
+            && !observed.examples.contains(&example)
+        {
+            observed.examples.push(example);
+        }
+    }
+
+    pub fn record_string(&mut self, pointer: &str, s: &str) {
+        let bounds = &mut self.pointers.entry(pointer.to_string()).or_default().bounds;
+        let format = StringFormat::detect(s);
+        let length = s.chars().count();
+        bounds.format = match bounds.length {
+            None => format,
+            Some(_) if bounds.format == format => format,
+            Some(_) => None,
+        };
+        bounds.length = Some(match bounds.length {
+            None => (length, length),
+            Some((min, max)) => (min.min(length), max.max(length)),
+        });
+    }
+
+    pub fn record_number(&mut self, pointer: &str, literal: &str) {
+        let value = match literal.parse::<f64>() {
+            Ok(value) => value,
+            Err(_) => return,
+        };
+        let bounds = &mut self.pointers.entry(pointer.to_string()).or_default().bounds;
+        let number = (value, literal.to_string());
+        bounds.range = Some(match bounds.range.take() {
+            None => (number.clone(), number),
+            Some((min, max)) => (
+                if value < min.0 { number.clone() } else { min },
+                if value > max.0 { number } else { max },
+            ),
+        });
+    }
+
+    pub fn record_items(&mut self, pointer: &str, len: usize) {
+        let bounds = &mut self.pointers.entry(pointer.to_string()).or_default().bounds;
+        bounds.items = Some(match bounds.items {
+            None => (len, len),
+            Some((min, max)) => (min.min(len), max.max(len)),
+        });
+    }
+
+    /// Add the observations for another root, with their pointers put under a prefix
+    pub fn extend_prefixed(&mut self, prefix: &str, other: Observations) {
+        for (pointer, observed) in other.pointers {
+            self.pointers
+                .insert(format!("{}{}", prefix, pointer), observed);
+        }
+    }
+
+    pub fn get(&self, pointer: &str) -> Option<&PointerObservations> {
+        self.pointers.get(pointer)
+    }
+
+    /// Lines of documentation for the value at a pointer, without any comment syntax
+    pub fn doc_lines(&self, pointer: &str) -> Vec<String> {
+        let mut lines = Vec::new();
+        let observed = match self.get(pointer) {
+            Some(observed) => observed,
+            None => return lines,
+        };
+
+        match observed.examples.len() {
+            0 => {}
+            1 => lines.push(format!("Example: {}", observed.examples[0])),
+            _ => lines.push(format!("Examples: {}", observed.examples.join(", "))),
+        }
+
+        // Only worth mentioning for values that are sometimes missing
+        let parent = &pointer[..pointer.rfind('/').unwrap_or(0)];
+        if let Some(parent) = self.get(parent)
+            && parent.count > observed.count
+        {
+            lines.push(format!(
+                "Present in {} of {} samples",
+                observed.count, parent.count
+            ));
+        }
+
+        lines
+    }
+
+    /// The constraints that apply to a value of the given shape at a pointer
+    pub fn constraints(&self, pointer: &str, shape: &Shape) -> Vec<Constraint> {
+        let mut constraints = Vec::new();
+        let bounds = match self.get(pointer) {
+            Some(observed) => &observed.bounds,
+            None => return constraints,
+        };
+        match strip_option(shape) {
+            Shape::StringT => match (bounds.format, bounds.length) {
+                (Some(format), _) => constraints.push(Constraint::Format(format)),
+                (None, Some((min, max))) => {
+                    if min > 0 {
+                        constraints.push(Constraint::MinLength(min));
+                    }
+                    constraints.push(Constraint::MaxLength(max));
+                }
+                (None, None) => {}
+            },
+            Shape::Integer | Shape::Floating => {
+                if let Some(((_, min), (_, max))) = &bounds.range {
+                    let floating = matches!(strip_option(shape), Shape::Floating);
+                    constraints.push(Constraint::Minimum(number_literal(min, floating)));
+                    constraints.push(Constraint::Maximum(number_literal(max, floating)));
+                }
+            }
+            Shape::VecT { .. } | Shape::Tuple(..) => {
+                if let Some((min, _)) = bounds.items
+                    && min > 0
+                {
+                    constraints.push(Constraint::NonEmpty);
+                }
+            }
+            _ => {}
+        }
+        constraints
+    }
+}
+
+/// A JSON number as a literal of the right kind, e.g. `1.0` rather than `1` for floats
+fn number_literal(literal: &str, floating: bool) -> String {
+    if floating && !literal.contains(['.', 'e', 'E']) {
+        format!("{}.0", literal)
+    } else {
+        literal.to_string()
+    }
+}
+
+fn is_uuid(s: &str) -> bool {
+    s.len() == 36
+        && s.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+/// `YYYY-MM-DD`
+fn is_date(s: &str) -> bool {
+    s.len() == 10
+        && s.char_indices().all(|(i, c)| match i {
+            4 | 7 => c == '-',
+            _ => c.is_ascii_digit(),
+        })
+}
+
+/// `THH:MM:SS`, followed by anything (fractions of seconds or offset)
+fn is_time(s: &str) -> bool {
+    s.get(..9).is_some_and(|s| {
+        s.char_indices().all(|(i, c)| match i {
+            0 => c == 'T',
+            3 | 6 => c == ':',
+            _ => c.is_ascii_digit(),
+        })
+    })
+}
+
+fn is_email(s: &str) -> bool {
+    match s.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !s.contains(char::is_whitespace)
+                && !domain.contains('@')
+        }
+        None => false,
+    }
+}
+
+/// Extend a JSON pointer with a reference token, escaped as per RFC 6901
+pub fn child_pointer(pointer: &str, token: &str) -> String {
+    format!(
+        "{}/{}",
+        pointer,
+        token.replace('~', "~0").replace('/', "~1")
+    )
+}
+
+/// A JSDoc/KDoc style comment, on a single line if possible
+pub fn block_doc_comment(lines: &[String], indent: &str) -> String {
+    let lines: Vec<String> = lines
+        .iter()
+        .map(|line| line.replace("*/", "*\\/"))
+        .collect();
+    match lines.len() {
+        0 => String::new(),
+        1 => format!("{}/** {} */\n", indent, lines[0]),
+        _ => {
+            let mut comment = format!("{}/**\n", indent);
+            for line in lines {
+                comment += &format!("{} * {}\n", indent, line);
+            }
+            comment + indent + " */\n"
+        }
+    }
+}
+
+/// A string value as JSON, shortened if it is long
+pub fn string_example(s: &str) -> String {
+    let mut example = String::from("\"");
+    for (i, c) in s.chars().enumerate() {
+        if i == MAX_EXAMPLE_LENGTH {
+            example.push_str("...");
+            break;
+        }
+        match c {
+            '"' => example.push_str("\\\""),
+            '\\' => example.push_str("\\\\"),
+            '\n' => example.push_str("\\n"),
+            '\r' => example.push_str("\\r"),
+            '\t' => example.push_str("\\t"),
+            c if c.is_control() => example.push_str(&format!("\\u{:04x}", c as u32)),
+            c => example.push(c),
+        }
+    }
+    example.push('"');
+    example
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presence_and_examples() {
+        let mut observations = Observations::default();
+        for id in ["0", "1", "1", "2"] {
+            observations.record("/-", None);
+            observations.record("/-/id", Some(id.to_string()));
+        }
+        observations.record("/-/note", Some(string_example("a\n\"b\"")));
+
+        assert_eq!(
+            observations.doc_lines("/-/id"),
+            vec!["Examples: 0, 1, 2".to_string()]
+        );
+        assert_eq!(
+            observations.doc_lines("/-/note"),
+            vec![
+                r#"Example: "a\n\"b\"""#.to_string(),
+                "Present in 1 of 4 samples".to_string()
+            ]
+        );
+        assert!(observations.doc_lines("/-/missing").is_empty());
+    }
+
+    #[test]
+    fn long_examples_are_shortened() {
+        assert_eq!(
+            string_example("2024-01-01T00:00:00.000000000+00:00"),
+            r#""2024-01-01T00:00:00.000000000+00...""#
+        );
+        assert_eq!(child_pointer("/a", "b/c~d"), "/a/b~1c~0d");
+    }
+
+    #[test]
+    fn constraints() {
+        let mut observations = Observations::default();
+        for (name, email, score) in [("Al", "al@example.com", "3"), ("Beatrice", "b@x.org", "-1")] {
+            observations.record_string("/name", name);
+            observations.record_string("/email", email);
+            observations.record_number("/score", score);
+        }
+        observations.record_string("/email", "not an email");
+        observations.record_items("/tags", 2);
+        observations.record_items("/tags", 1);
+
+        assert_eq!(
+            observations.constraints("/name", &Shape::StringT),
+            vec![Constraint::MinLength(2), Constraint::MaxLength(8)]
+        );
+        assert_eq!(
+            observations.constraints("/email", &Shape::StringT),
+            vec![Constraint::MinLength(7), Constraint::MaxLength(14)]
+        );
+        assert_eq!(
+            observations.constraints("/score", &Shape::Floating),
+            vec![
+                Constraint::Minimum("-1.0".into()),
+                Constraint::Maximum("3.0".into())
+            ]
+        );
+        let tags = Shape::VecT {
+            elem_type: Box::new(Shape::StringT),
+        };
+        assert_eq!(
+            observations.constraints("/tags", &Shape::Optional(Box::new(tags))),
+            vec![Constraint::NonEmpty]
+        );
+    }
+
+    #[test]
+    fn string_formats() {
+        let detect = StringFormat::detect;
+        assert_eq!(detect("a@b.co"), Some(StringFormat::Email));
+        assert_eq!(detect("https://example.com/a"), Some(StringFormat::Url));
+        assert_eq!(
+            detect("123e4567-e89b-12d3-a456-426614174000"),
+            Some(StringFormat::Uuid)
+        );
+        assert_eq!(detect("2024-02-29"), Some(StringFormat::Date));
+        assert_eq!(
+            detect("2024-02-29T12:00:00.000Z"),
+            Some(StringFormat::DateTime)
+        );
+        assert_eq!(detect("2024-02-29 12:00"), None);
+        assert_eq!(detect("@handle"), None);
+    }
+}
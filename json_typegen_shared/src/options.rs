@@ -1,3 +1,4 @@
+use crate::generation::generator::registered_generator;
 use crate::hints::Hint;
 
 /// Options for the code generation
@@ -20,6 +21,20 @@ pub struct Options {
     pub import_style: ImportStyle,
     pub collect_additional: bool,
     pub infer_map_threshold: Option<usize>,
+    pub graphql_input_types: bool,
+    pub avro_namespace: Option<String>,
+    pub sql_dialect: SqlDialect,
+    pub json_schema_draft: JsonSchemaDraft,
+    pub borrow: BorrowStyle,
+    pub doc_comments: bool,
+    pub round_trip: bool,
+    pub infer_constraints: bool,
+    pub validation_crate: ValidationCrate,
+    pub mock_seed: u64,
+    pub mock_count: usize,
+    pub mock_fill_optional: f64,
+    pub mock_array_length: (usize, usize),
+    pub template: Option<String>,
 }
 
 impl Default for Options {
@@ -39,10 +54,40 @@ impl Default for Options {
             import_style: ImportStyle::AddImports,
             collect_additional: false,
             infer_map_threshold: None,
+            graphql_input_types: false,
+            avro_namespace: None,
+            sql_dialect: SqlDialect::Postgres,
+            json_schema_draft: JsonSchemaDraft::Draft07,
+            borrow: BorrowStyle::Owned,
+            doc_comments: false,
+            round_trip: false,
+            infer_constraints: false,
+            validation_crate: ValidationCrate::Validator,
+            mock_seed: 0,
+            mock_count: 1,
+            mock_fill_optional: 0.5,
+            mock_array_length: (1, 3),
+            template: None,
         }
     }
 }
 
+impl Options {
+    /// Whether example values should be observed during inference
+    pub(crate) fn collect_examples(&self) -> bool {
+        self.doc_comments
+            || matches!(
+                self.output_mode,
+                OutputMode::MockData | OutputMode::Markdown | OutputMode::Html
+            )
+    }
+
+    /// Whether bounds for constraints should be observed during inference
+    pub(crate) fn collect_bounds(&self) -> bool {
+        self.infer_constraints || self.output_mode == OutputMode::MockData
+    }
+}
+
 #[cfg(feature = "option-parsing")]
 impl Options {
     pub(crate) fn macro_default() -> Options {
@@ -84,10 +129,36 @@ pub enum OutputMode {
     TypescriptTypeAlias,
     KotlinJackson,
     KotlinKotlinx,
+    KotlinMoshi,
+    KotlinGson,
     PythonPydantic,
+    PythonPydanticV1,
+    PythonDataclass,
+    PythonTypedDict,
+    PythonAttrs,
+    PythonMsgspec,
     JsonSchema,
     ZodSchema,
+    Valibot,
+    IoTs,
+    ArkType,
+    EffectSchema,
+    Protobuf,
+    GraphQL,
+    AvroSchema,
+    SqlDdl,
     Shape,
+    /// The inferred shape in the format read by `InputMode::ShapeJson`
+    ShapeJson,
+    /// Made up JSON documents of the inferred shape
+    MockData,
+    /// Output from the template in `Options::template`
+    Template,
+    /// Documentation of the types, with a table of the fields for each
+    Markdown,
+    Html,
+    /// An output mode added with `register_generator`
+    Custom(String),
 }
 
 impl OutputMode {
@@ -99,10 +170,32 @@ impl OutputMode {
             "kotlin" => Some(OutputMode::KotlinJackson),
             "kotlin/jackson" => Some(OutputMode::KotlinJackson),
             "kotlin/kotlinx" => Some(OutputMode::KotlinKotlinx),
+            "kotlin/moshi" => Some(OutputMode::KotlinMoshi),
+            "kotlin/gson" => Some(OutputMode::KotlinGson),
             "python" => Some(OutputMode::PythonPydantic),
+            "python/pydantic" => Some(OutputMode::PythonPydantic),
+            "python/pydantic_v1" => Some(OutputMode::PythonPydanticV1),
+            "python/dataclass" => Some(OutputMode::PythonDataclass),
+            "python/typeddict" => Some(OutputMode::PythonTypedDict),
+            "python/attrs" => Some(OutputMode::PythonAttrs),
+            "python/msgspec" => Some(OutputMode::PythonMsgspec),
             "json_schema" => Some(OutputMode::JsonSchema),
             "zod" => Some(OutputMode::ZodSchema),
+            "valibot" => Some(OutputMode::Valibot),
+            "io_ts" => Some(OutputMode::IoTs),
+            "arktype" => Some(OutputMode::ArkType),
+            "effect_schema" => Some(OutputMode::EffectSchema),
+            "protobuf" => Some(OutputMode::Protobuf),
+            "graphql" => Some(OutputMode::GraphQL),
+            "avro" => Some(OutputMode::AvroSchema),
+            "sql" => Some(OutputMode::SqlDdl),
             "shape" => Some(OutputMode::Shape),
+            "shape_json" => Some(OutputMode::ShapeJson),
+            "mock" => Some(OutputMode::MockData),
+            "template" => Some(OutputMode::Template),
+            "markdown" => Some(OutputMode::Markdown),
+            "html" => Some(OutputMode::Html),
+            _ if registered_generator(s).is_some() => Some(OutputMode::Custom(s.to_string())),
             _ => None,
         }
     }
@@ -113,6 +206,8 @@ impl OutputMode {
 pub enum InputMode {
     Json,
     Sql,
+    /// A shape saved with `OutputMode::ShapeJson`
+    ShapeJson,
 }
 
 impl InputMode {
@@ -120,6 +215,92 @@ impl InputMode {
         match s {
             "json" => Some(InputMode::Json),
             "sql" => Some(InputMode::Sql),
+            "shape_json" => Some(InputMode::ShapeJson),
+            _ => None,
+        }
+    }
+}
+
+/// How strings and unknown values are represented in generated Rust types
+#[non_exhaustive]
+#[derive(Debug, PartialEq, Clone)]
+pub enum BorrowStyle {
+    /// Owned `String` and `serde_json::Value`
+    Owned,
+    /// `&'a str` and `&'a RawValue`, borrowed from the input.
+    /// Fails to deserialize strings with escape sequences.
+    Str,
+    /// `Cow<'a, str>` and `&'a RawValue`, borrowing when possible
+    Cow,
+}
+
+impl BorrowStyle {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "owned" => Some(BorrowStyle::Owned),
+            "str" => Some(BorrowStyle::Str),
+            "cow" => Some(BorrowStyle::Cow),
+            _ => None,
+        }
+    }
+}
+
+/// The crate to use for validation attributes on generated Rust types
+#[non_exhaustive]
+#[derive(Debug, PartialEq, Clone)]
+pub enum ValidationCrate {
+    /// `#[validate(...)]` from the `validator` crate
+    Validator,
+    /// `#[garde(...)]` from the `garde` crate
+    Garde,
+}
+
+impl ValidationCrate {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "validator" => Some(ValidationCrate::Validator),
+            "garde" => Some(ValidationCrate::Garde),
+            _ => None,
+        }
+    }
+}
+
+/// The JSON Schema version (or dialect) to generate schemas for
+#[non_exhaustive]
+#[derive(Debug, PartialEq, Clone)]
+pub enum JsonSchemaDraft {
+    Draft07,
+    Draft201909,
+    Draft202012,
+    /// The schema objects of OpenAPI 3.0, an extended subset of draft-05
+    OpenApi30,
+}
+
+impl JsonSchemaDraft {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "07" | "draft-07" => Some(JsonSchemaDraft::Draft07),
+            "2019-09" => Some(JsonSchemaDraft::Draft201909),
+            "2020-12" => Some(JsonSchemaDraft::Draft202012),
+            "openapi-3.0" => Some(JsonSchemaDraft::OpenApi30),
+            _ => None,
+        }
+    }
+}
+
+/// The SQL dialect used for the column types of generated tables
+#[non_exhaustive]
+#[derive(Debug, PartialEq, Clone)]
+pub enum SqlDialect {
+    Postgres,
+    Sqlite,
+}
+
+impl SqlDialect {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "postgres" | "postgresql" => Some(SqlDialect::Postgres),
+            "sqlite" => Some(SqlDialect::Sqlite),
             _ => None,
         }
     }
@@ -7,7 +7,10 @@ use syn::parse::{boolean, ident, string};
 use synom::{IResult, alt, call, named, punct};
 
 use crate::hints::Hint;
-use crate::options::{ImportStyle, InputMode, Options, OutputMode, StringTransform};
+use crate::options::{
+    BorrowStyle, ImportStyle, InputMode, JsonSchemaDraft, Options, OutputMode, SqlDialect,
+    StringTransform, ValidationCrate,
+};
 
 #[derive(PartialEq, Debug)]
 pub struct MacroInput {
@@ -126,12 +129,28 @@ fn options_with_defaults(input: &str, default_options: Options) -> Result<Option
         "import_style" => string_option(remaining, "import_style", |val| {
             options.import_style = ImportStyle::parse(&val).unwrap_or(ImportStyle::QualifiedPaths);
         }),
+        "borrow" => string_option(remaining, "borrow", |val| {
+            options.borrow = BorrowStyle::parse(&val).unwrap_or(BorrowStyle::Owned);
+        }),
+        "validation_crate" => string_option(remaining, "validation_crate", |val| {
+            options.validation_crate =
+                ValidationCrate::parse(&val).unwrap_or(ValidationCrate::Validator);
+        }),
         "field_visibility" => string_option(remaining, "field_visibility", |val| {
             options.field_visibility = Some(val);
         }),
         "deny_unknown_fields" => boolean_option(remaining, "deny_unknown_fields", |val| {
             options.deny_unknown_fields = val;
         }),
+        "doc_comments" => boolean_option(remaining, "doc_comments", |val| {
+            options.doc_comments = val;
+        }),
+        "round_trip" => boolean_option(remaining, "round_trip", |val| {
+            options.round_trip = val;
+        }),
+        "infer_constraints" => boolean_option(remaining, "infer_constraints", |val| {
+            options.infer_constraints = val;
+        }),
         "use_default_for_missing_fields" => {
             boolean_option(remaining, "use_default_for_missing_fields", |val| {
                 options.use_default_for_missing_fields = val;
@@ -149,6 +168,34 @@ fn options_with_defaults(input: &str, default_options: Options) -> Result<Option
         "infer_map_threshold" => string_option(remaining, "infer_map_threshold", |val| {
             options.infer_map_threshold = val.parse().ok();
         }),
+        "mock_seed" => string_option(remaining, "mock_seed", |val| {
+            options.mock_seed = val.parse().unwrap_or(0);
+        }),
+        "mock_count" => string_option(remaining, "mock_count", |val| {
+            options.mock_count = val.parse().unwrap_or(1);
+        }),
+        "mock_fill_optional" => string_option(remaining, "mock_fill_optional", |val| {
+            options.mock_fill_optional = val.parse().unwrap_or(0.5);
+        }),
+        "mock_array_length" => string_option(remaining, "mock_array_length", |val| {
+            options.mock_array_length = parse_range(&val).unwrap_or((1, 3));
+        }),
+        "template" => string_option(remaining, "template", |val| {
+            options.template = Some(val);
+        }),
+        "avro_namespace" => string_option(remaining, "avro_namespace", |val| {
+            options.avro_namespace = Some(val);
+        }),
+        "graphql_input_types" => boolean_option(remaining, "graphql_input_types", |val| {
+            options.graphql_input_types = val;
+        }),
+        "sql_dialect" => string_option(remaining, "sql_dialect", |val| {
+            options.sql_dialect = SqlDialect::parse(&val).unwrap_or(SqlDialect::Postgres);
+        }),
+        "json_schema_draft" => string_option(remaining, "json_schema_draft", |val| {
+            options.json_schema_draft =
+                JsonSchemaDraft::parse(&val).unwrap_or(JsonSchemaDraft::Draft07);
+        }),
         key if key.is_empty() || key.starts_with('/') => {
             let (rem, hints) = pointer_block(remaining)?;
             for hint in hints {
@@ -182,6 +229,9 @@ fn pointer_block(input: &str) -> Result<(&str, Vec<Hint>), String> {
         "type_name" => string_option(input_after_key, "type_name", |val| {
             hints.push(Hint::type_name(val));
         }),
+        "type_parameter" => string_option(input_after_key, "type_parameter", |val| {
+            hints.push(Hint::type_parameter(val));
+        }),
         _ => Err(format!("Unknown option: {}", key)),
     })?;
 
@@ -269,6 +319,13 @@ fn skip<'a>(input: &'a str, symbol: &'static str, msg: &str) -> Result<&'a str,
     }
 }
 
+/// An inclusive range like `"1..3"`, or a single number
+fn parse_range(s: &str) -> Option<(usize, usize)> {
+    let (min, max) = s.split_once("..").unwrap_or((s, s));
+    let (min, max) = (min.trim().parse().ok()?, max.trim().parse().ok()?);
+    if min <= max { Some((min, max)) } else { None }
+}
+
 #[cfg(test)]
 mod macro_input_tests {
     use super::*;
@@ -37,6 +37,13 @@ pub enum Shape {
         val_type: Box<Shape>,
     },
     Opaque(String),
+    /// `Recursive(T)` marks a struct that refers to itself, with `SelfRef` in its fields
+    Recursive(Box<Shape>),
+    /// A reference to the innermost enclosing `Recursive` shape
+    SelfRef,
+    /// `TypeParam(name, T)` is a value typed with a type parameter of the enclosing struct,
+    /// where `T` is the shape of the type argument
+    TypeParam(String, Box<Shape>),
 }
 
 pub fn fold_shapes(shapes: Vec<Shape>) -> Shape {
@@ -81,6 +88,9 @@ pub fn common_shape(a: Shape, b: Shape) -> Shape {
         (Struct { fields: f1 }, Struct { fields: f2 }) => Struct {
             fields: common_field_shapes(f1, f2),
         },
+        (TypeParam(n1, a), TypeParam(n2, b)) if n1 == n2 => {
+            TypeParam(n1, Box::new(common_shape(*a, *b)))
+        }
         (Opaque(t), _) | (_, Opaque(t)) => Opaque(t),
         (a, Nullable(b)) | (Nullable(b), a) => common_shape(a, *b).into_nullable(),
         _ => Any,
@@ -145,6 +155,9 @@ impl Shape {
                 e1.is_acceptable_substitution_for(e2)
             }
             (MapT { val_type: v1 }, MapT { val_type: v2 }) => v1.is_acceptable_substitution_for(v2),
+            (Recursive(a), Recursive(b)) => a.is_acceptable_substitution_for(b),
+            // A generic type can be used for any type argument, see `type_args`
+            (TypeParam(n1, _), TypeParam(n2, _)) => n1 == n2,
             (Tuple(a, _), Tuple(b, _)) => {
                 a.len() == b.len()
                     && a.iter()
@@ -168,6 +181,314 @@ impl Shape {
     }
 }
 
+/// The shape of a value when it is present and not null
+pub(crate) fn strip_option(shape: &Shape) -> &Shape {
+    match shape {
+        Shape::Optional(inner) | Shape::Nullable(inner) => strip_option(inner),
+        _ => shape,
+    }
+}
+
+/// Short arrays are inferred as tuples, but only become tuple types if the elements differ
+pub(crate) fn as_generated(shape: &Shape) -> Shape {
+    if let Shape::Tuple(shapes, _) = shape {
+        let folded = fold_shapes(shapes.clone());
+        if folded != Shape::Any || shapes.iter().all(|s| s == &Shape::Any) {
+            return Shape::VecT {
+                elem_type: Box::new(folded),
+            };
+        }
+    }
+    shape.clone()
+}
+
+/// A short description of the shape, for messages
+pub(crate) fn describe(shape: &Shape) -> String {
+    use self::Shape::*;
+    match shape {
+        Bottom => "nothing".into(),
+        Any => "any".into(),
+        Optional(inner) | Recursive(inner) | TypeParam(_, inner) => describe(inner),
+        Nullable(inner) => format!("nullable {}", describe(inner)),
+        Null => "null".into(),
+        Bool => "bool".into(),
+        StringT => "string".into(),
+        Integer => "integer".into(),
+        Floating => "floating".into(),
+        VecT { elem_type } => format!("array of {}", describe(elem_type)),
+        Struct { .. } => "object".into(),
+        Tuple(shapes, _) => format!("tuple of {}", shapes.len()),
+        MapT { val_type } => format!("map of {}", describe(val_type)),
+        Opaque(name) => name.clone(),
+        SelfRef => "self".into(),
+    }
+}
+
+/// Find objects nested in an object they are compatible with, like in a tree,
+/// and turn them into references to that object
+pub(crate) fn fold_recursive_structs(shape: Shape) -> Shape {
+    use self::Shape::*;
+    match shape {
+        Struct { fields } => {
+            let mut nested = Vec::new();
+            let folded: LinkedHashMap<String, Shape> = fields
+                .iter()
+                .map(|(key, shape)| {
+                    let shape =
+                        replace_compatible_structs(shape.clone(), &fields, key, &mut nested);
+                    (key.clone(), shape)
+                })
+                .collect();
+            if nested.is_empty() {
+                return Struct {
+                    fields: fold_recursive_fields(folded),
+                };
+            }
+            let fields = nested.into_iter().fold(folded, common_field_shapes);
+            Recursive(Box::new(Struct {
+                fields: fold_recursive_fields(fields),
+            }))
+        }
+        // A self reference would be ambiguous if an enclosing recursive struct is referred to
+        Recursive(_) => shape,
+        VecT { elem_type: e } => VecT {
+            elem_type: Box::new(fold_recursive_structs(*e)),
+        },
+        MapT { val_type: v } => MapT {
+            val_type: Box::new(fold_recursive_structs(*v)),
+        },
+        Tuple(shapes, n) => Tuple(shapes.into_iter().map(fold_recursive_structs).collect(), n),
+        Optional(e) => Optional(Box::new(fold_recursive_structs(*e))),
+        Nullable(e) => Nullable(Box::new(fold_recursive_structs(*e))),
+        TypeParam(name, arg) => TypeParam(name, Box::new(fold_recursive_structs(*arg))),
+        _ => shape,
+    }
+}
+
+fn fold_recursive_fields(fields: LinkedHashMap<String, Shape>) -> LinkedHashMap<String, Shape> {
+    fields
+        .into_iter()
+        .map(|(key, shape)| {
+            if contains_self_ref(&shape) {
+                (key, shape)
+            } else {
+                (key, fold_recursive_structs(shape))
+            }
+        })
+        .collect()
+}
+
+fn contains_self_ref(shape: &Shape) -> bool {
+    use self::Shape::*;
+    match shape {
+        SelfRef => true,
+        Struct { fields } => fields.values().any(contains_self_ref),
+        VecT { elem_type: e } | MapT { val_type: e } | Optional(e) | Nullable(e) => {
+            contains_self_ref(e)
+        }
+        Tuple(shapes, _) => shapes.iter().any(contains_self_ref),
+        _ => false,
+    }
+}
+
+/// The type arguments of an object, for each type parameter used in it and not in nested objects,
+/// along with the key of the field it is used in. In the order the parameters are first used.
+pub(crate) fn type_args(shape: &Shape) -> Vec<(&str, &Shape)> {
+    fn collect<'a>(
+        key: &'a str,
+        shape: &'a Shape,
+        names: &mut Vec<&'a str>,
+        args: &mut Vec<(&'a str, &'a Shape)>,
+    ) {
+        use self::Shape::*;
+        match shape {
+            TypeParam(name, arg) => {
+                collect(key, arg, names, args);
+                if !names.contains(&name.as_str()) {
+                    names.push(name);
+                    args.push((key, arg));
+                }
+            }
+            VecT { elem_type: e } | MapT { val_type: e } | Optional(e) | Nullable(e) => {
+                collect(key, e, names, args)
+            }
+            Tuple(shapes, _) => shapes.iter().for_each(|s| collect(key, s, names, args)),
+            _ => {}
+        }
+    }
+
+    let mut names = Vec::new();
+    let mut args = Vec::new();
+    if let Shape::Struct { fields } = strip_recursive(shape) {
+        for (key, shape) in fields {
+            collect(key, shape, &mut names, &mut args);
+        }
+    }
+    args
+}
+
+fn strip_recursive(shape: &Shape) -> &Shape {
+    match shape {
+        Shape::Recursive(inner) => inner,
+        _ => shape,
+    }
+}
+
+/// The shape for output without recursive types, with references to an enclosing object as `Any`
+pub(crate) fn without_recursion(shape: Shape) -> Shape {
+    match shape {
+        Shape::Recursive(e) => without_recursion(*e),
+        Shape::SelfRef => Shape::Any,
+        shape => map_children(shape, without_recursion),
+    }
+}
+
+/// The shape for output without generic types, with type parameters replaced by their arguments
+pub(crate) fn without_type_params(shape: Shape) -> Shape {
+    match shape {
+        Shape::TypeParam(_, arg) => without_type_params(*arg),
+        shape => map_children(shape, without_type_params),
+    }
+}
+
+/// The shape with `f` applied to the shapes directly in it
+fn map_children(shape: Shape, f: fn(Shape) -> Shape) -> Shape {
+    use self::Shape::*;
+    match shape {
+        Struct { fields } => Struct {
+            fields: fields
+                .into_iter()
+                .map(|(key, shape)| (key, f(shape)))
+                .collect(),
+        },
+        VecT { elem_type: e } => VecT {
+            elem_type: Box::new(f(*e)),
+        },
+        MapT { val_type: v } => MapT {
+            val_type: Box::new(f(*v)),
+        },
+        Tuple(shapes, n) => Tuple(shapes.into_iter().map(f).collect(), n),
+        Optional(e) => Optional(Box::new(f(*e))),
+        Nullable(e) => Nullable(Box::new(f(*e))),
+        Recursive(e) => Recursive(Box::new(f(*e))),
+        TypeParam(name, arg) => TypeParam(name, Box::new(f(*arg))),
+        _ => shape,
+    }
+}
+
+/// An object is compatible with an ancestor if one has a subset of the keys of the other, the
+/// fields they share have compatible shapes, and the key used to get to it from the ancestor
+/// (like `children`) leads to more of the same objects or to nothing, like `[]` or `null`
+fn replace_compatible_structs(
+    shape: Shape,
+    ancestor: &LinkedHashMap<String, Shape>,
+    via_key: &str,
+    nested: &mut Vec<LinkedHashMap<String, Shape>>,
+) -> Shape {
+    use self::Shape::*;
+    match shape {
+        Struct { fields } => {
+            let is_candidate = fields.contains_key(via_key)
+                && (fields.keys().all(|key| ancestor.contains_key(key))
+                    || ancestor.keys().all(|key| fields.contains_key(key)))
+                && fields.iter().all(|(key, shape)| {
+                    ancestor
+                        .get(key)
+                        .is_none_or(|ancestor_shape| is_compatible(ancestor_shape, shape))
+                });
+            if is_candidate {
+                let mut inner_nested = Vec::new();
+                let replaced: LinkedHashMap<String, Shape> = fields
+                    .iter()
+                    .map(|(key, shape)| {
+                        let shape = replace_compatible_structs(
+                            shape.clone(),
+                            ancestor,
+                            key,
+                            &mut inner_nested,
+                        );
+                        (key.clone(), shape)
+                    })
+                    .collect();
+                if is_self_reference(&replaced[via_key]) {
+                    nested.extend(inner_nested);
+                    nested.push(replaced);
+                    return SelfRef;
+                }
+            }
+            Struct {
+                fields: fields
+                    .into_iter()
+                    .map(|(key, shape)| {
+                        let shape = replace_compatible_structs(shape, ancestor, via_key, nested);
+                        (key, shape)
+                    })
+                    .collect(),
+            }
+        }
+        VecT { elem_type: e } => VecT {
+            elem_type: Box::new(replace_compatible_structs(*e, ancestor, via_key, nested)),
+        },
+        MapT { val_type: v } => MapT {
+            val_type: Box::new(replace_compatible_structs(*v, ancestor, via_key, nested)),
+        },
+        Tuple(shapes, n) => Tuple(
+            shapes
+                .into_iter()
+                .map(|shape| replace_compatible_structs(shape, ancestor, via_key, nested))
+                .collect(),
+            n,
+        ),
+        Optional(e) => Optional(Box::new(replace_compatible_structs(
+            *e, ancestor, via_key, nested,
+        ))),
+        Nullable(e) => Nullable(Box::new(replace_compatible_structs(
+            *e, ancestor, via_key, nested,
+        ))),
+        _ => shape,
+    }
+}
+
+/// Whether a shape is a self reference, possibly in a list, map or option, or is empty,
+/// like the children of a leaf in a tree
+fn is_self_reference(shape: &Shape) -> bool {
+    use self::Shape::*;
+    match shape {
+        SelfRef | Bottom | Null => true,
+        Optional(e) | Nullable(e) | VecT { elem_type: e } | MapT { val_type: e } => {
+            is_self_reference(e)
+        }
+        Tuple(shapes, _) => shapes.iter().all(is_self_reference),
+        _ => false,
+    }
+}
+
+/// Whether two shapes could be found at the same place in different samples, without
+/// conflicting. Missing information, like in empty lists and `null`, fits anything.
+fn is_compatible(a: &Shape, b: &Shape) -> bool {
+    use self::Shape::*;
+    match (a, b) {
+        (Bottom | Null | Any | SelfRef, _) | (_, Bottom | Null | Any | SelfRef) => true,
+        (Optional(a) | Nullable(a) | Recursive(a), b)
+        | (b, Optional(a) | Nullable(a) | Recursive(a)) => is_compatible(a, b),
+        (Integer, Floating) | (Floating, Integer) => true,
+        (VecT { elem_type: e1 }, VecT { elem_type: e2 }) => is_compatible(e1, e2),
+        (MapT { val_type: v1 }, MapT { val_type: v2 }) => is_compatible(v1, v2),
+        (Tuple(shapes, _), VecT { elem_type: e }) | (VecT { elem_type: e }, Tuple(shapes, _)) => {
+            shapes.iter().all(|shape| is_compatible(shape, e))
+        }
+        (Tuple(a, _), Tuple(b, _)) => {
+            a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| is_compatible(a, b))
+        }
+        (Struct { fields: f1 }, Struct { fields: f2 }) => f1.iter().all(|(key, shape)| {
+            f2.get(key)
+                .is_none_or(|shape2| is_compatible(shape, shape2))
+        }),
+        (TypeParam(n1, a), TypeParam(n2, b)) => n1 == n2 && is_compatible(a, b),
+        _ => a == b,
+    }
+}
+
 #[test]
 fn test_unify() {
     use self::Shape::*;
@@ -221,3 +542,50 @@ fn test_common_field_shapes() {
         );
     }
 }
+
+#[test]
+fn test_fold_recursive_structs() {
+    use self::Shape::*;
+    use crate::util::string_hashmap;
+
+    let leaf = Struct {
+        fields: string_hashmap! {
+            "name" => StringT,
+            "children" => VecT { elem_type: Box::new(Bottom) },
+        },
+    };
+    let middle = Struct {
+        fields: string_hashmap! {
+            "name" => StringT,
+            "children" => VecT { elem_type: Box::new(leaf) },
+            "size" => Integer,
+        },
+    };
+    let root = Struct {
+        fields: string_hashmap! {
+            "name" => StringT,
+            "children" => VecT { elem_type: Box::new(middle) },
+        },
+    };
+    assert_eq!(
+        fold_recursive_structs(root),
+        Recursive(Box::new(Struct {
+            fields: string_hashmap! {
+                "name" => StringT,
+                "children" => VecT { elem_type: Box::new(SelfRef) },
+                "size" => Optional(Box::new(Integer)),
+            },
+        }))
+    );
+
+    // Nested objects with a subset of the keys, but not the key used to get to them
+    let not_recursive = Struct {
+        fields: string_hashmap! {
+            "id" => Integer,
+            "owner" => Struct {
+                fields: string_hashmap! { "id" => Integer },
+            },
+        },
+    };
+    assert_eq!(fold_recursive_structs(not_recursive.clone()), not_recursive);
+}
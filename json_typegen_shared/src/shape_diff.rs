@@ -0,0 +1,297 @@
+use std::fmt;
+
+use crate::observations::child_pointer;
+use crate::shape::{Shape, as_generated, describe};
+
+/// A difference between an old and a new shape, at a [JSON Pointer] into the values
+///
+/// [JSON Pointer]: https://tools.ietf.org/html/rfc6901
+#[derive(Debug, PartialEq, Clone)]
+pub struct ShapeChange {
+    /// Where the change is, with `-` for any array element or map value
+    pub pointer: String,
+    pub kind: ChangeKind,
+    /// Whether types generated from the old shape can fail to deserialize values of the new shape
+    pub breaking: bool,
+}
+
+#[non_exhaustive]
+#[derive(Debug, PartialEq, Clone)]
+pub enum ChangeKind {
+    /// Breaking only with `deny_unknown_fields`
+    FieldAdded,
+    /// Breaking if the field was required
+    FieldRemoved,
+    /// Breaking, since the field used to always be present
+    BecameOptional,
+    BecameRequired,
+    /// Breaking, since the value used to never be null
+    BecameNullable,
+    BecameNonNullable,
+    /// Breaking, the new type accepts values the old one does not, e.g. `Integer` to `Floating`
+    TypeWidened {
+        old: Shape,
+        new: Shape,
+    },
+    /// The new type only has values the old one accepts, e.g. `Any` to `StringT`
+    TypeNarrowed {
+        old: Shape,
+        new: Shape,
+    },
+    /// Breaking, the types are unrelated
+    TypeChanged {
+        old: Shape,
+        new: Shape,
+    },
+}
+
+impl fmt::Display for ShapeChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let pointer = if self.pointer.is_empty() {
+            "(root)"
+        } else {
+            &self.pointer
+        };
+        let compatibility = if self.breaking {
+            "breaking"
+        } else {
+            "compatible"
+        };
+        write!(f, "{}: {}: ", compatibility, pointer)?;
+        match &self.kind {
+            ChangeKind::FieldAdded => write!(f, "field added"),
+            ChangeKind::FieldRemoved => write!(f, "field removed"),
+            ChangeKind::BecameOptional => write!(f, "field became optional"),
+            ChangeKind::BecameRequired => write!(f, "field became required"),
+            ChangeKind::BecameNullable => write!(f, "value became nullable"),
+            ChangeKind::BecameNonNullable => write!(f, "value is no longer null"),
+            ChangeKind::TypeWidened { old, new } => {
+                write!(
+                    f,
+                    "type widened from {} to {}",
+                    describe(old),
+                    describe(new)
+                )
+            }
+            ChangeKind::TypeNarrowed { old, new } => {
+                write!(
+                    f,
+                    "type narrowed from {} to {}",
+                    describe(old),
+                    describe(new)
+                )
+            }
+            ChangeKind::TypeChanged { old, new } => {
+                write!(
+                    f,
+                    "type changed from {} to {}",
+                    describe(old),
+                    describe(new)
+                )
+            }
+        }
+    }
+}
+
+/// Compare an old shape to a new one, like yesterday's response of an API to today's
+///
+/// Changes are classified as breaking if types generated from the old shape may fail to
+/// deserialize values of the new shape. Added fields are never classified as breaking,
+/// even though they are with `deny_unknown_fields`.
+pub fn diff_shapes(old: &Shape, new: &Shape) -> Vec<ShapeChange> {
+    let mut changes = Vec::new();
+    diff(&mut changes, "", old, new);
+    changes
+}
+
+fn diff(changes: &mut Vec<ShapeChange>, pointer: &str, old: &Shape, new: &Shape) {
+    use crate::shape::Shape::*;
+    if old == new {
+        return;
+    }
+    let (old, new) = (&as_generated(old), &as_generated(new));
+
+    let (old_null, old) = split_null(old);
+    let (new_null, new) = split_null(new);
+    if new_null && !old_null {
+        let breaking = !accepts(old, &Null);
+        changes.push(change(pointer, ChangeKind::BecameNullable, breaking));
+    } else if old_null && !new_null {
+        changes.push(change(pointer, ChangeKind::BecameNonNullable, false));
+    }
+
+    match (old, new) {
+        (_, Bottom) | (SelfRef, _) | (_, SelfRef) => {}
+        (Recursive(old), new) | (TypeParam(_, old), new) => diff(changes, pointer, old, new),
+        (old, Recursive(new)) | (old, TypeParam(_, new)) => diff(changes, pointer, old, new),
+        (Struct { fields: f1 }, Struct { fields: f2 }) => {
+            for (key, old_field) in f1.iter() {
+                let field_pointer = child_pointer(pointer, key);
+                let Some(new_field) = f2.get(key) else {
+                    let breaking = !is_optional(old_field);
+                    changes.push(change(&field_pointer, ChangeKind::FieldRemoved, breaking));
+                    continue;
+                };
+                if is_optional(new_field) && !is_optional(old_field) {
+                    changes.push(change(&field_pointer, ChangeKind::BecameOptional, true));
+                } else if is_optional(old_field) && !is_optional(new_field) {
+                    changes.push(change(&field_pointer, ChangeKind::BecameRequired, false));
+                }
+                diff(
+                    changes,
+                    &field_pointer,
+                    strip_optional(old_field),
+                    strip_optional(new_field),
+                );
+            }
+            for key in f2.keys().filter(|key| !f1.contains_key(*key)) {
+                let field_pointer = child_pointer(pointer, key);
+                changes.push(change(&field_pointer, ChangeKind::FieldAdded, false));
+            }
+        }
+        (VecT { elem_type: e1 }, VecT { elem_type: e2 }) => {
+            diff(changes, &child_pointer(pointer, "-"), e1, e2)
+        }
+        (MapT { val_type: v1 }, MapT { val_type: v2 }) => {
+            diff(changes, &child_pointer(pointer, "-"), v1, v2)
+        }
+        (Tuple(s1, _), Tuple(s2, _)) if s1.len() == s2.len() => {
+            for (i, (e1, e2)) in s1.iter().zip(s2.iter()).enumerate() {
+                diff(changes, &child_pointer(pointer, &i.to_string()), e1, e2);
+            }
+        }
+        (old, new) if old != new => {
+            let kind = if accepts(old, new) {
+                ChangeKind::TypeNarrowed {
+                    old: old.clone(),
+                    new: new.clone(),
+                }
+            } else if accepts(new, old) {
+                ChangeKind::TypeWidened {
+                    old: old.clone(),
+                    new: new.clone(),
+                }
+            } else {
+                ChangeKind::TypeChanged {
+                    old: old.clone(),
+                    new: new.clone(),
+                }
+            };
+            let breaking = !matches!(kind, ChangeKind::TypeNarrowed { .. });
+            changes.push(change(pointer, kind, breaking));
+        }
+        _ => {}
+    }
+}
+
+fn change(pointer: &str, kind: ChangeKind, breaking: bool) -> ShapeChange {
+    ShapeChange {
+        pointer: pointer.to_string(),
+        kind,
+        breaking,
+    }
+}
+
+/// Whether a type generated from `old` can deserialize any value of shape `new`
+///
+/// More lenient than `is_acceptable_substitution_for`, which is about reusing a type as is.
+fn accepts(old: &Shape, new: &Shape) -> bool {
+    use crate::shape::Shape::*;
+    match (&as_generated(old), &as_generated(new)) {
+        (Any | Bottom, _) | (_, Bottom) => true,
+        (Floating, Integer) => true,
+        (Optional(_) | Nullable(_) | Null, Null) => true,
+        (Optional(old) | Nullable(old), new) => accepts(old, strip_optional(new)),
+        (Recursive(old), new) | (TypeParam(_, old), new) => accepts(old, new),
+        (old, Recursive(new)) | (old, TypeParam(_, new)) => accepts(old, new),
+        (VecT { elem_type: e1 }, VecT { elem_type: e2 }) => accepts(e1, e2),
+        (MapT { val_type: v1 }, MapT { val_type: v2 }) => accepts(v1, v2),
+        (Struct { fields: f1 }, Struct { fields: f2 }) => {
+            f1.iter().all(|(key, old_field)| match f2.get(key) {
+                Some(new_field) => accepts(old_field, new_field),
+                None => is_optional(old_field),
+            })
+        }
+        _ => old.is_acceptable_substitution_for(new),
+    }
+}
+
+/// Whether the shape includes null, and the shape of non-null values
+fn split_null(shape: &Shape) -> (bool, &Shape) {
+    match shape {
+        Shape::Null => (true, &Shape::Bottom),
+        Shape::Nullable(inner) => (true, inner),
+        _ => (false, shape),
+    }
+}
+
+fn is_optional(shape: &Shape) -> bool {
+    matches!(shape, Shape::Optional(_))
+}
+
+fn strip_optional(shape: &Shape) -> &Shape {
+    match shape {
+        Shape::Optional(inner) => inner,
+        _ => shape,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linked_hash_map::LinkedHashMap;
+
+    fn object(fields: Vec<(&str, Shape)>) -> Shape {
+        let fields: LinkedHashMap<String, Shape> = fields
+            .into_iter()
+            .map(|(key, shape)| (key.to_string(), shape))
+            .collect();
+        Shape::Struct { fields }
+    }
+
+    fn opt(shape: Shape) -> Shape {
+        Shape::Optional(Box::new(shape))
+    }
+
+    #[test]
+    fn accepts_widest() {
+        assert!(accepts(&Shape::Floating, &Shape::Integer));
+        assert!(!accepts(&Shape::Integer, &Shape::Floating));
+        assert!(accepts(&Shape::Any, &Shape::StringT));
+        assert!(accepts(
+            &object(vec![("a", Shape::Integer), ("b", opt(Shape::Bool))]),
+            &object(vec![("a", Shape::Integer), ("c", Shape::Bool)]),
+        ));
+    }
+
+    #[test]
+    fn field_changes() {
+        let old = object(vec![
+            ("id", Shape::Integer),
+            ("name", Shape::StringT),
+            ("note", opt(Shape::StringT)),
+        ]);
+        let new = object(vec![
+            ("id", Shape::Integer),
+            ("name", opt(Shape::StringT)),
+            (
+                "tags",
+                Shape::VecT {
+                    elem_type: Box::new(Shape::StringT),
+                },
+            ),
+        ]);
+        let changes: Vec<String> = diff_shapes(&old, &new)
+            .iter()
+            .map(|change| change.to_string())
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                "breaking: /name: field became optional",
+                "compatible: /note: field removed",
+                "compatible: /tags: field added",
+            ]
+        );
+    }
+}
@@ -0,0 +1,344 @@
+//! A stable JSON format for shapes, so they can be saved, edited by hand and loaded again
+//!
+//! ```json
+//! {
+//!   "json_typegen_shape": 1,
+//!   "shape": {
+//!     "type": "struct",
+//!     "fields": {
+//!       "id": "integer",
+//!       "tags": { "type": "array", "item": "string" }
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! Shapes without any content are plain strings, the rest are objects with a `type`.
+//! The number after `json_typegen_shape` is the version of the format. It is increased
+//! whenever the format changes in a way that older versions can not read.
+
+use linked_hash_map::LinkedHashMap;
+
+use crate::JTError;
+use crate::generation::value::{Value, pretty_print_value};
+use crate::inference::{JsonInputErr, JsonLexer, JsonToken};
+use crate::shape::Shape;
+use crate::util::string_hashmap;
+
+/// The current version of the format
+const VERSION: u64 = 1;
+
+const VERSION_KEY: &str = "json_typegen_shape";
+
+impl Shape {
+    /// Serialize the shape, to be loaded again with `Shape::from_json`
+    pub fn to_json(&self) -> String {
+        let value = Value::Object(string_hashmap! {
+            VERSION_KEY => Value::Number(VERSION.to_string()),
+            "shape" => value_from_shape(self),
+        });
+        pretty_print_value(0, &value) + "\n"
+    }
+
+    /// Load a shape serialized with `Shape::to_json`, or written by hand in the same format
+    #[allow(clippy::result_large_err)]
+    pub fn from_json(input: &str) -> Result<Shape, JTError> {
+        shape_from_json(input).map_err(JTError::ShapeParsingError)
+    }
+}
+
+fn shape_from_json(input: &str) -> Result<Shape, String> {
+    let mut tokens = JsonLexer::new(input.as_bytes()).peekable();
+    let value = parse_value(&mut tokens).map_err(|e| e.to_string())?;
+    if tokens.next().is_some() {
+        return Err("Unexpected content after the shape".into());
+    }
+
+    let Value::Object(mut file) = value else {
+        return Err("Expected a shape file object".into());
+    };
+    match file.remove(VERSION_KEY) {
+        Some(Value::Number(n)) if n == VERSION.to_string() => {}
+        Some(Value::Number(n)) => return Err(format!("Unsupported shape format version {}", n)),
+        _ => return Err(format!("Missing \"{}\" version number", VERSION_KEY)),
+    }
+    let shape = file.remove("shape").ok_or("Missing \"shape\"")?;
+    shape_from_value(&shape)
+}
+
+fn value_from_shape(shape: &Shape) -> Value {
+    use crate::shape::Shape::*;
+    match shape {
+        Bottom => Value::Str("bottom"),
+        Any => Value::Str("any"),
+        Null => Value::Str("null"),
+        Bool => Value::Str("bool"),
+        StringT => Value::Str("string"),
+        Integer => Value::Str("integer"),
+        Floating => Value::Str("floating"),
+        SelfRef => Value::Str("self"),
+        Optional(e) => item_object("optional", e),
+        Nullable(e) => item_object("nullable", e),
+        Recursive(e) => item_object("recursive", e),
+        VecT { elem_type: e } => item_object("array", e),
+        Tuple(shapes, n) => Value::Object(string_hashmap! {
+            "type" => Value::Str("tuple"),
+            "items" => Value::Array(shapes.iter().map(value_from_shape).collect()),
+            "count" => Value::Number(n.to_string()),
+        }),
+        Struct { fields } => Value::Object(string_hashmap! {
+            "type" => Value::Str("struct"),
+            "fields" => Value::Object(
+                fields
+                    .iter()
+                    .map(|(name, shape)| (name.clone(), value_from_shape(shape)))
+                    .collect(),
+            ),
+        }),
+        MapT { val_type: v } => Value::Object(string_hashmap! {
+            "type" => Value::Str("map"),
+            "values" => value_from_shape(v),
+        }),
+        Opaque(t) => Value::Object(string_hashmap! {
+            "type" => Value::Str("opaque"),
+            "name" => Value::String(t.clone()),
+        }),
+        TypeParam(name, arg) => Value::Object(string_hashmap! {
+            "type" => Value::Str("type_parameter"),
+            "name" => Value::String(name.clone()),
+            "item" => value_from_shape(arg),
+        }),
+    }
+}
+
+fn item_object(typ: &'static str, item: &Shape) -> Value {
+    Value::Object(string_hashmap! {
+        "type" => Value::Str(typ),
+        "item" => value_from_shape(item),
+    })
+}
+
+fn shape_from_value(value: &Value) -> Result<Shape, String> {
+    let object = match value {
+        Value::String(name) => {
+            return Ok(match name.as_str() {
+                "bottom" => Shape::Bottom,
+                "any" => Shape::Any,
+                "null" => Shape::Null,
+                "bool" => Shape::Bool,
+                "string" => Shape::StringT,
+                "integer" => Shape::Integer,
+                "floating" => Shape::Floating,
+                "self" => Shape::SelfRef,
+                _ => return Err(format!("Unknown shape \"{}\"", name)),
+            });
+        }
+        Value::Object(object) => object,
+        _ => return Err("Expected a shape name or a shape object".into()),
+    };
+
+    let typ = match object.get("type") {
+        Some(Value::String(typ)) => typ.as_str(),
+        _ => return Err("Expected a \"type\" in shape object".into()),
+    };
+    let item = || shape_from_value(field(object, typ, "item")?).map(Box::new);
+    Ok(match typ {
+        "optional" => Shape::Optional(item()?),
+        "nullable" => Shape::Nullable(item()?),
+        "recursive" => Shape::Recursive(item()?),
+        "array" => Shape::VecT { elem_type: item()? },
+        "tuple" => {
+            let Value::Array(items) = field(object, typ, "items")? else {
+                return Err("Expected \"items\" of tuple to be an array".into());
+            };
+            let count = match object.get("count") {
+                Some(Value::Number(n)) => n
+                    .parse()
+                    .map_err(|_| format!("Invalid tuple count {}", n))?,
+                None => 1,
+                _ => return Err("Expected \"count\" of tuple to be a number".into()),
+            };
+            let shapes = items
+                .iter()
+                .map(shape_from_value)
+                .collect::<Result<_, _>>()?;
+            Shape::Tuple(shapes, count)
+        }
+        "struct" => {
+            let Value::Object(fields) = field(object, typ, "fields")? else {
+                return Err("Expected \"fields\" of struct to be an object".into());
+            };
+            let mut shapes = LinkedHashMap::new();
+            for (name, value) in fields {
+                shapes.insert(name.clone(), shape_from_value(value)?);
+            }
+            Shape::Struct { fields: shapes }
+        }
+        "map" => Shape::MapT {
+            val_type: Box::new(shape_from_value(field(object, typ, "values")?)?),
+        },
+        "opaque" => Shape::Opaque(string_field(object, typ, "name")?),
+        "type_parameter" => Shape::TypeParam(string_field(object, typ, "name")?, item()?),
+        _ => return Err(format!("Unknown shape type \"{}\"", typ)),
+    })
+}
+
+fn field<'a>(
+    object: &'a LinkedHashMap<String, Value>,
+    typ: &str,
+    key: &str,
+) -> Result<&'a Value, String> {
+    object
+        .get(key)
+        .ok_or_else(|| format!("Missing \"{}\" in {} shape", key, typ))
+}
+
+fn string_field(
+    object: &LinkedHashMap<String, Value>,
+    typ: &str,
+    key: &str,
+) -> Result<String, String> {
+    match field(object, typ, key)? {
+        Value::String(s) => Ok(s.clone()),
+        _ => Err(format!(
+            "Expected \"{}\" of {} shape to be a string",
+            key, typ
+        )),
+    }
+}
+
+type Tokens<'a> = std::iter::Peekable<JsonLexer<&'a [u8]>>;
+
+fn next_token(tokens: &mut Tokens) -> Result<JsonToken, JsonInputErr> {
+    tokens
+        .next()
+        .unwrap_or(Err(JsonInputErr::UnexpectedEndOfInput))
+}
+
+fn parse_value(tokens: &mut Tokens) -> Result<Value, JsonInputErr> {
+    Ok(match next_token(tokens)? {
+        JsonToken::Null => Value::Null,
+        JsonToken::True => Value::Bool(true),
+        JsonToken::False => Value::Bool(false),
+        JsonToken::Number(n) => Value::Number(n),
+        JsonToken::String(s) => Value::String(s),
+        JsonToken::ArrayStart => {
+            let mut values = Vec::new();
+            if let Some(Ok(JsonToken::ArrayEnd)) = tokens.peek() {
+                tokens.next();
+                return Ok(Value::Array(values));
+            }
+            loop {
+                values.push(parse_value(tokens)?);
+                match next_token(tokens)? {
+                    JsonToken::Comma => {}
+                    JsonToken::ArrayEnd => break,
+                    _ => return Err(JsonInputErr::InvalidJson),
+                }
+            }
+            Value::Array(values)
+        }
+        JsonToken::ObjectStart => {
+            let mut object = LinkedHashMap::new();
+            if let Some(Ok(JsonToken::ObjectEnd)) = tokens.peek() {
+                tokens.next();
+                return Ok(Value::Object(object));
+            }
+            loop {
+                let JsonToken::String(key) = next_token(tokens)? else {
+                    return Err(JsonInputErr::InvalidJson);
+                };
+                if next_token(tokens)? != JsonToken::Colon {
+                    return Err(JsonInputErr::InvalidJson);
+                }
+                object.insert(key, parse_value(tokens)?);
+                match next_token(tokens)? {
+                    JsonToken::Comma => {}
+                    JsonToken::ObjectEnd => break,
+                    _ => return Err(JsonInputErr::InvalidJson),
+                }
+            }
+            Value::Object(object)
+        }
+        _ => return Err(JsonInputErr::InvalidJson),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(shape: Shape) {
+        let json = shape.to_json();
+        assert_eq!(shape_from_json(&json), Ok(shape), "\n{}", json);
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut fields = LinkedHashMap::new();
+        fields.insert("id".to_string(), Shape::Integer);
+        fields.insert(
+            "name \"quoted\"".to_string(),
+            Shape::Optional(Box::new(Shape::Nullable(Box::new(Shape::StringT)))),
+        );
+        fields.insert(
+            "children".to_string(),
+            Shape::VecT {
+                elem_type: Box::new(Shape::SelfRef),
+            },
+        );
+        fields.insert(
+            "pair".to_string(),
+            Shape::Tuple(vec![Shape::Bool, Shape::Floating], 3),
+        );
+        fields.insert(
+            "extra".to_string(),
+            Shape::MapT {
+                val_type: Box::new(Shape::Opaque("Value".into())),
+            },
+        );
+        fields.insert(
+            "data".to_string(),
+            Shape::TypeParam("T".into(), Box::new(Shape::Any)),
+        );
+        fields.insert("nothing".to_string(), Shape::Null);
+        fields.insert("never".to_string(), Shape::Bottom);
+        round_trip(Shape::Recursive(Box::new(Shape::Struct { fields })));
+        round_trip(Shape::Struct {
+            fields: LinkedHashMap::new(),
+        });
+        round_trip(Shape::Tuple(vec![], 1));
+    }
+
+    #[test]
+    fn hand_written() {
+        let shape = shape_from_json(
+            r#"{ "json_typegen_shape": 1, "shape": { "type": "tuple", "items": ["string"] } }"#,
+        );
+        assert_eq!(shape, Ok(Shape::Tuple(vec![Shape::StringT], 1)));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            shape_from_json(r#"{ "json_typegen_shape": 2, "shape": "any" }"#),
+            Err("Unsupported shape format version 2".into())
+        );
+        assert_eq!(
+            shape_from_json(r#"{ "shape": "any" }"#),
+            Err("Missing \"json_typegen_shape\" version number".into())
+        );
+        assert_eq!(
+            shape_from_json(r#"{ "json_typegen_shape": 1, "shape": "text" }"#),
+            Err("Unknown shape \"text\"".into())
+        );
+        assert_eq!(
+            shape_from_json(r#"{ "json_typegen_shape": 1, "shape": { "type": "array" } }"#),
+            Err("Missing \"item\" in array shape".into())
+        );
+        assert_eq!(
+            shape_from_json(r#"{ "json_typegen_shape": 1, "shape": "any""#),
+            Err("Reached end of input while parsing".into())
+        );
+    }
+}
@@ -0,0 +1,290 @@
+use std::fmt;
+use std::io::Read;
+use std::iter::Peekable;
+
+use crate::inference::{JsonInputErr, JsonLexer, JsonToken};
+use crate::observations::child_pointer;
+use crate::shape::{Shape, as_generated, describe};
+
+/// A value in a sample that does not fit the shape it was checked against
+#[derive(Debug, PartialEq, Clone)]
+pub struct Mismatch {
+    /// [JSON Pointer](https://tools.ietf.org/html/rfc6901) to the value in the sample
+    pub pointer: String,
+    pub expected: Shape,
+    /// What was found instead, e.g. `"string"`, `"null"` or `"missing"`
+    pub actual: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let pointer = if self.pointer.is_empty() {
+            "(root)"
+        } else {
+            &self.pointer
+        };
+        write!(
+            f,
+            "{}: expected {}, found {}",
+            pointer,
+            describe(&self.expected),
+            self.actual
+        )
+    }
+}
+
+/// Check a JSON document against a shape, without reading it all into memory
+///
+/// Values are checked the way types generated from the shape deserialize them, so e.g.
+/// unknown fields are allowed, nullable fields may be missing, an integer is accepted where a
+/// float is expected, and values with no inferred type (like an empty array's elements) accept
+/// anything.
+pub fn validate<R: Read>(read: R, shape: &Shape) -> Result<Vec<Mismatch>, JsonInputErr> {
+    let mut validation = Validation {
+        tokens: JsonLexer::new(read).peekable(),
+        recursive: Vec::new(),
+        mismatches: Vec::new(),
+    };
+    validation.validate_value("", shape)?;
+    if validation.tokens.next().is_some() {
+        return Err(JsonInputErr::InvalidJson);
+    }
+    Ok(validation.mismatches)
+}
+
+struct Validation<T: Iterator<Item = Result<JsonToken, JsonInputErr>>> {
+    tokens: Peekable<T>,
+    /// The shapes `SelfRef` refers to, innermost last
+    recursive: Vec<Shape>,
+    mismatches: Vec<Mismatch>,
+}
+
+impl<T: Iterator<Item = Result<JsonToken, JsonInputErr>>> Validation<T> {
+    fn next_token(&mut self) -> Result<JsonToken, JsonInputErr> {
+        match self.tokens.next() {
+            Some(Ok(token)) => Ok(token),
+            Some(Err(err)) => Err(err),
+            None => Err(JsonInputErr::UnexpectedEndOfInput),
+        }
+    }
+
+    fn mismatch(&mut self, pointer: &str, expected: &Shape, actual: &str) {
+        self.mismatches.push(Mismatch {
+            pointer: pointer.to_string(),
+            expected: expected.clone(),
+            actual: actual.to_string(),
+        });
+    }
+
+    fn validate_value(&mut self, pointer: &str, shape: &Shape) -> Result<(), JsonInputErr> {
+        use crate::shape::Shape::*;
+        match shape {
+            Any | Bottom | Null | Opaque(_) => return self.skip_value(),
+            Optional(inner) | TypeParam(_, inner) => return self.validate_value(pointer, inner),
+            Nullable(inner) => {
+                if let Some(Ok(JsonToken::Null)) = self.tokens.peek() {
+                    self.tokens.next();
+                    return Ok(());
+                }
+                return self.validate_value(pointer, inner);
+            }
+            Recursive(inner) => {
+                self.recursive.push((**inner).clone());
+                let result = self.validate_value(pointer, inner);
+                self.recursive.pop();
+                return result;
+            }
+            SelfRef => {
+                let Some(inner) = self.recursive.last().cloned() else {
+                    return self.skip_value();
+                };
+                return self.validate_value(pointer, &inner);
+            }
+            _ => {}
+        }
+
+        let shape = &as_generated(shape);
+        let token = self.next_token()?;
+        match (shape, token) {
+            (Bool, JsonToken::True | JsonToken::False) => {}
+            (StringT, JsonToken::String(_)) => {}
+            (Floating, JsonToken::Number(_)) => {}
+            (Integer, JsonToken::Number(n)) => {
+                if n.contains('.') {
+                    self.mismatch(pointer, shape, "floating");
+                }
+            }
+            (VecT { elem_type }, JsonToken::ArrayStart) => {
+                let mut i = 0;
+                while self.next_element(i)? {
+                    self.validate_value(&child_pointer(pointer, &i.to_string()), elem_type)?;
+                    i += 1;
+                }
+            }
+            (Tuple(shapes, _), JsonToken::ArrayStart) => {
+                let mut i = 0;
+                while self.next_element(i)? {
+                    match shapes.get(i) {
+                        Some(elem_shape) => self
+                            .validate_value(&child_pointer(pointer, &i.to_string()), elem_shape)?,
+                        None => self.skip_value()?,
+                    }
+                    i += 1;
+                }
+                if i != shapes.len() {
+                    self.mismatch(pointer, shape, &format!("array of {}", i));
+                }
+            }
+            (Struct { fields }, JsonToken::ObjectStart) => {
+                let mut seen = Vec::new();
+                while let Some(key) = self.next_key(seen.len())? {
+                    match fields.get(&key) {
+                        Some(field_shape) => {
+                            self.validate_value(&child_pointer(pointer, &key), field_shape)?
+                        }
+                        None => self.skip_value()?,
+                    }
+                    seen.push(key);
+                }
+                for (key, field_shape) in fields.iter() {
+                    // nullable fields are options, which serde deserializes from missing fields
+                    let may_be_missing = matches!(field_shape, Optional(_) | Nullable(_) | Null);
+                    if !may_be_missing && !seen.contains(key) {
+                        self.mismatch(&child_pointer(pointer, key), field_shape, "missing");
+                    }
+                }
+            }
+            (MapT { val_type }, JsonToken::ObjectStart) => {
+                let mut i = 0;
+                while let Some(key) = self.next_key(i)? {
+                    self.validate_value(&child_pointer(pointer, &key), val_type)?;
+                    i += 1;
+                }
+            }
+            (_, token) => {
+                let actual = match token {
+                    JsonToken::True | JsonToken::False => "bool",
+                    JsonToken::Null => "null",
+                    JsonToken::Number(ref n) if n.contains('.') => "floating",
+                    JsonToken::Number(_) => "integer",
+                    JsonToken::String(_) => "string",
+                    JsonToken::ArrayStart => "array",
+                    JsonToken::ObjectStart => "object",
+                    _ => return Err(JsonInputErr::InvalidJson),
+                };
+                self.mismatch(pointer, shape, actual);
+                match token {
+                    JsonToken::ArrayStart => self.skip_array()?,
+                    JsonToken::ObjectStart => self.skip_object()?,
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Move to the next array element, if any. Assumes the array start has been consumed.
+    fn next_element(&mut self, index: usize) -> Result<bool, JsonInputErr> {
+        if let Some(Ok(JsonToken::ArrayEnd)) = self.tokens.peek() {
+            self.tokens.next();
+            return Ok(false);
+        }
+        if index > 0 && self.next_token()? != JsonToken::Comma {
+            return Err(JsonInputErr::InvalidJson);
+        }
+        Ok(true)
+    }
+
+    /// Read the key of the next object field, if any. Assumes the object start has been consumed.
+    fn next_key(&mut self, index: usize) -> Result<Option<String>, JsonInputErr> {
+        if let Some(Ok(JsonToken::ObjectEnd)) = self.tokens.peek() {
+            self.tokens.next();
+            return Ok(None);
+        }
+        if index > 0 && self.next_token()? != JsonToken::Comma {
+            return Err(JsonInputErr::InvalidJson);
+        }
+        let JsonToken::String(key) = self.next_token()? else {
+            return Err(JsonInputErr::InvalidJson);
+        };
+        if self.next_token()? != JsonToken::Colon {
+            return Err(JsonInputErr::InvalidJson);
+        }
+        Ok(Some(key))
+    }
+
+    fn skip_value(&mut self) -> Result<(), JsonInputErr> {
+        match self.next_token()? {
+            JsonToken::ArrayStart => self.skip_array(),
+            JsonToken::ObjectStart => self.skip_object(),
+            JsonToken::ArrayEnd | JsonToken::ObjectEnd | JsonToken::Comma | JsonToken::Colon => {
+                Err(JsonInputErr::InvalidJson)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn skip_array(&mut self) -> Result<(), JsonInputErr> {
+        let mut i = 0;
+        while self.next_element(i)? {
+            self.skip_value()?;
+            i += 1;
+        }
+        Ok(())
+    }
+
+    fn skip_object(&mut self) -> Result<(), JsonInputErr> {
+        let mut i = 0;
+        while self.next_key(i)?.is_some() {
+            self.skip_value()?;
+            i += 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linked_hash_map::LinkedHashMap;
+
+    fn check(sample: &str, shape: &Shape) -> Vec<String> {
+        validate(sample.as_bytes(), shape)
+            .unwrap()
+            .iter()
+            .map(|mismatch| mismatch.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn recursive_shapes() {
+        let mut fields = LinkedHashMap::new();
+        fields.insert("name".to_string(), Shape::StringT);
+        fields.insert(
+            "children".to_string(),
+            Shape::VecT {
+                elem_type: Box::new(Shape::SelfRef),
+            },
+        );
+        let shape = Shape::Recursive(Box::new(Shape::Struct { fields }));
+        assert_eq!(
+            check(
+                r#"{ "name": "a", "children": [{ "name": "b", "children": [{ "name": 3, "children": [] }] }] }"#,
+                &shape
+            ),
+            vec!["/children/0/children/0/name: expected string, found integer"]
+        );
+    }
+
+    #[test]
+    fn invalid_json() {
+        assert_eq!(
+            validate(r#"{ "a": 1 "#.as_bytes(), &Shape::Any),
+            Err(JsonInputErr::UnexpectedEndOfInput)
+        );
+        assert_eq!(
+            validate(r#"[1 2]"#.as_bytes(), &Shape::Any),
+            Err(JsonInputErr::InvalidJson)
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use json_typegen_shared::{Options, OutputMode, codegen, parse};
+
+fn code_output_test(name: &str, input: &str, mut options: Options, expected: &str) {
+    options.output_mode = OutputMode::AvroSchema;
+    let res = codegen(name, input, options);
+    let output = res.unwrap();
+    let expected = &expected[1..];
+    assert_eq!(
+        output, expected,
+        "\n\nUnexpected output code:\n  input: {}\n  output:\n{}\n  expected: {}",
+        input, output, expected
+    );
+}
+
+#[test]
+fn list_of_numbers() {
+    code_output_test(
+        "Numbers",
+        r##"
+            [1, 2, 3]
+        "##,
+        Options::default(),
+        r##"
+{
+  "type": "array",
+  "items": "long"
+}
+"##,
+    );
+}
+
+#[test]
+fn records_with_namespace() {
+    code_output_test(
+        "Order",
+        r##"
+            [
+                {
+                    "id": 1,
+                    "note": "leave at door",
+                    "buyer": { "name": "Jane" },
+                    "seller": { "name": "John" },
+                    "prices": { "a": 1.5 }
+                },
+                {
+                    "id": 2,
+                    "note": null,
+                    "buyer": { "name": "Jim" },
+                    "seller": { "name": "Jill" },
+                    "prices": { "a": 2.5 }
+                }
+            ]
+        "##,
+        parse::options(
+            r#"{
+                avro_namespace: "com.example",
+                unwrap: "/-",
+                "/prices": { use_type: "map" }
+            }"#,
+        )
+        .unwrap(),
+        r##"
+{
+  "type": "record",
+  "name": "Order",
+  "namespace": "com.example",
+  "fields": [
+    {
+      "name": "id",
+      "type": "long"
+    },
+    {
+      "name": "note",
+      "type": [
+        "null",
+        "string"
+      ],
+      "default": null
+    },
+    {
+      "name": "buyer",
+      "type": {
+        "type": "record",
+        "name": "Buyer",
+        "fields": [
+          {
+            "name": "name",
+            "type": "string"
+          }
+        ]
+      }
+    },
+    {
+      "name": "seller",
+      "type": "Buyer"
+    },
+    {
+      "name": "prices",
+      "type": {
+        "type": "map",
+        "values": "double"
+      }
+    }
+  ]
+}
+"##,
+    );
+}
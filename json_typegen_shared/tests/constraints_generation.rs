@@ -0,0 +1,235 @@
+use json_typegen_shared::{ImportStyle, Options, OutputMode, ValidationCrate, codegen};
+
+fn code_output_test(name: &str, input: &str, options: Options, expected: &str) {
+    let mut options = options;
+    options.import_style = ImportStyle::AssumeExisting;
+    options.infer_constraints = true;
+    let res = codegen(name, input, options);
+    let output = res.unwrap();
+    let expected = &expected[1..];
+    assert_eq!(
+        output, expected,
+        "\n\nUnexpected output code:\n  input: {}\n  output:\n{}\n  expected: {}",
+        input, output, expected
+    );
+}
+
+fn options(output_mode: OutputMode) -> Options {
+    let mut options = Options::default();
+    options.output_mode = output_mode;
+    options
+}
+
+const INPUT: &str = r##"
+    [
+        {
+            "username": "alice",
+            "email": "alice@example.com",
+            "age": 31,
+            "score": 0.5,
+            "roles": ["admin"],
+            "address": { "zip": "0150" }
+        },
+        {
+            "username": "bob_the_builder",
+            "email": "bob@example.org",
+            "age": 19,
+            "score": 1,
+            "roles": ["user", "editor"],
+            "address": { "zip": "5003" }
+        }
+    ]
+"##;
+
+#[test]
+fn rust_validator_attributes() {
+    code_output_test(
+        "Users",
+        INPUT,
+        options(OutputMode::Rust),
+        r##"
+pub type Users = Vec<User>;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
+pub struct User {
+    #[validate(length(min = 5, max = 15))]
+    pub username: String,
+    #[validate(email)]
+    pub email: String,
+    #[validate(range(min = 19, max = 31))]
+    pub age: i64,
+    #[validate(range(min = 0.5, max = 1.0))]
+    pub score: f64,
+    #[validate(length(min = 1))]
+    pub roles: Vec<String>,
+    #[validate(nested)]
+    pub address: Address,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
+pub struct Address {
+    #[validate(length(min = 4, max = 4))]
+    pub zip: String,
+}
+"##,
+    );
+}
+
+#[test]
+fn rust_garde_attributes() {
+    let mut options = options(OutputMode::Rust);
+    options.validation_crate = ValidationCrate::Garde;
+    code_output_test(
+        "Users",
+        INPUT,
+        options,
+        r##"
+pub type Users = Vec<User>;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
+pub struct User {
+    #[garde(length(chars, min = 5, max = 15))]
+    pub username: String,
+    #[garde(email)]
+    pub email: String,
+    #[garde(range(min = 19, max = 31))]
+    pub age: i64,
+    #[garde(range(min = 0.5, max = 1.0))]
+    pub score: f64,
+    #[garde(length(min = 1))]
+    pub roles: Vec<String>,
+    #[garde(dive)]
+    pub address: Address,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
+pub struct Address {
+    #[garde(length(chars, min = 4, max = 4))]
+    pub zip: String,
+}
+"##,
+    );
+}
+
+#[test]
+fn pydantic_field_constraints() {
+    code_output_test(
+        "Users",
+        INPUT,
+        options(OutputMode::PythonPydantic),
+        r##"
+class Address(BaseModel):
+    zip: str = Field(min_length=4, max_length=4)
+
+
+class User(BaseModel):
+    username: str = Field(min_length=5, max_length=15)
+    email: str = Field(pattern=r"^[^@\s]+@[^@\s]+\.[^@\s]+$")
+    age: int = Field(ge=19, le=31)
+    score: float = Field(ge=0.5, le=1.0)
+    roles: list[str] = Field(min_length=1)
+    address: Address
+
+
+Users = list[User]
+"##,
+    );
+}
+
+#[test]
+fn zod_refinements() {
+    code_output_test(
+        "Users",
+        INPUT,
+        options(OutputMode::ZodSchema),
+        r##"
+export const addressSchema = z.object({
+    zip: z.string().min(4).max(4),
+});
+
+export type Address = z.infer<typeof addressSchema>;
+
+export const userSchema = z.object({
+    username: z.string().min(5).max(15),
+    email: z.string().email(),
+    age: z.number().min(19).max(31),
+    score: z.number().min(0.5).max(1.0),
+    roles: z.string().min(4).max(6).array().min(1),
+    address: addressSchema,
+});
+
+export type User = z.infer<typeof userSchema>;
+
+export const usersSchema = userSchema.array().min(1);
+
+export type Users = z.infer<typeof usersSchema>;
+"##,
+    );
+}
+
+#[test]
+fn json_schema_keywords() {
+    code_output_test(
+        "User",
+        r#"{ "id": "123e4567-e89b-12d3-a456-426614174000", "tags": ["a", "bc"] }"#,
+        options(OutputMode::JsonSchema),
+        r##"
+{
+  "$schema": "http://json-schema.org/draft-07/schema#",
+  "title": "Generated schema for User",
+  "type": "object",
+  "properties": {
+    "id": {
+      "type": "string",
+      "format": "uuid"
+    },
+    "tags": {
+      "type": "array",
+      "items": {
+        "type": "string",
+        "minLength": 1,
+        "maxLength": 2
+      },
+      "minItems": 1
+    }
+  },
+  "required": [
+    "id",
+    "tags"
+  ]
+}
+"##,
+    );
+}
+
+#[test]
+fn non_ascii_strings() {
+    code_output_test(
+        "Note",
+        r#"{ "text": "123456789éxyz", "at": "2024-01-01T12:00:0é" }"#,
+        options(OutputMode::JsonSchema),
+        r##"
+{
+  "$schema": "http://json-schema.org/draft-07/schema#",
+  "title": "Generated schema for Note",
+  "type": "object",
+  "properties": {
+    "text": {
+      "type": "string",
+      "minLength": 13,
+      "maxLength": 13
+    },
+    "at": {
+      "type": "string",
+      "minLength": 19,
+      "maxLength": 19
+    }
+  },
+  "required": [
+    "text",
+    "at"
+  ]
+}
+"##,
+    );
+}
@@ -0,0 +1,205 @@
+use json_typegen_shared::{
+    Generator, Options, OutputMode, TypeDef, codegen, codegen_from_shape, parse,
+    register_generator, registered_generators,
+};
+
+/// Go structs, as an example of an output mode that is not built in
+struct Go;
+
+impl Generator for Go {
+    fn any_type(&self) -> String {
+        "interface{}".to_string()
+    }
+
+    fn bool_type(&self) -> String {
+        "bool".to_string()
+    }
+
+    fn string_type(&self) -> String {
+        "string".to_string()
+    }
+
+    fn integer_type(&self) -> String {
+        "int64".to_string()
+    }
+
+    fn floating_type(&self) -> String {
+        "float64".to_string()
+    }
+
+    fn list_type(&self, elem_type: &str) -> String {
+        format!("[]{}", elem_type)
+    }
+
+    fn map_type(&self, value_type: &str) -> String {
+        format!("map[string]{}", value_type)
+    }
+
+    fn nullable_type(&self, inner: &str) -> String {
+        format!("*{}", inner)
+    }
+
+    fn type_reference(&self, name: &str, is_self_reference: bool) -> String {
+        if is_self_reference {
+            format!("*{}", name)
+        } else {
+            name.to_string()
+        }
+    }
+
+    fn field_name(&self, json_name: &str) -> String {
+        self.type_name(json_name)
+    }
+
+    fn reserved_words(&self) -> &[&str] {
+        &["Type"]
+    }
+
+    fn type_definition(&self, def: &TypeDef) -> String {
+        let mut code = format!("type {} struct {{\n", def.name);
+        for field in &def.fields {
+            for line in &field.doc_lines {
+                code += &format!("\t// {}\n", line);
+            }
+            let omit = if field.optional { ",omitempty" } else { "" };
+            code += &format!(
+                "\t{} {} `json:\"{}{}\"`\n",
+                field.name, field.typ, field.json_name, omit
+            );
+        }
+        code + "}"
+    }
+
+    fn type_alias(&self, name: &str, typ: &str) -> String {
+        format!("type {} {}", name, typ)
+    }
+
+    fn file(&self, definitions: Vec<String>) -> String {
+        format!("package types\n\n{}", definitions.join("\n\n"))
+    }
+}
+
+fn go(input: &str, options: &str) -> String {
+    register_generator("go", Go).unwrap();
+    let options = parse::options(&format!("{{ output_mode: \"go\", {} }}", options)).unwrap();
+    codegen("Root", input, options).unwrap()
+}
+
+fn code_output_test(input: &str, options: &str, expected: &str) {
+    let output = go(input, options);
+    let expected = &expected[1..];
+    assert_eq!(
+        output, expected,
+        "\n\nUnexpected output:\n  input: {}\n  output:\n{}\n  expected: {}",
+        input, output, expected
+    );
+}
+
+#[test]
+fn registered_output_mode() {
+    register_generator("go", Go).unwrap();
+    assert_eq!(
+        OutputMode::parse("go"),
+        Some(OutputMode::Custom("go".to_string()))
+    );
+    assert_eq!(OutputMode::parse("rust"), Some(OutputMode::Rust));
+    assert!(register_generator("rust", Go).is_err());
+    assert_eq!(OutputMode::parse("rust"), Some(OutputMode::Rust));
+    assert!(registered_generators().contains(&"go".to_string()));
+    assert_eq!(OutputMode::parse("cobol"), None);
+}
+
+#[test]
+fn unknown_output_mode() {
+    let mut options = Options::default();
+    options.output_mode = OutputMode::Custom("cobol".to_string());
+    let shape = json_typegen_shared::infer_shape("{}", Options::default()).unwrap();
+    assert!(codegen_from_shape("Root", &shape, options).is_err());
+}
+
+#[test]
+fn nested_objects_and_lists() {
+    code_output_test(
+        r#"[
+            { "id": 1, "type": "a", "tags": ["x"], "owner": { "name": "Al" }, "score": 1.5 },
+            { "id": 2, "type": "b", "tags": [], "owner": { "name": "Bo" }, "extra": null }
+        ]"#,
+        r#"unwrap: "/-""#,
+        r##"
+package types
+
+type Root struct {
+	Id int64 `json:"id"`
+	Type_ string `json:"type"`
+	Tags []string `json:"tags"`
+	Owner Owner `json:"owner"`
+	Score float64 `json:"score,omitempty"`
+	Extra *interface{} `json:"extra"`
+}
+
+type Owner struct {
+	Name string `json:"name"`
+}
+"##,
+    );
+}
+
+#[test]
+fn shared_and_named_types() {
+    code_output_test(
+        r#"{
+            "sender": { "name": "Al", "age": 3 },
+            "receiver": { "name": "Bo", "age": 4 },
+            "sizes": { "a": 1, "b": 2 }
+        }"#,
+        r#""/sizes": { use_type: "map" }"#,
+        r##"
+package types
+
+type Root struct {
+	Sender Sender `json:"sender"`
+	Receiver Sender `json:"receiver"`
+	Sizes map[string]int64 `json:"sizes"`
+}
+
+type Sender struct {
+	Name string `json:"name"`
+	Age int64 `json:"age"`
+}
+"##,
+    );
+}
+
+#[test]
+fn root_alias_and_nullable() {
+    code_output_test(
+        r#"[{ "a": 1, "b": null }, { "a": null, "b": true }]"#,
+        "",
+        r##"
+package types
+
+type Root []Root2
+
+type Root2 struct {
+	A *int64 `json:"a"`
+	B *bool `json:"b"`
+}
+"##,
+    );
+}
+
+#[test]
+fn recursive_types() {
+    code_output_test(
+        r#"{ "value": 1, "children": [{ "value": 2, "children": [] }] }"#,
+        "",
+        r##"
+package types
+
+type Root struct {
+	Value int64 `json:"value"`
+	Children []*Root `json:"children"`
+}
+"##,
+    );
+}
@@ -0,0 +1,169 @@
+use json_typegen_shared::{ImportStyle, Options, OutputMode, codegen};
+
+fn code_output_test(name: &str, input: &str, output_mode: OutputMode, expected: &str) {
+    let mut options = Options::default();
+    options.import_style = ImportStyle::AssumeExisting;
+    options.output_mode = output_mode;
+    options.doc_comments = true;
+    let res = codegen(name, input, options);
+    let output = res.unwrap();
+    let expected = &expected[1..];
+    assert_eq!(
+        output, expected,
+        "\n\nUnexpected output code:\n  input: {}\n  output:\n{}\n  expected: {}",
+        input, output, expected
+    );
+}
+
+const INPUT: &str = r##"
+    [
+        {
+            "id": 1,
+            "created_at": "2024-01-01T00:00:00.000000000+00:00",
+            "tags": ["a", "b"],
+            "owner": { "name": "Alice" }
+        },
+        {
+            "id": 2,
+            "created_at": "2024-01-02T00:00:00.000000000+00:00",
+            "tags": [],
+            "owner": { "name": "Bob", "email": "bob@example.com" }
+        },
+        {
+            "id": 3,
+            "created_at": "2024-01-03T00:00:00.000000000+00:00",
+            "owner": { "name": "Alice" }
+        }
+    ]
+"##;
+
+#[test]
+fn rust_doc_comments() {
+    code_output_test(
+        "Events",
+        INPUT,
+        OutputMode::Rust,
+        r##"
+pub type Events = Vec<Event>;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Event {
+    /// Examples: 1, 2, 3
+    pub id: i64,
+    /// Examples: "2024-01-01T00:00:00.000000000+00...", "2024-01-02T00:00:00.000000000+00...", "2024-01-03T00:00:00.000000000+00..."
+    pub created_at: String,
+    /// Present in 2 of 3 samples
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub owner: Owner,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Owner {
+    /// Examples: "Alice", "Bob"
+    pub name: String,
+    /// Example: "bob@example.com"
+    /// Present in 1 of 3 samples
+    pub email: Option<String>,
+}
+"##,
+    );
+}
+
+#[test]
+fn typescript_doc_comments() {
+    code_output_test(
+        "Events",
+        INPUT,
+        OutputMode::Typescript,
+        r##"
+export type Events = Event[];
+
+export interface Event {
+    /** Examples: 1, 2, 3 */
+    id: number;
+    /** Examples: "2024-01-01T00:00:00.000000000+00...", "2024-01-02T00:00:00.000000000+00...", "2024-01-03T00:00:00.000000000+00..." */
+    created_at: string;
+    /** Present in 2 of 3 samples */
+    tags?: string[];
+    owner: Owner;
+}
+
+export interface Owner {
+    /** Examples: "Alice", "Bob" */
+    name: string;
+    /**
+     * Example: "bob@example.com"
+     * Present in 1 of 3 samples
+     */
+    email?: string;
+}
+"##,
+    );
+}
+
+#[test]
+fn kotlin_doc_comments() {
+    code_output_test(
+        "Events",
+        INPUT,
+        OutputMode::KotlinJackson,
+        r##"
+typealias Events = List<Event>;
+
+data class Event(
+    /** Examples: 1, 2, 3 */
+    val id: Long,
+    /** Examples: "2024-01-01T00:00:00.000000000+00...", "2024-01-02T00:00:00.000000000+00...", "2024-01-03T00:00:00.000000000+00..." */
+    @JsonProperty("created_at")
+    val createdAt: String,
+    /** Present in 2 of 3 samples */
+    val tags: List<String>?,
+    val owner: Owner,
+)
+
+data class Owner(
+    /** Examples: "Alice", "Bob" */
+    val name: String,
+    /**
+     * Example: "bob@example.com"
+     * Present in 1 of 3 samples
+     */
+    val email: String?,
+)
+"##,
+    );
+}
+
+#[test]
+fn python_docstrings() {
+    code_output_test(
+        "Events",
+        INPUT,
+        OutputMode::PythonPydantic,
+        r##"
+class Owner(BaseModel):
+    name: str
+    """Examples: "Alice", "Bob"
+    """
+    email: Optional[str]
+    """Example: "bob@example.com"
+    Present in 1 of 3 samples
+    """
+
+
+class Event(BaseModel):
+    id: int
+    """Examples: 1, 2, 3"""
+    created_at: str
+    """Examples: "2024-01-01T00:00:00.000000000+00...", "2024-01-02T00:00:00.000000000+00...", "2024-01-03T00:00:00.000000000+00..."
+    """
+    tags: Optional[list[str]]
+    """Present in 2 of 3 samples"""
+    owner: Owner
+
+
+Events = list[Event]
+"##,
+    );
+}
@@ -0,0 +1,189 @@
+use json_typegen_shared::{OutputMode, codegen, parse};
+
+fn code_output_test(input: &str, output_mode: OutputMode, options: &str, expected: &str) {
+    let mut options = parse::options(options).unwrap();
+    options.output_mode = output_mode;
+    let output = codegen("Root", input, options).unwrap();
+    let expected = &expected[1..];
+    assert_eq!(
+        output, expected,
+        "\n\nUnexpected output:\n  input: {}\n  output:\n{}\n  expected: {}",
+        input, output, expected
+    );
+}
+
+const ORDERS: &str = r#"[
+    {
+        "orderId": 1,
+        "customer": { "name": "Alice", "email": "alice@example.org" },
+        "lines": [{ "sku": "a|1", "price": 2.5 }],
+        "tags": ["new"],
+        "coupon": null
+    },
+    {
+        "orderId": 2,
+        "customer": { "name": "<Bob>", "email": null },
+        "lines": [{ "sku": "b", "price": 3 }, { "sku": "c", "price": 1.25 }],
+        "tags": [],
+        "coupon": "SPRING",
+        "note": "leave at door"
+    }
+]"#;
+
+#[test]
+fn markdown() {
+    code_output_test(
+        ORDERS,
+        OutputMode::Markdown,
+        r#"{ unwrap: "/-" }"#,
+        r##"
+## Root
+
+| Field | Type | Required | Nullable | Example | Path |
+| --- | --- | --- | --- | --- | --- |
+| `orderId` | integer | yes | no | `1` | `/orderId` |
+| `customer` | [Customer](#customer) | yes | no |  | `/customer` |
+| `lines` | array of [Lines](#lines) | yes | no |  | `/lines` |
+| `tags` | array of string | yes | no | `"new"` | `/tags` |
+| `coupon` | string | yes | yes | `"SPRING"` | `/coupon` |
+| `note` | string | no | no | `"leave at door"` | `/note` |
+
+## Customer
+
+| Field | Type | Required | Nullable | Example | Path |
+| --- | --- | --- | --- | --- | --- |
+| `name` | string | yes | no | `"Alice"` | `/customer/name` |
+| `email` | string | yes | yes | `"alice@example.org"` | `/customer/email` |
+
+## Lines
+
+| Field | Type | Required | Nullable | Example | Path |
+| --- | --- | --- | --- | --- | --- |
+| `sku` | string | yes | no | `"a\|1"` | `/lines/-/sku` |
+| `price` | number | yes | no | `2.5` | `/lines/-/price` |
+"##,
+    );
+}
+
+#[test]
+fn html() {
+    code_output_test(
+        ORDERS,
+        OutputMode::Html,
+        r#"{ unwrap: "/-" }"#,
+        r##"
+<h2 id="Root">Root</h2>
+
+<table>
+  <thead>
+    <tr><th>Field</th><th>Type</th><th>Required</th><th>Nullable</th><th>Example</th><th>Path</th></tr>
+  </thead>
+  <tbody>
+    <tr><td><code>orderId</code></td><td>integer</td><td>yes</td><td>no</td><td><code>1</code></td><td><code>/orderId</code></td></tr>
+    <tr><td><code>customer</code></td><td><a href="#Customer">Customer</a></td><td>yes</td><td>no</td><td></td><td><code>/customer</code></td></tr>
+    <tr><td><code>lines</code></td><td>array of <a href="#Lines">Lines</a></td><td>yes</td><td>no</td><td></td><td><code>/lines</code></td></tr>
+    <tr><td><code>tags</code></td><td>array of string</td><td>yes</td><td>no</td><td><code>&quot;new&quot;</code></td><td><code>/tags</code></td></tr>
+    <tr><td><code>coupon</code></td><td>string</td><td>yes</td><td>yes</td><td><code>&quot;SPRING&quot;</code></td><td><code>/coupon</code></td></tr>
+    <tr><td><code>note</code></td><td>string</td><td>no</td><td>no</td><td><code>&quot;leave at door&quot;</code></td><td><code>/note</code></td></tr>
+  </tbody>
+</table>
+
+<h2 id="Customer">Customer</h2>
+
+<table>
+  <thead>
+    <tr><th>Field</th><th>Type</th><th>Required</th><th>Nullable</th><th>Example</th><th>Path</th></tr>
+  </thead>
+  <tbody>
+    <tr><td><code>name</code></td><td>string</td><td>yes</td><td>no</td><td><code>&quot;Alice&quot;</code></td><td><code>/customer/name</code></td></tr>
+    <tr><td><code>email</code></td><td>string</td><td>yes</td><td>yes</td><td><code>&quot;alice@example.org&quot;</code></td><td><code>/customer/email</code></td></tr>
+  </tbody>
+</table>
+
+<h2 id="Lines">Lines</h2>
+
+<table>
+  <thead>
+    <tr><th>Field</th><th>Type</th><th>Required</th><th>Nullable</th><th>Example</th><th>Path</th></tr>
+  </thead>
+  <tbody>
+    <tr><td><code>sku</code></td><td>string</td><td>yes</td><td>no</td><td><code>&quot;a|1&quot;</code></td><td><code>/lines/-/sku</code></td></tr>
+    <tr><td><code>price</code></td><td>number</td><td>yes</td><td>no</td><td><code>2.5</code></td><td><code>/lines/-/price</code></td></tr>
+  </tbody>
+</table>
+"##,
+    );
+}
+
+#[test]
+fn root_arrays_and_recursion() {
+    code_output_test(
+        r#"[{ "name": "a", "children": [{ "name": "b", "children": [] }] }]"#,
+        OutputMode::Markdown,
+        "{}",
+        r##"
+## Root
+
+Type: array of [Root2](#root2)
+
+## Root2
+
+| Field | Type | Required | Nullable | Example | Path |
+| --- | --- | --- | --- | --- | --- |
+| `name` | string | yes | no | `"a"` | `/-/name` |
+| `children` | array of [Root2](#root2) | yes | no |  | `/-/children` |
+"##,
+    );
+}
+
+#[test]
+fn only_null_values() {
+    code_output_test(
+        r#"{ "id": 1, "opt": null }"#,
+        OutputMode::Markdown,
+        "{}",
+        r##"
+## Root
+
+| Field | Type | Required | Nullable | Example | Path |
+| --- | --- | --- | --- | --- | --- |
+| `id` | integer | yes | no | `1` | `/id` |
+| `opt` | any | yes | yes | `null` | `/opt` |
+"##,
+    );
+}
+
+#[test]
+fn opaque_types() {
+    let options = r#"{ "/counts": { use_type: "HashMap<String, i64>" } }"#;
+    let input = r#"{ "counts": { "a": 1 } }"#;
+    code_output_test(
+        input,
+        OutputMode::Markdown,
+        options,
+        r##"
+## Root
+
+| Field | Type | Required | Nullable | Example | Path |
+| --- | --- | --- | --- | --- | --- |
+| `counts` | `HashMap<String, i64>` | yes | no |  | `/counts` |
+"##,
+    );
+    code_output_test(
+        input,
+        OutputMode::Html,
+        options,
+        r##"
+<h2 id="Root">Root</h2>
+
+<table>
+  <thead>
+    <tr><th>Field</th><th>Type</th><th>Required</th><th>Nullable</th><th>Example</th><th>Path</th></tr>
+  </thead>
+  <tbody>
+    <tr><td><code>counts</code></td><td><code>HashMap&lt;String, i64&gt;</code></td><td>yes</td><td>no</td><td></td><td><code>/counts</code></td></tr>
+  </tbody>
+</table>
+"##,
+    );
+}
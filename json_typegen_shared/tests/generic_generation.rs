@@ -0,0 +1,362 @@
+use json_typegen_shared::{ImportStyle, OutputMode, codegen, parse};
+
+fn code_output_test(name: &str, input: &str, output_mode: OutputMode, expected: &str) {
+    let pointer = if input.trim().starts_with('[') {
+        "/-/data"
+    } else {
+        "/data"
+    };
+    let options_block = format!(r#"{{ "{}": {{ type_parameter: "T" }} }}"#, pointer);
+    let mut options = parse::options(&options_block).unwrap();
+    options.import_style = ImportStyle::AssumeExisting;
+    options.output_mode = output_mode;
+    let res = codegen(name, input, options);
+    let output = res.unwrap();
+    let expected = &expected[1..];
+    assert_eq!(
+        output, expected,
+        "\n\nUnexpected output code:\n  input: {}\n  output:\n{}\n  expected: {}",
+        input, output, expected
+    );
+}
+
+const ENVELOPE: &str = r##"
+    {
+        "data": { "id": 1, "name": "Alice" },
+        "meta": { "page": 1 },
+        "errors": []
+    }
+"##;
+
+const ENVELOPES: &str = r##"
+    [
+        {
+            "data": { "id": 1, "name": "Alice" },
+            "meta": { "page": 1 },
+            "errors": []
+        }
+    ]
+"##;
+
+#[test]
+fn rust_generic_struct() {
+    code_output_test(
+        "Envelope",
+        ENVELOPE,
+        OutputMode::Rust,
+        r##"
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Envelope<T> {
+    pub data: T,
+    pub meta: Meta,
+    pub errors: Vec<Value>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Data {
+    pub id: i64,
+    pub name: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Meta {
+    pub page: i64,
+}
+"##,
+    );
+    code_output_test(
+        "Envelopes",
+        ENVELOPES,
+        OutputMode::Rust,
+        r##"
+pub type Envelopes = Vec<Envelope<Data>>;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Envelope<T> {
+    pub data: T,
+    pub meta: Meta,
+    pub errors: Vec<Value>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Data {
+    pub id: i64,
+    pub name: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Meta {
+    pub page: i64,
+}
+"##,
+    );
+}
+
+#[test]
+fn typescript_generic_interface() {
+    code_output_test(
+        "Envelopes",
+        ENVELOPES,
+        OutputMode::Typescript,
+        r##"
+export type Envelopes = Envelope<Data>[];
+
+export interface Envelope<T> {
+    data: T;
+    meta: Meta;
+    errors: any[];
+}
+
+export interface Data {
+    id: number;
+    name: string;
+}
+
+export interface Meta {
+    page: number;
+}
+"##,
+    );
+}
+
+#[test]
+fn kotlin_generic_class() {
+    code_output_test(
+        "Envelope",
+        ENVELOPE,
+        OutputMode::KotlinKotlinx,
+        r##"
+@Serializable
+data class Envelope<T>(
+    val data: T,
+    val meta: Meta,
+    val errors: List<Any?>,
+)
+
+@Serializable
+data class Data(
+    val id: Long,
+    val name: String,
+)
+
+@Serializable
+data class Meta(
+    val page: Long,
+)
+"##,
+    );
+}
+
+#[test]
+fn python_generic_class() {
+    code_output_test(
+        "Envelopes",
+        ENVELOPES,
+        OutputMode::PythonPydantic,
+        r##"
+T = TypeVar("T")
+
+
+class Data(BaseModel):
+    id: int
+    name: str
+
+
+class Meta(BaseModel):
+    page: int
+
+
+class Envelope(BaseModel, Generic[T]):
+    data: T
+    meta: Meta
+    errors: list[Any]
+
+
+Envelopes = list[Envelope[Data]]
+"##,
+    );
+    code_output_test(
+        "Envelope",
+        ENVELOPE,
+        OutputMode::PythonDataclass,
+        r##"
+T = TypeVar("T")
+
+
+@dataclass
+class Data:
+    id: int
+    name: str
+
+
+@dataclass
+class Meta:
+    page: int
+
+
+@dataclass
+class Envelope(Generic[T]):
+    data: T
+    meta: Meta
+    errors: list[Any]
+"##,
+    );
+    code_output_test(
+        "Envelopes",
+        ENVELOPES,
+        OutputMode::PythonPydanticV1,
+        r##"
+T = TypeVar("T")
+
+
+class Data(BaseModel):
+    id: int
+    name: str
+
+
+class Meta(BaseModel):
+    page: int
+
+
+class Envelope(GenericModel, Generic[T]):
+    data: T
+    meta: Meta
+    errors: list[Any]
+
+
+Envelopes = list[Envelope[Data]]
+"##,
+    );
+}
+
+#[test]
+fn type_argument_inlined_without_generics() {
+    code_output_test(
+        "Envelope",
+        ENVELOPE,
+        OutputMode::ZodSchema,
+        r##"
+export const dataSchema = z.object({
+    id: z.number(),
+    name: z.string(),
+});
+
+export type Data = z.infer<typeof dataSchema>;
+
+export const metaSchema = z.object({
+    page: z.number(),
+});
+
+export type Meta = z.infer<typeof metaSchema>;
+
+export const envelopeSchema = z.object({
+    data: dataSchema,
+    meta: metaSchema,
+    errors: z.unknown().array(),
+});
+
+export type Envelope = z.infer<typeof envelopeSchema>;
+"##,
+    );
+}
+
+#[test]
+fn type_argument_used_without_generic_types() {
+    code_output_test(
+        "Envelope",
+        ENVELOPE,
+        OutputMode::GraphQL,
+        r##"
+scalar JSON
+
+type Envelope {
+  data: Data!
+  meta: Meta!
+  errors: [JSON]!
+}
+
+type Data {
+  id: Int!
+  name: String!
+}
+
+type Meta {
+  page: Int!
+}
+"##,
+    );
+}
+
+#[test]
+fn envelopes_with_different_payloads() {
+    let input = r##"
+        {
+            "users": { "data": [{ "name": "Alice" }], "page": 1 },
+            "orders": { "data": [{ "id": 1, "total": 2.5 }], "page": 1 }
+        }
+    "##;
+    for (output_mode, expected) in [
+        (
+            OutputMode::Rust,
+            r##"
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Root {
+    pub users: Users<Vec<Data>>,
+    pub orders: Users<Vec<Data2>>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Users<T> {
+    pub data: T,
+    pub page: i64,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Data {
+    pub name: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Data2 {
+    pub id: i64,
+    pub total: f64,
+}
+"##,
+        ),
+        (
+            OutputMode::Typescript,
+            r##"
+export interface Root {
+    users: Users<Data[]>;
+    orders: Users<Data2[]>;
+}
+
+export interface Users<T> {
+    data: T;
+    page: number;
+}
+
+export interface Data {
+    name: string;
+}
+
+export interface Data2 {
+    id: number;
+    total: number;
+}
+"##,
+        ),
+    ] {
+        let mut options = parse::options(
+            r#"{
+                "/users/data": { type_parameter: "T" },
+                "/orders/data": { type_parameter: "T" }
+            }"#,
+        )
+        .unwrap();
+        options.import_style = ImportStyle::AssumeExisting;
+        options.output_mode = output_mode;
+        let output = codegen("Root", input, options).unwrap();
+        assert_eq!(output, &expected[1..], "\n\nUnexpected output:\n{}", output);
+    }
+}
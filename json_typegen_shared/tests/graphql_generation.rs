@@ -0,0 +1,115 @@
+use json_typegen_shared::{Options, OutputMode, codegen};
+
+fn code_output_test(name: &str, input: &str, mut options: Options, expected: &str) {
+    options.output_mode = OutputMode::GraphQL;
+    let res = codegen(name, input, options);
+    let output = res.unwrap();
+    let expected = &expected[1..];
+    assert_eq!(
+        output, expected,
+        "\n\nUnexpected output code:\n  input: {}\n  output:\n{}\n  expected: {}",
+        input, output, expected
+    );
+}
+
+#[test]
+fn list_of_numbers() {
+    code_output_test(
+        "Numbers",
+        r##"
+            [1, 2, 3]
+        "##,
+        Options::default(),
+        r##"
+# Numbers = [Int!]!
+"##,
+    );
+}
+
+#[test]
+fn optionals_and_json_scalar() {
+    code_output_test(
+        "Opts",
+        r##"
+            [
+                {
+                    "in_both": 5,
+                    "missing": 5,
+                    "has_null": 5,
+                    "conflicting": 5,
+                    "first-name": "Jane"
+                },
+                {
+                    "in_both": 5,
+                    "has_null": null,
+                    "conflicting": "five",
+                    "first-name": "John"
+                }
+            ]
+        "##,
+        Options::default(),
+        r##"
+scalar JSON
+
+# Opts = [Opt!]!
+
+type Opt {
+  in_both: Int!
+  missing: Int
+  has_null: Int
+  conflicting: JSON
+  firstName: String!
+}
+"##,
+    );
+}
+
+#[test]
+fn input_types() {
+    code_output_test(
+        "User",
+        r##"
+            {
+                "name": "Jane",
+                "address": {
+                    "street": "Main Street"
+                },
+                "tags": [{ "label": "admin" }]
+            }
+        "##,
+        {
+            let mut options = Options::default();
+            options.graphql_input_types = true;
+            options
+        },
+        r##"
+type User {
+  name: String!
+  address: Address!
+  tags: [Tag!]!
+}
+
+input UserInput {
+  name: String!
+  address: AddressInput!
+  tags: [TagInput!]!
+}
+
+type Address {
+  street: String!
+}
+
+input AddressInput {
+  street: String!
+}
+
+type Tag {
+  label: String!
+}
+
+input TagInput {
+  label: String!
+}
+"##,
+    );
+}
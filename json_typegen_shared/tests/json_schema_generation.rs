@@ -0,0 +1,345 @@
+use json_typegen_shared::{JsonSchemaDraft, Options, OutputMode, codegen};
+
+fn code_output_test(name: &str, input: &str, mut options: Options, expected: &str) {
+    options.output_mode = OutputMode::JsonSchema;
+    let res = codegen(name, input, options);
+    let output = res.unwrap();
+    let expected = &expected[1..];
+    assert_eq!(
+        output, expected,
+        "\n\nUnexpected output code:\n  input: {}\n  output:\n{}\n  expected: {}",
+        input, output, expected
+    );
+}
+
+const INPUT: &str = r##"
+    {
+        "id": 1,
+        "scores": [1, null],
+        "billing": { "city": "Oslo" },
+        "shipping": { "city": "Bergen" },
+        "previous": [{ "city": "Trondheim" }, null],
+        "pair": [1, "one"]
+    }
+"##;
+
+#[test]
+fn draft_07() {
+    let mut options = Options::default();
+    options.deny_unknown_fields = true;
+    code_output_test(
+        "Order",
+        INPUT,
+        options,
+        r##"
+{
+  "$schema": "http://json-schema.org/draft-07/schema#",
+  "title": "Generated schema for Order",
+  "type": "object",
+  "properties": {
+    "id": {
+      "type": "integer"
+    },
+    "scores": {
+      "type": "array",
+      "items": {
+        "type": [
+          "integer",
+          "null"
+        ]
+      }
+    },
+    "billing": {
+      "$ref": "#/definitions/Billing"
+    },
+    "shipping": {
+      "$ref": "#/definitions/Billing"
+    },
+    "previous": {
+      "type": "array",
+      "items": {
+        "anyOf": [
+          {
+            "$ref": "#/definitions/Billing"
+          },
+          {
+            "type": "null"
+          }
+        ]
+      }
+    },
+    "pair": {
+      "type": "array",
+      "items": [
+        {
+          "type": "integer"
+        },
+        {
+          "type": "string"
+        }
+      ],
+      "additionalItems": false
+    }
+  },
+  "required": [
+    "id",
+    "scores",
+    "billing",
+    "shipping",
+    "previous",
+    "pair"
+  ],
+  "additionalProperties": false,
+  "definitions": {
+    "Billing": {
+      "type": "object",
+      "properties": {
+        "city": {
+          "type": "string"
+        }
+      },
+      "required": [
+        "city"
+      ],
+      "additionalProperties": false
+    }
+  }
+}
+"##,
+    );
+}
+
+#[test]
+fn draft_2020_12() {
+    let mut options = Options::default();
+    options.json_schema_draft = JsonSchemaDraft::Draft202012;
+    code_output_test(
+        "Order",
+        INPUT,
+        options,
+        r##"
+{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "title": "Generated schema for Order",
+  "type": "object",
+  "properties": {
+    "id": {
+      "type": "integer"
+    },
+    "scores": {
+      "type": "array",
+      "items": {
+        "type": [
+          "integer",
+          "null"
+        ]
+      }
+    },
+    "billing": {
+      "$ref": "#/$defs/Billing"
+    },
+    "shipping": {
+      "$ref": "#/$defs/Billing"
+    },
+    "previous": {
+      "type": "array",
+      "items": {
+        "anyOf": [
+          {
+            "$ref": "#/$defs/Billing"
+          },
+          {
+            "type": "null"
+          }
+        ]
+      }
+    },
+    "pair": {
+      "type": "array",
+      "prefixItems": [
+        {
+          "type": "integer"
+        },
+        {
+          "type": "string"
+        }
+      ],
+      "items": false
+    }
+  },
+  "required": [
+    "id",
+    "scores",
+    "billing",
+    "shipping",
+    "previous",
+    "pair"
+  ],
+  "$defs": {
+    "Billing": {
+      "type": "object",
+      "properties": {
+        "city": {
+          "type": "string"
+        }
+      },
+      "required": [
+        "city"
+      ]
+    }
+  }
+}
+"##,
+    );
+}
+
+#[test]
+fn openapi_3_0() {
+    let mut options = Options::default();
+    options.json_schema_draft = JsonSchemaDraft::OpenApi30;
+    code_output_test(
+        "Order",
+        INPUT,
+        options,
+        r##"
+{
+  "components": {
+    "schemas": {
+      "Order": {
+        "type": "object",
+        "properties": {
+          "id": {
+            "type": "integer"
+          },
+          "scores": {
+            "type": "array",
+            "items": {
+              "type": "integer",
+              "nullable": true
+            }
+          },
+          "billing": {
+            "$ref": "#/components/schemas/Billing"
+          },
+          "shipping": {
+            "$ref": "#/components/schemas/Billing"
+          },
+          "previous": {
+            "type": "array",
+            "items": {
+              "allOf": [
+                {
+                  "$ref": "#/components/schemas/Billing"
+                }
+              ],
+              "nullable": true
+            }
+          },
+          "pair": {
+            "type": "array",
+            "items": {
+              "anyOf": [
+                {
+                  "type": "integer"
+                },
+                {
+                  "type": "string"
+                }
+              ]
+            }
+          }
+        },
+        "required": [
+          "id",
+          "scores",
+          "billing",
+          "shipping",
+          "previous",
+          "pair"
+        ]
+      },
+      "Billing": {
+        "type": "object",
+        "properties": {
+          "city": {
+            "type": "string"
+          }
+        },
+        "required": [
+          "city"
+        ]
+      }
+    }
+  }
+}
+"##,
+    );
+}
+
+#[test]
+fn objects_in_short_arrays_inlined() {
+    code_output_test(
+        "Root",
+        r#"[{"a":{"x":1}},{"a":{"x":2}}]"#,
+        Options::default(),
+        r##"
+{
+  "$schema": "http://json-schema.org/draft-07/schema#",
+  "title": "Generated schema for Root",
+  "type": "array",
+  "items": {
+    "type": "object",
+    "properties": {
+      "a": {
+        "type": "object",
+        "properties": {
+          "x": {
+            "type": "integer"
+          }
+        },
+        "required": [
+          "x"
+        ]
+      }
+    },
+    "required": [
+      "a"
+    ]
+  }
+}
+"##,
+    );
+}
+
+#[test]
+fn no_required_fields() {
+    let mut options = Options::default();
+    options.json_schema_draft = JsonSchemaDraft::OpenApi30;
+    code_output_test(
+        "Root",
+        r#"[{"a":1},{"b":2}]"#,
+        options,
+        r##"
+{
+  "components": {
+    "schemas": {
+      "Root": {
+        "type": "array",
+        "items": {
+          "type": "object",
+          "properties": {
+            "a": {
+              "type": "integer"
+            },
+            "b": {
+              "type": "integer"
+            }
+          }
+        }
+      }
+    }
+  }
+}
+"##,
+    );
+}
@@ -0,0 +1,92 @@
+use json_typegen_shared::{ImportStyle, Options, OutputMode, codegen};
+
+fn code_output_test(name: &str, input: &str, output_mode: OutputMode, expected: &str) {
+    let mut options = Options::default();
+    options.import_style = ImportStyle::AssumeExisting;
+    options.output_mode = output_mode;
+    options.collect_additional = true;
+    let res = codegen(name, input, options);
+    let output = res.unwrap();
+    let expected = &expected[1..];
+    assert_eq!(
+        output, expected,
+        "\n\nUnexpected output code:\n  input: {}\n  output:\n{}\n  expected: {}",
+        input, output, expected
+    );
+}
+
+const INPUT: &str = r##"
+    {
+        "user_id": 1,
+        "name": "Alice"
+    }
+"##;
+
+#[test]
+fn jackson_flavour() {
+    code_output_test(
+        "User",
+        INPUT,
+        OutputMode::KotlinJackson,
+        r##"
+data class User(
+    @JsonProperty("user_id")
+    val userId: Long,
+    val name: String,
+    @JsonAnySetter
+    @get:JsonAnyGetter
+    val additionalFields: Map<String, Any> = mutableMapOf(),
+)
+"##,
+    );
+}
+
+#[test]
+fn moshi_flavour() {
+    code_output_test(
+        "User",
+        INPUT,
+        OutputMode::KotlinMoshi,
+        r##"
+@JsonClass(generateAdapter = true)
+data class User(
+    @Json(name = "user_id")
+    val userId: Long,
+    val name: String,
+)
+"##,
+    );
+}
+
+#[test]
+fn gson_flavour() {
+    code_output_test(
+        "User",
+        INPUT,
+        OutputMode::KotlinGson,
+        r##"
+data class User(
+    @SerializedName("user_id")
+    val userId: Long,
+    val name: String,
+)
+"##,
+    );
+}
+
+#[test]
+fn kotlinx_flavour() {
+    code_output_test(
+        "User",
+        INPUT,
+        OutputMode::KotlinKotlinx,
+        r##"
+@Serializable
+data class User(
+    @SerialName("user_id")
+    val userId: Long,
+    val name: String,
+)
+"##,
+    );
+}
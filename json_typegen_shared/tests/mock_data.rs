@@ -0,0 +1,181 @@
+use json_typegen_shared::{InputMode, Options, OutputMode, codegen, parse};
+
+fn mock(input: &str, options: &str) -> String {
+    let mut options = parse::options(options).unwrap();
+    options.output_mode = OutputMode::MockData;
+    codegen("Root", input, options).unwrap()
+}
+
+fn code_output_test(input: &str, options: &str, expected: &str) {
+    let output = mock(input, options);
+    let expected = &expected[1..];
+    assert_eq!(
+        output, expected,
+        "\n\nUnexpected output:\n  input: {}\n  output:\n{}\n  expected: {}",
+        input, output, expected
+    );
+}
+
+const USERS: &str = r#"[
+    { "id": 1, "email": "alice@example.org", "role": "admin", "score": 1.5, "tags": ["a", "b"], "bio": null },
+    { "id": 20, "email": "bob@example.org", "role": "user", "score": 3.25, "tags": ["c"], "nick": "bobby" },
+    { "id": 9, "email": "carol@example.org", "role": "user", "score": 2, "tags": [], "bio": "Hi" }
+]"#;
+
+#[test]
+fn uses_examples_and_bounds() {
+    code_output_test(
+        USERS,
+        r#"{ unwrap: "/-", mock_count: "3", mock_seed: "7" }"#,
+        r##"
+[
+  {
+    "id": 5,
+    "email": "alice@example.org",
+    "role": "admin",
+    "score": 2.32,
+    "tags": [
+      "t"
+    ],
+    "bio": null
+  },
+  {
+    "id": 1,
+    "email": "bob@example.org",
+    "role": "user",
+    "score": 3.25,
+    "tags": [
+      "m",
+      "a"
+    ],
+    "bio": "Hi"
+  },
+  {
+    "id": 13,
+    "email": "dolor5272@example.com",
+    "role": "user",
+    "score": 2,
+    "tags": [
+      "i"
+    ],
+    "bio": null,
+    "nick": "bobby"
+  }
+]
+"##,
+    );
+}
+
+#[test]
+fn deterministic() {
+    let options = r#"{ mock_count: "5", mock_seed: "42" }"#;
+    assert_eq!(mock(USERS, options), mock(USERS, options));
+    assert_ne!(
+        mock(USERS, options),
+        mock(USERS, r#"{ mock_count: "5", mock_seed: "43" }"#)
+    );
+}
+
+#[test]
+fn wide_integer_bounds() {
+    let output = mock(
+        "[-9000000000000000000, -1, 1, 9000000000000000000]",
+        r#"{ mock_count: "20", mock_array_length: "1" }"#,
+    );
+    let numbers: Vec<i64> = output
+        .split(|c: char| !(c.is_ascii_digit() || c == '-'))
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().unwrap())
+        .collect();
+    assert_eq!(numbers.len(), 20);
+    assert!(
+        numbers
+            .iter()
+            .all(|n| (-9000000000000000000..=9000000000000000000).contains(n))
+    );
+}
+
+#[test]
+fn fill_optional_and_array_length() {
+    code_output_test(
+        r#"[{ "a": 1, "items": [1, 2] }, { "a": 2, "b": true, "items": [] }, { "a": 3, "empty": [] }]"#,
+        r#"{ unwrap: "/-", mock_fill_optional: "1", mock_array_length: "4" }"#,
+        r##"
+{
+  "a": 1,
+  "items": [
+    2,
+    1,
+    2,
+    1
+  ],
+  "b": true,
+  "empty": []
+}
+"##,
+    );
+}
+
+#[test]
+fn from_sql() {
+    let mut options = Options::default();
+    options.input_mode = InputMode::Sql;
+    options.output_mode = OutputMode::MockData;
+    options.mock_fill_optional = 0.0;
+    let output = codegen(
+        "Root",
+        "CREATE TABLE users (id INT NOT NULL, name TEXT NOT NULL, nick TEXT);",
+        options,
+    )
+    .unwrap();
+    let expected = r##"
+{
+  "id": 67,
+  "name": "magna",
+  "nick": null
+}
+"##;
+    assert_eq!(output, &expected[1..]);
+}
+
+#[test]
+fn recursive_shape() {
+    let mut options = Options::default();
+    options.input_mode = InputMode::ShapeJson;
+    options.output_mode = OutputMode::MockData;
+    options.mock_array_length = (1, 1);
+    let output = codegen(
+        "Root",
+        r#"{
+            "json_typegen_shape": 1,
+            "shape": { "type": "recursive", "item": { "type": "struct", "fields": {
+                "id": "integer",
+                "children": { "type": "array", "item": "self" }
+            } } }
+        }"#,
+        options,
+    )
+    .unwrap();
+    let expected = r##"
+{
+  "id": 67,
+  "children": [
+    {
+      "id": 88,
+      "children": [
+        {
+          "id": 14,
+          "children": [
+            {
+              "id": 73,
+              "children": []
+            }
+          ]
+        }
+      ]
+    }
+  ]
+}
+"##;
+    assert_eq!(output, &expected[1..]);
+}
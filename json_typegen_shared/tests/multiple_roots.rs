@@ -0,0 +1,208 @@
+use json_typegen_shared::{ImportStyle, Options, OutputMode, codegen_multiple};
+
+fn code_output_test(roots: &[(&str, &str)], output_mode: OutputMode, expected: &str) {
+    let mut options = Options::default();
+    options.import_style = ImportStyle::AssumeExisting;
+    options.output_mode = output_mode;
+    let res = codegen_multiple(roots, options);
+    let output = res.unwrap();
+    let expected = &expected[1..];
+    assert_eq!(
+        output, expected,
+        "\n\nUnexpected output code:\n  roots: {:?}\n  output:\n{}\n  expected: {}",
+        roots, output, expected
+    );
+}
+
+const USER: &str = r#"{ "id": 1, "name": "Alice" }"#;
+const ORDER: &str =
+    r#"{ "id": 7, "buyer": { "id": 2, "name": "Bob" }, "lines": [{ "sku": "A1" }] }"#;
+const ORDERS: &str = r#"[{ "id": 8, "name": "Coffee" }]"#;
+
+#[test]
+fn rust_shared_types() {
+    code_output_test(
+        &[("User", USER), ("Order", ORDER), ("Orders", ORDERS)],
+        OutputMode::Rust,
+        r##"
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct User {
+    pub id: i64,
+    pub name: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Order {
+    pub id: i64,
+    pub buyer: User,
+    pub lines: Vec<Lines>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lines {
+    pub sku: String,
+}
+
+pub type Orders = Vec<User>;
+"##,
+    );
+}
+
+#[test]
+fn rust_shared_type_named_by_first_use() {
+    code_output_test(
+        &[("Order", ORDER), ("User", USER)],
+        OutputMode::Rust,
+        r##"
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Order {
+    pub id: i64,
+    pub buyer: Buyer,
+    pub lines: Vec<Lines>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Buyer {
+    pub id: i64,
+    pub name: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lines {
+    pub sku: String,
+}
+
+pub type User = Buyer;
+"##,
+    );
+}
+
+#[test]
+fn rust_visibility_in_names() {
+    code_output_test(
+        &[("pub(crate) User", USER), ("pub(crate) Orders", ORDERS)],
+        OutputMode::Rust,
+        r##"
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct User {
+    pub id: i64,
+    pub name: String,
+}
+
+pub(crate) type Orders = Vec<User>;
+"##,
+    );
+
+    let mut options = Options::default();
+    options.output_mode = OutputMode::Rust;
+    let res = codegen_multiple(&[("pub(crate) User", USER), ("Order", ORDER)], options);
+    assert!(res.is_err());
+}
+
+#[test]
+fn typescript_shared_types() {
+    code_output_test(
+        &[("User", USER), ("Order", ORDER)],
+        OutputMode::Typescript,
+        r##"
+export interface User {
+    id: number;
+    name: string;
+}
+
+export interface Order {
+    id: number;
+    buyer: User;
+    lines: Lines[];
+}
+
+export interface Lines {
+    sku: string;
+}
+"##,
+    );
+}
+
+#[test]
+fn python_shared_types() {
+    code_output_test(
+        &[("User", USER), ("Order", ORDER)],
+        OutputMode::PythonPydantic,
+        r##"
+class User(BaseModel):
+    id: int
+    name: str
+
+
+class Lines(BaseModel):
+    sku: str
+
+
+class Order(BaseModel):
+    id: int
+    buyer: User
+    lines: list[Lines]
+"##,
+    );
+}
+
+#[test]
+fn kotlin_shared_types() {
+    code_output_test(
+        &[("User", USER), ("Order", ORDER)],
+        OutputMode::KotlinJackson,
+        r##"
+data class User(
+    val id: Long,
+    val name: String,
+)
+
+data class Order(
+    val id: Long,
+    val buyer: User,
+    val lines: List<Lines>,
+)
+
+data class Lines(
+    val sku: String,
+)
+"##,
+    );
+}
+
+#[test]
+fn separate_roots_without_shared_types() {
+    code_output_test(
+        &[("User", USER), ("Order", ORDER)],
+        OutputMode::ZodSchema,
+        r##"
+export const userSchema = z.object({
+    id: z.number(),
+    name: z.string(),
+});
+
+export type User = z.infer<typeof userSchema>;
+
+export const buyerSchema = z.object({
+    id: z.number(),
+    name: z.string(),
+});
+
+export type Buyer = z.infer<typeof buyerSchema>;
+
+export const linesSchema = z.object({
+    sku: z.string(),
+});
+
+export type Lines = z.infer<typeof linesSchema>;
+
+export const orderSchema = z.object({
+    id: z.number(),
+    buyer: buyerSchema,
+    lines: linesSchema.array(),
+});
+
+export type Order = z.infer<typeof orderSchema>;
+"##,
+    );
+}
@@ -0,0 +1,132 @@
+use json_typegen_shared::{Options, OutputMode, codegen};
+
+fn code_output_test(name: &str, input: &str, expected: &str) {
+    let mut options = Options::default();
+    options.output_mode = OutputMode::Protobuf;
+    let res = codegen(name, input, options);
+    let output = res.unwrap();
+    let expected = &expected[1..];
+    assert_eq!(
+        output, expected,
+        "\n\nUnexpected output code:\n  input: {}\n  output:\n{}\n  expected: {}",
+        input, output, expected
+    );
+}
+
+#[test]
+fn empty_object() {
+    code_output_test(
+        "Root",
+        r##"
+            {}
+        "##,
+        r##"
+syntax = "proto3";
+
+message Root {
+}
+"##,
+    );
+}
+
+#[test]
+fn list_of_numbers() {
+    code_output_test(
+        "Numbers",
+        r##"
+            [1, 2, 3]
+        "##,
+        r##"
+syntax = "proto3";
+
+message Numbers {
+  repeated int64 value = 1;
+}
+"##,
+    );
+}
+
+#[test]
+fn optionals() {
+    code_output_test(
+        "Opts",
+        r##"
+            {
+                "items": [
+                    {
+                        "in_both": 5,
+                        "missing": 5,
+                        "has_null": 5,
+                        "tags": ["a"]
+                    },
+                    {
+                        "in_both": 5,
+                        "has_null": null,
+                        "added": 5.5
+                    }
+                ]
+            }
+        "##,
+        r##"
+syntax = "proto3";
+
+message Opts {
+  repeated Item items = 1;
+}
+
+message Item {
+  int64 in_both = 1 [json_name = "in_both"];
+  optional int64 missing = 2;
+  optional int64 has_null = 3 [json_name = "has_null"];
+  repeated string tags = 4;
+  optional double added = 5;
+}
+"##,
+    );
+}
+
+#[test]
+fn nesting_and_well_known_types() {
+    code_output_test(
+        "NestedTypes",
+        r##"
+            {
+                "userInfo": {
+                    "firstName": "Jane",
+                    "scores": [1, null]
+                },
+                "lookup": {
+                    "a": { "x": 1 }
+                },
+                "anything": null,
+                "matrix": [[1], [2], [3]]
+            }
+        "##,
+        r##"
+syntax = "proto3";
+
+import "google/protobuf/struct.proto";
+import "google/protobuf/wrappers.proto";
+
+message NestedTypes {
+  UserInfo user_info = 1;
+  Lookup lookup = 2;
+  google.protobuf.Value anything = 3;
+  repeated google.protobuf.ListValue matrix = 4;
+}
+
+message UserInfo {
+  string first_name = 1;
+  repeated google.protobuf.Int64Value scores = 2;
+}
+
+message Lookup {
+  A a = 1;
+}
+
+message A {
+  int64 x = 1;
+}
+"##,
+    );
+}
@@ -1,10 +1,18 @@
-use json_typegen_shared::{ImportStyle, Options, OutputMode, codegen};
+use json_typegen_shared::{ImportStyle, Options, OutputMode, StringTransform, codegen};
 
 /// Function to test AST equality, not string equality
 fn code_output_test(name: &str, input: &str, expected: &str) {
+    flavour_output_test(name, input, OutputMode::PythonPydantic, expected);
+}
+
+fn flavour_output_test(name: &str, input: &str, output_mode: OutputMode, expected: &str) {
     let mut options = Options::default();
+    options.output_mode = output_mode;
+    options_output_test(name, input, options, expected);
+}
+
+fn options_output_test(name: &str, input: &str, mut options: Options, expected: &str) {
     options.import_style = ImportStyle::AssumeExisting;
-    options.output_mode = OutputMode::PythonPydantic;
     let res = codegen(name, input, options);
     let output = res.unwrap();
     let expected = &expected[1..];
@@ -213,3 +221,152 @@ class Renamed(BaseModel):
 "##,
     );
 }
+
+const FLAVOUR_INPUT: &str = r##"
+    [
+        { "userId": 1, "display-name": "Alice", "nickname": null, "avatar": { "url": "a.png" } },
+        { "userId": 2, "display-name": "Bob", "nickname": "bobby" }
+    ]
+"##;
+
+#[test]
+fn dataclass_flavour() {
+    flavour_output_test(
+        "Users",
+        FLAVOUR_INPUT,
+        OutputMode::PythonDataclass,
+        r##"
+@dataclass
+class Avatar:
+    url: str
+
+
+@dataclass
+class User:
+    user_id: int = field(metadata={"alias": "userId"})
+    display_name: str = field(metadata={"alias": "display-name"})
+    nickname: Optional[str]
+    avatar: Optional[Avatar]
+
+
+Users = list[User]
+"##,
+    );
+}
+
+#[test]
+fn typeddict_flavour() {
+    flavour_output_test(
+        "Users",
+        FLAVOUR_INPUT,
+        OutputMode::PythonTypedDict,
+        r##"
+class Avatar(TypedDict):
+    url: str
+
+
+User = TypedDict("User", {
+    "userId": int,
+    "display-name": str,
+    "nickname": Optional[str],
+    "avatar": NotRequired[Avatar],
+})
+
+
+Users = list[User]
+"##,
+    );
+}
+
+#[test]
+fn attrs_flavour() {
+    flavour_output_test(
+        "Users",
+        FLAVOUR_INPUT,
+        OutputMode::PythonAttrs,
+        r##"
+@define
+class Avatar:
+    url: str
+
+
+@define
+class User:
+    user_id: int = field(metadata={"alias": "userId"})
+    display_name: str = field(metadata={"alias": "display-name"})
+    nickname: Optional[str]
+    avatar: Optional[Avatar]
+
+
+Users = list[User]
+"##,
+    );
+}
+
+#[test]
+fn msgspec_flavour() {
+    flavour_output_test(
+        "Users",
+        FLAVOUR_INPUT,
+        OutputMode::PythonMsgspec,
+        r##"
+class Avatar(Struct):
+    url: str
+
+
+class User(Struct):
+    user_id: int = field(name="userId")
+    display_name: str = field(name="display-name")
+    nickname: Optional[str]
+    avatar: Optional[Avatar]
+
+
+Users = list[User]
+"##,
+    );
+}
+
+#[test]
+fn pydantic_model_config() {
+    let mut options = Options::default();
+    options.output_mode = OutputMode::PythonPydantic;
+    options.deny_unknown_fields = true;
+    options.property_name_format = Some(StringTransform::CamelCase);
+    options_output_test(
+        "User",
+        r##"
+            { "userId": 1, "displayName": "Alice" }
+        "##,
+        options,
+        r##"
+class User(BaseModel):
+    model_config = ConfigDict(populate_by_name=True, extra="forbid", alias_generator=to_camel)
+
+    user_id: int
+    display_name: str
+"##,
+    );
+}
+
+#[test]
+fn pydantic_v1_config() {
+    let mut options = Options::default();
+    options.output_mode = OutputMode::PythonPydanticV1;
+    options.collect_additional = true;
+    options_output_test(
+        "User",
+        r##"
+            { "userId": 1, "name": "Alice" }
+        "##,
+        options,
+        r##"
+class User(BaseModel):
+    user_id: int = Field(alias="userId")
+    name: str
+
+    class Config:
+        allow_population_by_field_name = True
+        extra = Extra.allow
+"##,
+    );
+}
@@ -0,0 +1,282 @@
+use json_typegen_shared::{BorrowStyle, ImportStyle, Options, OutputMode, codegen};
+
+fn code_output_test(name: &str, input: &str, output_mode: OutputMode, expected: &str) {
+    let mut options = Options::default();
+    options.import_style = ImportStyle::AssumeExisting;
+    options.output_mode = output_mode;
+    let res = codegen(name, input, options);
+    let output = res.unwrap();
+    let expected = &expected[1..];
+    assert_eq!(
+        output, expected,
+        "\n\nUnexpected output code:\n  input: {}\n  output:\n{}\n  expected: {}",
+        input, output, expected
+    );
+}
+
+const THREAD: &str = r##"
+    {
+        "id": 1,
+        "text": "First!",
+        "author": { "name": "Alice" },
+        "replies": [
+            {
+                "id": 2,
+                "text": "Second",
+                "author": { "name": "Bob" },
+                "replies": [
+                    { "id": 3, "text": "Third", "author": { "name": "Alice" }, "replies": [] }
+                ]
+            }
+        ]
+    }
+"##;
+
+const LINKED: &str = r##"
+    {
+        "value": 1,
+        "next": { "value": 2, "next": { "value": 3, "next": null } }
+    }
+"##;
+
+#[test]
+fn rust_recursive_types() {
+    code_output_test(
+        "Comment",
+        THREAD,
+        OutputMode::Rust,
+        r##"
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Comment {
+    pub id: i64,
+    pub text: String,
+    pub author: Author,
+    pub replies: Vec<Comment>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Author {
+    pub name: String,
+}
+"##,
+    );
+    code_output_test(
+        "Node",
+        LINKED,
+        OutputMode::Rust,
+        r##"
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Node {
+    pub value: i64,
+    pub next: Option<Box<Node>>,
+}
+"##,
+    );
+}
+
+#[test]
+fn typescript_recursive_types() {
+    code_output_test(
+        "Comment",
+        THREAD,
+        OutputMode::Typescript,
+        r##"
+export interface Comment {
+    id: number;
+    text: string;
+    author: Author;
+    replies: Comment[];
+}
+
+export interface Author {
+    name: string;
+}
+"##,
+    );
+}
+
+#[test]
+fn kotlin_recursive_types() {
+    code_output_test(
+        "Comment",
+        THREAD,
+        OutputMode::KotlinJackson,
+        r##"
+data class Comment(
+    val id: Long,
+    val text: String,
+    val author: Author,
+    val replies: List<Comment>,
+)
+
+data class Author(
+    val name: String,
+)
+"##,
+    );
+}
+
+#[test]
+fn python_recursive_types() {
+    code_output_test(
+        "Node",
+        LINKED,
+        OutputMode::PythonPydantic,
+        r##"
+class Node(BaseModel):
+    value: int
+    next: Optional["Node"]
+"##,
+    );
+}
+
+#[test]
+fn zod_recursive_schemas() {
+    code_output_test(
+        "Comment",
+        THREAD,
+        OutputMode::ZodSchema,
+        r##"
+export const authorSchema = z.object({
+    name: z.string(),
+});
+
+export type Author = z.infer<typeof authorSchema>;
+
+export type Comment = {
+    id: number;
+    text: string;
+    author: Author;
+    replies: Comment[];
+};
+
+export const commentSchema: z.ZodType<Comment> = z.object({
+    id: z.number(),
+    text: z.string(),
+    author: authorSchema,
+    replies: z.lazy(() => commentSchema).array(),
+});
+"##,
+    );
+}
+
+#[test]
+fn json_schema_recursive_refs() {
+    code_output_test(
+        "Comment",
+        THREAD,
+        OutputMode::JsonSchema,
+        r##"
+{
+  "$schema": "http://json-schema.org/draft-07/schema#",
+  "title": "Generated schema for Comment",
+  "type": "object",
+  "properties": {
+    "id": {
+      "type": "integer"
+    },
+    "text": {
+      "type": "string"
+    },
+    "author": {
+      "type": "object",
+      "properties": {
+        "name": {
+          "type": "string"
+        }
+      },
+      "required": [
+        "name"
+      ]
+    },
+    "replies": {
+      "type": "array",
+      "items": {
+        "$ref": "#"
+      }
+    }
+  },
+  "required": [
+    "id",
+    "text",
+    "author",
+    "replies"
+  ]
+}
+"##,
+    );
+}
+
+#[test]
+fn nested_objects_with_same_keys_are_not_recursive() {
+    code_output_test(
+        "Root",
+        r#"{"name":"a","meta":{"name":"b","meta":"x"}}"#,
+        OutputMode::Rust,
+        r##"
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Root {
+    pub name: String,
+    pub meta: Meta,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Meta {
+    pub name: String,
+    pub meta: String,
+}
+"##,
+    );
+    code_output_test(
+        "Root",
+        r#"{"data":{"data":1}}"#,
+        OutputMode::Rust,
+        r##"
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Root {
+    pub data: Data,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Data {
+    pub data: i64,
+}
+"##,
+    );
+}
+
+#[test]
+fn rust_recursive_types_with_lifetime() {
+    let input = r#"{ "name": "a", "children": [{ "name": "b", "children": [] }] }"#;
+    for (borrow, name_type) in [
+        (BorrowStyle::Str, "&'a str"),
+        (BorrowStyle::Cow, "Cow<'a, str>"),
+    ] {
+        let mut options = Options::default();
+        options.import_style = ImportStyle::AssumeExisting;
+        options.borrow = borrow;
+        let output = codegen("Root", input, options).unwrap();
+        assert!(output.contains("pub struct Root<'a> {"), "{}", output);
+        assert!(
+            output.contains(&format!("pub name: {},", name_type)),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("pub children: Vec<Root<'a>>,"),
+            "{}",
+            output
+        );
+    }
+
+    let mut options = Options::default();
+    options.import_style = ImportStyle::AssumeExisting;
+    options.borrow = BorrowStyle::Cow;
+    let input = r#"{ "value": "a", "next": { "value": "b", "next": null } }"#;
+    let output = codegen("Node", input, options).unwrap();
+    assert!(
+        output.contains("pub next: Option<Box<Node<'a>>>,"),
+        "{}",
+        output
+    );
+}
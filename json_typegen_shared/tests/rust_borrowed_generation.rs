@@ -0,0 +1,161 @@
+use testsyn::{Item, parse_str};
+
+use json_typegen_shared::{BorrowStyle, ImportStyle, Options, codegen};
+
+/// Function to test AST equality, not string equality
+fn borrowed_output_test(name: &str, borrow: BorrowStyle, input: &str, expected: &str) {
+    let mut options = Options::default();
+    options.derives = "Debug, Deserialize".into();
+    borrowed_output_test_with_options(name, borrow, options, input, expected);
+}
+
+fn borrowed_output_test_with_options(
+    name: &str,
+    borrow: BorrowStyle,
+    mut options: Options,
+    input: &str,
+    expected: &str,
+) {
+    options.import_style = ImportStyle::AssumeExisting;
+    options.borrow = borrow;
+    let res = codegen(name, input, options);
+    let output = res.unwrap();
+    assert_eq!(
+        // Wrapping in mod Foo { } since there is no impl Parse for Vec<Item>
+        parse_str::<Item>(&format!("mod Foo {{ {} }}", &output)).unwrap(),
+        parse_str::<Item>(&format!("mod Foo {{ {} }}", expected)).unwrap(),
+        "\n\nUnexpected output code:\n  input: {}\n  output:\n{}\n  expected: {}",
+        input,
+        output,
+        expected
+    );
+}
+
+#[test]
+fn borrowed_str() {
+    borrowed_output_test(
+        "Event",
+        BorrowStyle::Str,
+        // language=JSON
+        r##"
+            {
+                "id": 1,
+                "kind": "click",
+                "payload": null,
+                "tags": ["a", "b"],
+                "user": { "name": "Alice", "active": true },
+                "position": { "x": 1, "y": 2 }
+            }
+        "##,
+        // language=Rust
+        r##"
+            #[derive(Debug, Deserialize)]
+            pub struct Event<'a> {
+                pub id: i64,
+                pub kind: &'a str,
+                #[serde(borrow)]
+                pub payload: &'a RawValue,
+                #[serde(borrow)]
+                pub tags: Vec<&'a str>,
+                #[serde(borrow)]
+                pub user: User<'a>,
+                pub position: Position,
+            }
+
+            #[derive(Debug, Deserialize)]
+            pub struct User<'a> {
+                pub name: &'a str,
+                pub active: bool,
+            }
+
+            #[derive(Debug, Deserialize)]
+            pub struct Position {
+                pub x: i64,
+                pub y: i64,
+            }
+        "##,
+    );
+}
+
+#[test]
+fn borrowed_cow_in_list() {
+    borrowed_output_test(
+        "Users",
+        BorrowStyle::Cow,
+        // language=JSON
+        r##"
+            [
+                { "name": "Alice", "nickname": "Al", "scores": { "a": 1 } },
+                { "name": "Bob", "nickname": null, "scores": { "b": 2 } }
+            ]
+        "##,
+        // language=Rust
+        r##"
+            pub type Users<'a> = Vec<User<'a>>;
+
+            #[derive(Debug, Deserialize)]
+            pub struct User<'a> {
+                #[serde(borrow)]
+                pub name: Cow<'a, str>,
+                #[serde(borrow)]
+                pub nickname: Option<Cow<'a, str>>,
+                pub scores: Scores,
+            }
+
+            #[derive(Debug, Deserialize)]
+            pub struct Scores {
+                pub a: Option<i64>,
+                pub b: Option<i64>,
+            }
+        "##,
+    );
+}
+
+#[test]
+fn raw_value_without_default_and_partial_eq() {
+    borrowed_output_test_with_options(
+        "Event",
+        BorrowStyle::Str,
+        Options::default(),
+        // language=JSON
+        r##"
+            {
+                "name": "x",
+                "extra": null,
+                "meta": { "source": "web", "details": { "raw": [] } },
+                "position": { "x": 1, "y": 2 }
+            }
+        "##,
+        // language=Rust
+        r##"
+            #[derive(Debug, Clone, Serialize, Deserialize)]
+            pub struct Event<'a> {
+                pub name: &'a str,
+                #[serde(borrow)]
+                pub extra: &'a RawValue,
+                #[serde(borrow)]
+                pub meta: Meta<'a>,
+                pub position: Position,
+            }
+
+            #[derive(Debug, Clone, Serialize, Deserialize)]
+            pub struct Meta<'a> {
+                pub source: &'a str,
+                #[serde(borrow)]
+                pub details: Details<'a>,
+            }
+
+            #[derive(Debug, Clone, Serialize, Deserialize)]
+            pub struct Details<'a> {
+                #[serde(borrow)]
+                pub raw: Vec<&'a RawValue>,
+            }
+
+            #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+            pub struct Position {
+                pub x: i64,
+                pub y: i64,
+            }
+        "##,
+    );
+}
@@ -0,0 +1,283 @@
+use json_typegen_shared::{Options, merge_rust, parse};
+
+fn merge_output_test(existing: &str, input: &str, options: &str, expected: &str) {
+    let options = parse::options(options).unwrap();
+    let output = merge_rust(existing, "Root", input, options).unwrap().code;
+    let expected = &expected[1..];
+    assert_eq!(
+        output, expected,
+        "\n\nUnexpected output:\n  input: {}\n  output:\n{}\n  expected: {}",
+        input, output, expected
+    );
+}
+
+const EXISTING: &str = r##"use serde_derive::{Deserialize, Serialize};
+
+/// A user, as returned by the API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Root {
+    #[serde(rename = "userName")]
+    pub name: String,
+    pub id: i64,
+    pub role: Role,
+    pub home: Location,
+    pub legacy: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Location {
+    pub city: String, // where they live
+    pub zip: String
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Role {
+    Admin,
+    User,
+}
+"##;
+
+#[test]
+fn keeps_manual_changes() {
+    merge_output_test(
+        EXISTING,
+        r#"{
+            "id": 1.5,
+            "userName": "alice",
+            "role": "admin",
+            "home": { "city": "Oslo", "zip": "0150", "country": "NO" },
+            "legacy": true,
+            "email": "alice@example.org"
+        }"#,
+        "{}",
+        r##"
+use serde_derive::{Deserialize, Serialize};
+
+/// A user, as returned by the API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Root {
+    #[serde(rename = "userName")]
+    pub name: String,
+    pub id: f64,
+    pub role: Role,
+    pub home: Location,
+    pub legacy: bool,
+    pub email: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Location {
+    pub city: String, // where they live
+    pub zip: String,
+    pub country: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Role {
+    Admin,
+    User,
+}
+"##,
+    );
+}
+
+#[test]
+fn adds_types_for_new_objects() {
+    merge_output_test(
+        EXISTING,
+        r#"{
+            "id": 1,
+            "userName": "alice",
+            "role": "admin",
+            "home": { "city": "Oslo", "zip": "0150" },
+            "legacy": true,
+            "settings": { "theme": "dark", "shortcuts": [{ "key": "s", "action": "save" }] }
+        }"#,
+        "{}",
+        r##"
+use serde_derive::{Deserialize, Serialize};
+
+/// A user, as returned by the API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Root {
+    #[serde(rename = "userName")]
+    pub name: String,
+    pub id: i64,
+    pub role: Role,
+    pub home: Location,
+    pub legacy: bool,
+    pub settings: Settings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Location {
+    pub city: String, // where they live
+    pub zip: String
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Role {
+    Admin,
+    User,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    pub theme: String,
+    pub shortcuts: Vec<Shortcut>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Shortcut {
+    pub key: String,
+    pub action: String,
+}
+"##,
+    );
+}
+
+#[test]
+fn wraps_types_that_became_optional() {
+    merge_output_test(
+        EXISTING,
+        r#"[
+            { "id": 1, "userName": "alice", "role": "admin", "home": null, "legacy": true },
+            { "id": 2, "role": "user", "home": { "city": "Oslo", "zip": "0150" }, "legacy": false }
+        ]"#,
+        r#"{ unwrap: "/-" }"#,
+        r##"
+use serde_derive::{Deserialize, Serialize};
+
+/// A user, as returned by the API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Root {
+    #[serde(rename = "userName")]
+    pub name: Option<String>,
+    pub id: i64,
+    pub role: Role,
+    pub home: Option<Location>,
+    pub legacy: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Location {
+    pub city: String, // where they live
+    pub zip: String
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Role {
+    Admin,
+    User,
+}
+"##,
+    );
+}
+
+#[test]
+fn rename_all_and_aliases() {
+    merge_output_test(
+        r##"pub type Root = Vec<Item>;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Item {
+    pub item_id: u32,
+    pub r#type: String,
+}
+"##,
+        r#"[{ "itemId": 3, "type": "a", "inStock": true }]"#,
+        "{}",
+        r##"
+pub type Root = Vec<Item>;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Item {
+    pub item_id: u32,
+    pub r#type: String,
+    pub in_stock: bool,
+}
+"##,
+    );
+}
+
+#[test]
+fn reports_removed_fields() {
+    let merged = merge_rust(
+        EXISTING,
+        "Root",
+        r#"{ "id": 1, "userName": "alice", "role": "admin", "home": { "city": "Oslo" } }"#,
+        Options::default(),
+    )
+    .unwrap();
+    let removed: Vec<String> = merged
+        .removed_fields
+        .iter()
+        .map(|removed| removed.to_string())
+        .collect();
+    assert_eq!(
+        removed,
+        vec![
+            "/home/zip: Location::zip is not in the input",
+            "/legacy: Root::legacy is not in the input",
+        ]
+    );
+    assert_eq!(merged.code, EXISTING);
+}
+
+#[test]
+fn appends_missing_root() {
+    merge_output_test(
+        "use serde_derive::Deserialize;\n\n#[derive(Deserialize)]\npub struct Other {}\n",
+        r#"{ "a": 1 }"#,
+        "{}",
+        r##"
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+
+#[derive(Deserialize)]
+pub struct Other {}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Root {
+    pub a: i64,
+}
+"##,
+    );
+}
+
+#[test]
+fn invalid_existing_code() {
+    let result = merge_rust(
+        "pub struct Root { a: \"i64 }",
+        "Root",
+        r#"{ "a": 1 }"#,
+        Options::default(),
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn root_that_can_not_be_merged() {
+    for (existing, message) in [
+        (EXISTING, "Root is a struct, but the input is tuple of 2"),
+        (
+            "pub struct Root(i64);\n",
+            "Root is neither a struct with named fields nor a type alias",
+        ),
+        (
+            "pub enum Root {\n    A,\n}\n",
+            "Root is neither a struct with named fields nor a type alias",
+        ),
+    ] {
+        let err = merge_rust(existing, "Root", "[1, true]", Options::default()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "The existing Rust code can not be updated to fit the input: {}",
+                message
+            )
+        );
+    }
+}
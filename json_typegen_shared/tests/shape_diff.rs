@@ -0,0 +1,75 @@
+use json_typegen_shared::{ChangeKind, InputMode, Options, diff_shapes, infer_shape};
+
+fn diff_samples(old: &str, new: &str) -> Vec<String> {
+    let old = infer_shape(old, Options::default()).unwrap();
+    let mut options = Options::default();
+    if new.trim_start().starts_with('[') {
+        options.unwrap = "/-".into();
+    }
+    let new = infer_shape(new, options).unwrap();
+    diff_shapes(&old, &new)
+        .iter()
+        .map(|change| change.to_string())
+        .collect()
+}
+
+#[test]
+fn unchanged() {
+    assert_eq!(
+        diff_samples(
+            r#"{ "a": 1, "b": ["x"] }"#,
+            r#"{ "b": ["y", "z"], "a": 2 }"#
+        ),
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+fn changes_between_samples() {
+    let old = r#"{
+        "id": 1,
+        "price": 10,
+        "ratio": 0.5,
+        "name": "Alice",
+        "tags": ["a"],
+        "meta": { "source": "web", "legacy": true }
+    }"#;
+    let new = r#"[
+        { "id": "u-1", "price": 10.5, "ratio": 1, "name": null, "tags": [1, "b"], "meta": { "source": "app" } },
+        { "id": "u-2", "price": 11, "ratio": 1, "name": "Bob", "tags": [], "created": "2024-01-01" }
+    ]"#;
+    assert_eq!(
+        diff_samples(old, new),
+        vec![
+            "breaking: /id: type changed from integer to string",
+            "breaking: /price: type widened from integer to floating",
+            "compatible: /ratio: type narrowed from floating to integer",
+            "breaking: /name: value became nullable",
+            "breaking: /tags/-: type widened from string to any",
+            "breaking: /meta: field became optional",
+            "breaking: /meta/legacy: field removed",
+            "compatible: /created: field added",
+        ]
+    );
+}
+
+#[test]
+fn unwrapped_and_saved_shapes() {
+    let mut options = Options::default();
+    options.unwrap = "/-".into();
+    let new = infer_shape(r#"[{ "a": 1 }, { "a": 2, "b": "x" }]"#, options).unwrap();
+
+    let mut options = Options::default();
+    options.input_mode = InputMode::ShapeJson;
+    let old = infer_shape(
+        r#"{ "json_typegen_shape": 1, "shape": { "type": "struct", "fields": { "a": "integer" } } }"#,
+        options,
+    )
+    .unwrap();
+
+    let changes = diff_shapes(&old, &new);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].pointer, "/b");
+    assert_eq!(changes[0].kind, ChangeKind::FieldAdded);
+    assert!(!changes[0].breaking);
+}
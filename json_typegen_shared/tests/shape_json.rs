@@ -0,0 +1,113 @@
+use json_typegen_shared::{InputMode, Options, OutputMode, Shape, codegen, codegen_from_shape};
+
+const SAMPLE: &str = r#"[
+    { "id": 1, "name": "Alice", "tags": ["a"], "address": { "city": "Oslo" } },
+    { "id": 2, "name": null, "tags": [], "score": 1.5 }
+]"#;
+
+fn options(output_mode: OutputMode) -> Options {
+    let mut options = Options::default();
+    options.output_mode = output_mode;
+    options
+}
+
+#[test]
+fn saved_shape_generates_same_code() {
+    let saved = codegen("Root", SAMPLE, options(OutputMode::ShapeJson)).unwrap();
+
+    for output_mode in [
+        OutputMode::Rust,
+        OutputMode::Typescript,
+        OutputMode::KotlinJackson,
+        OutputMode::PythonPydantic,
+        OutputMode::JsonSchema,
+    ] {
+        let mut from_shape_options = options(output_mode.clone());
+        from_shape_options.input_mode = InputMode::ShapeJson;
+        assert_eq!(
+            codegen("Root", &saved, from_shape_options).unwrap(),
+            codegen("Root", SAMPLE, options(output_mode)).unwrap()
+        );
+    }
+}
+
+#[test]
+fn edited_shape() {
+    let shape = Shape::from_json(
+        r#"{
+            "json_typegen_shape": 1,
+            "shape": {
+                "type": "struct",
+                "fields": {
+                    "id": { "type": "opaque", "name": "Uuid" },
+                    "counts": { "type": "map", "values": "integer" },
+                    "note": { "type": "optional", "item": "string" }
+                }
+            }
+        }"#,
+    )
+    .unwrap();
+
+    let mut options = options(OutputMode::Rust);
+    options.derives = "Debug".into();
+    let expected = r##"
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub struct Root {
+    pub id: Uuid,
+    pub counts: HashMap<String, i64>,
+    pub note: Option<String>,
+}
+"##;
+    assert_eq!(
+        codegen_from_shape("Root", &shape, options).unwrap(),
+        &expected[1..]
+    );
+}
+
+#[test]
+fn shape_output() {
+    let output = codegen(
+        "Root",
+        r#"{ "id": 1, "pair": [true, "a"] }"#,
+        options(OutputMode::ShapeJson),
+    )
+    .unwrap();
+    let expected = r##"
+{
+  "json_typegen_shape": 1,
+  "shape": {
+    "type": "struct",
+    "fields": {
+      "id": "integer",
+      "pair": {
+        "type": "tuple",
+        "items": [
+          "bool",
+          "string"
+        ],
+        "count": 1
+      }
+    }
+  }
+}
+"##;
+    assert_eq!(output, &expected[1..]);
+}
+
+#[test]
+fn invalid_shape() {
+    let mut options = options(OutputMode::Rust);
+    options.input_mode = InputMode::ShapeJson;
+    let err = codegen(
+        "Root",
+        r#"{ "json_typegen_shape": 1, "shape": "text" }"#,
+        options,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "An error occurred while parsing a shape: Unknown shape \"text\""
+    );
+}
@@ -0,0 +1,135 @@
+use json_typegen_shared::{Options, OutputMode, SqlDialect, codegen};
+
+fn code_output_test(name: &str, input: &str, mut options: Options, expected: &str) {
+    options.output_mode = OutputMode::SqlDdl;
+    let res = codegen(name, input, options);
+    let output = res.unwrap();
+    let expected = &expected[1..];
+    assert_eq!(
+        output, expected,
+        "\n\nUnexpected output code:\n  input: {}\n  output:\n{}\n  expected: {}",
+        input, output, expected
+    );
+}
+
+#[test]
+fn flattened_columns() {
+    code_output_test(
+        "Customer",
+        r##"
+            [
+                {
+                    "name": "Alice",
+                    "age": 31,
+                    "verified": true,
+                    "nickname": null,
+                    "address": {
+                        "street": "Main Street 1",
+                        "geo": { "lat": 59.9, "lng": 10.7, "extra": { "source": "gps" } }
+                    },
+                    "tags": ["a", "b"],
+                    "meta": 1
+                },
+                {
+                    "name": "Bob",
+                    "age": 25,
+                    "verified": false,
+                    "nickname": "bobby",
+                    "address": {
+                        "street": "Side Street 2",
+                        "geo": { "lat": 60.4, "lng": 5.3, "extra": { "source": "manual" } }
+                    },
+                    "tags": [],
+                    "meta": "one"
+                }
+            ]
+        "##,
+        Options::default(),
+        r##"
+CREATE TABLE customer (
+    name TEXT NOT NULL,
+    age BIGINT NOT NULL,
+    verified BOOLEAN NOT NULL,
+    nickname TEXT,
+    address_street TEXT NOT NULL,
+    address_geo_lat DOUBLE PRECISION NOT NULL,
+    address_geo_lng DOUBLE PRECISION NOT NULL,
+    address_geo_extra JSONB NOT NULL,
+    tags JSONB NOT NULL,
+    meta JSONB
+);
+"##,
+    );
+}
+
+#[test]
+fn child_tables() {
+    code_output_test(
+        "Order",
+        r##"
+            {
+                "id": 1,
+                "lines": [
+                    { "sku": "A-1", "quantity": 2, "discounts": [{ "code": "SUMMER" }] }
+                ],
+                "notes": [{ "text": "leave at door" }]
+            }
+        "##,
+        Options::default(),
+        r##"
+CREATE TABLE "order" (
+    id BIGINT PRIMARY KEY
+);
+
+CREATE TABLE order_lines (
+    id BIGSERIAL PRIMARY KEY,
+    order_id BIGINT NOT NULL REFERENCES "order" (id),
+    sku TEXT NOT NULL,
+    quantity BIGINT NOT NULL
+);
+
+CREATE TABLE order_lines_discounts (
+    order_lines_id BIGINT NOT NULL REFERENCES order_lines (id),
+    code TEXT NOT NULL
+);
+
+CREATE TABLE order_notes (
+    order_id BIGINT NOT NULL REFERENCES "order" (id),
+    text TEXT NOT NULL
+);
+"##,
+    );
+}
+
+#[test]
+fn sqlite_dialect() {
+    let mut options = Options::default();
+    options.sql_dialect = SqlDialect::Sqlite;
+    code_output_test(
+        "Event",
+        r##"
+            {
+                "name": "deploy",
+                "success": true,
+                "duration": 1.5,
+                "payload": [1, "two"],
+                "steps": [{ "name": "build" }]
+            }
+        "##,
+        options,
+        r##"
+CREATE TABLE event (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    success INTEGER NOT NULL,
+    duration REAL NOT NULL,
+    payload TEXT NOT NULL
+);
+
+CREATE TABLE event_steps (
+    event_id INTEGER NOT NULL REFERENCES event (id),
+    name TEXT NOT NULL
+);
+"##,
+    );
+}
@@ -0,0 +1,130 @@
+use json_typegen_shared::{Options, OutputMode, codegen};
+
+fn render(input: &str, template: &str) -> Result<String, String> {
+    let mut options = Options::default();
+    options.output_mode = OutputMode::Template;
+    options.template = Some(template.to_string());
+    codegen("Root", input, options).map_err(|err| err.to_string())
+}
+
+fn template_output_test(input: &str, template: &str, expected: &str) {
+    let output = render(input, template).unwrap();
+    let expected = &expected[1..];
+    assert_eq!(
+        output, expected,
+        "\n\nUnexpected output:\n  input: {}\n  output:\n{}\n  expected: {}",
+        input, output, expected
+    );
+}
+
+const ORDER: &str = r#"{
+    "orderId": 1,
+    "customer": { "name": "Alice", "vip": true },
+    "lines": [{ "sku": "a", "price": 2.5 }, { "sku": "b", "price": 3, "note": "gift" }],
+    "coupon": null
+}"#;
+
+#[test]
+fn orm_models() {
+    template_output_test(
+        ORDER,
+        r#"{{#types}}
+class {{name}}(Model):
+{{#fields}}
+    {{name}} = {{#type_ref}}Ref("{{type_ref}}"){{/type_ref}}{{^type_ref}}Field("{{kind}}"){{/type_ref}}{{#renamed}}  # {{json_name}}{{/renamed}}
+{{/fields}}
+{{^last}}
+
+{{/last}}
+{{/types}}
+"#,
+        r##"
+class Root(Model):
+    order_id = Field("integer")  # orderId
+    customer = Ref("Customer")
+    lines = Ref("Lines")
+    coupon = Field("any")
+
+class Customer(Model):
+    name = Field("string")
+    vip = Field("boolean")
+
+class Lines(Model):
+    sku = Field("string")
+    price = Field("number")
+    note = Field("string")
+"##,
+    );
+}
+
+#[test]
+fn documentation_table() {
+    template_output_test(
+        ORDER,
+        r#"{{#types}}
+## {{name}}
+
+| Field | Type | Required |
+|-------|------|----------|
+{{#fields}}
+| `{{json_name}}` | {{type}} | {{^optional}}yes{{/optional}}{{#optional}}no{{/optional}} |
+{{/fields}}
+{{^last}}
+
+{{/last}}
+{{/types}}
+"#,
+        r##"
+## Root
+
+| Field | Type | Required |
+|-------|------|----------|
+| `orderId` | integer | yes |
+| `customer` | Customer | yes |
+| `lines` | Lines[] | yes |
+| `coupon` | any | yes |
+
+## Customer
+
+| Field | Type | Required |
+|-------|------|----------|
+| `name` | string | yes |
+| `vip` | boolean | yes |
+
+## Lines
+
+| Field | Type | Required |
+|-------|------|----------|
+| `sku` | string | yes |
+| `price` | number | yes |
+| `note` | string | no |
+"##,
+    );
+}
+
+#[test]
+fn aliases_and_separators() {
+    template_output_test(
+        r#"[{ "a": 1, "b": [{ "c": null }, { "c": 1 }] }]"#,
+        r#"{{#aliases}}{{name}} = {{type}} ({{kind}} of {{type_ref}})
+{{/aliases}}
+{{#types}}{{name}}({{#fields}}{{camel_name}}: {{type}}{{#nullable}} or null{{/nullable}}{{^last}}, {{/last}}{{/fields}})
+{{/types}}"#,
+        r##"
+Root = Root2[] (list of Root2)
+Root2(a: integer, b: B[])
+B(c: integer? or null)
+"##,
+    );
+}
+
+#[test]
+fn template_errors() {
+    assert!(render("{}", "{{#types}}").is_err());
+    assert!(render("{}", "{{#types}}{{/fields}}").is_err());
+    assert!(render("{}", "{{types").is_err());
+    assert!(render("{}", "{{unknown}}").is_err());
+    let mut options = Options::default();
+    options.output_mode = OutputMode::Template;
+    assert!(codegen("Root", "{}", options).is_err());
+}
@@ -0,0 +1,71 @@
+use json_typegen_shared::{InputMode, Options, Shape, infer_shape, validate_sample};
+
+fn check(sample: &str, shape: &Shape) -> Vec<String> {
+    validate_sample(sample, shape)
+        .unwrap()
+        .iter()
+        .map(|mismatch| mismatch.to_string())
+        .collect()
+}
+
+const REFERENCE: &str = r#"[
+    { "id": 1, "price": 1.5, "name": "a", "note": null, "tags": ["x"], "pair": [1, "b"], "extra": 1 },
+    { "id": 2, "price": 2, "name": "b", "note": "n", "tags": [], "pair": [2, "c"] }
+]"#;
+
+#[test]
+fn fitting_sample() {
+    let shape = infer_shape(REFERENCE, Options::default()).unwrap();
+    assert_eq!(
+        check(
+            r#"[{ "id": 3, "price": 4, "name": "c", "note": null, "tags": [], "pair": [3, "d"], "new": {} }]"#,
+            &shape
+        ),
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+fn mismatches() {
+    let shape = infer_shape(REFERENCE, Options::default()).unwrap();
+    assert_eq!(
+        check(
+            r#"[
+                { "id": 1.5, "price": "1", "name": null, "note": 3, "tags": ["y", 2], "pair": [1] },
+                { "id": 2, "price": 2, "pair": [2, "c"], "tags": {} }
+            ]"#,
+            &shape
+        ),
+        vec![
+            "/0/id: expected integer, found floating",
+            "/0/price: expected floating, found string",
+            "/0/name: expected string, found null",
+            "/0/note: expected string, found integer",
+            "/0/tags/1: expected string, found integer",
+            "/0/pair: expected tuple of 2, found array of 1",
+            "/1/tags: expected array of string, found object",
+            "/1/name: expected string, found missing",
+        ]
+    );
+}
+
+#[test]
+fn saved_shape() {
+    let mut options = Options::default();
+    options.input_mode = InputMode::ShapeJson;
+    let shape = infer_shape(
+        r#"{ "json_typegen_shape": 1, "shape": { "type": "map", "values": "integer" } }"#,
+        options,
+    )
+    .unwrap();
+    assert_eq!(
+        check(r#"{ "a": 1, "b/c": "2" }"#, &shape),
+        vec!["/b~1c: expected integer, found string"]
+    );
+}
+
+#[test]
+fn invalid_json() {
+    let err = validate_sample(r#"{ "a": 1,, }"#, &Shape::Any).unwrap_err();
+    assert_eq!(err.to_string(), "An error occurred while parsing JSON");
+}
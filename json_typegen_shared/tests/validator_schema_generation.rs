@@ -0,0 +1,135 @@
+use json_typegen_shared::{Options, OutputMode, codegen};
+
+fn code_output_test(name: &str, input: &str, output_mode: OutputMode, expected: &str) {
+    let mut options = Options::default();
+    options.output_mode = output_mode;
+    let res = codegen(name, input, options);
+    let output = res.unwrap();
+    let expected = &expected[1..];
+    assert_eq!(
+        output, expected,
+        "\n\nUnexpected output code:\n  input: {}\n  output:\n{}\n  expected: {}",
+        input, output, expected
+    );
+}
+
+const INPUT: &str = r##"
+    [
+        {
+            "id": 1,
+            "display-name": "Alice",
+            "nickname": null,
+            "tags": ["admin"],
+            "address": { "city": "Oslo" },
+            "scores": { "math": 1.5 }
+        },
+        {
+            "id": 2,
+            "display-name": "Bob",
+            "nickname": "bobby",
+            "tags": [],
+            "scores": { "math": 2 }
+        }
+    ]
+"##;
+
+#[test]
+fn valibot() {
+    code_output_test(
+        "Users",
+        INPUT,
+        OutputMode::Valibot,
+        r##"
+export const usersSchema = v.array(v.object({
+    id: v.number(),
+    "display-name": v.string(),
+    nickname: v.nullable(v.string()),
+    tags: v.array(v.string()),
+    address: v.optional(v.object({
+        city: v.string(),
+    })),
+    scores: v.object({
+        math: v.number(),
+    }),
+}));
+
+export type Users = v.InferOutput<typeof usersSchema>;
+"##,
+    );
+}
+
+#[test]
+fn io_ts() {
+    code_output_test(
+        "Users",
+        INPUT,
+        OutputMode::IoTs,
+        r##"
+export const usersCodec = t.array(t.intersection([t.type({
+    id: t.number,
+    "display-name": t.string,
+    nickname: t.union([t.string, t.null]),
+    tags: t.array(t.string),
+    scores: t.type({
+        math: t.number,
+    }),
+}), t.partial({
+    address: t.type({
+        city: t.string,
+    }),
+})]));
+
+export type Users = t.TypeOf<typeof usersCodec>;
+"##,
+    );
+}
+
+#[test]
+fn arktype() {
+    code_output_test(
+        "Users",
+        INPUT,
+        OutputMode::ArkType,
+        r##"
+export const usersSchema = type({
+    id: "number",
+    "display-name": "string",
+    nickname: "string | null",
+    tags: "string[]",
+    "address?": type({
+        city: "string",
+    }),
+    scores: type({
+        math: "number",
+    }),
+}).array();
+
+export type Users = typeof usersSchema.infer;
+"##,
+    );
+}
+
+#[test]
+fn effect_schema() {
+    code_output_test(
+        "Users",
+        INPUT,
+        OutputMode::EffectSchema,
+        r##"
+export const usersSchema = Schema.Array(Schema.Struct({
+    id: Schema.Number,
+    "display-name": Schema.String,
+    nickname: Schema.NullOr(Schema.String),
+    tags: Schema.Array(Schema.String),
+    address: Schema.optional(Schema.Struct({
+        city: Schema.String,
+    })),
+    scores: Schema.Struct({
+        math: Schema.Number,
+    }),
+}));
+
+export type Users = typeof usersSchema.Type;
+"##,
+    );
+}
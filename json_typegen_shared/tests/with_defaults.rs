@@ -6,6 +6,10 @@ use json_typegen_shared::{ImportStyle, Options, codegen};
 fn code_output_test(name: &str, input: &str, expected: &str) {
     let mut options = Options::default();
     options.import_style = ImportStyle::AssumeExisting;
+    options_output_test(name, input, options, expected);
+}
+
+fn options_output_test(name: &str, input: &str, options: Options, expected: &str) {
     let res = codegen(name, input, options);
     let output = res.unwrap();
     assert_eq!(
@@ -128,6 +132,55 @@ fn optionals() {
     );
 }
 
+#[test]
+fn round_trip_optionals() {
+    let mut options = Options::default();
+    options.import_style = ImportStyle::AssumeExisting;
+    options.round_trip = true;
+    options_output_test(
+        "Optionals",
+        // language=JSON
+        r##"
+            [
+                {
+                    "in_both": 5,
+                    "missing": 5,
+                    "has_null": 5,
+                    "list": [1],
+                    "missing_or_null": 5
+                },
+                {
+                    "in_both": 5,
+                    "has_null": null,
+                    "missing_or_null": null
+                },
+                {
+                    "in_both": 5,
+                    "has_null": 5,
+                    "missing": 5
+                }
+            ]
+        "##,
+        options,
+        // language=Rust
+        r##"
+            pub type Optionals = Vec<Optional>;
+
+            #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+            pub struct Optional {
+                pub in_both: i64,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                pub missing: Option<i64>,
+                pub has_null: Option<i64>,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                pub list: Option<Vec<i64>>,
+                #[serde(default, skip_serializing_if = "Option::is_none", with = "::serde_with::rust::double_option")]
+                pub missing_or_null: Option<Option<i64>>,
+            }
+        "##,
+    );
+}
+
 #[test]
 fn fallback() {
     code_output_test(
@@ -0,0 +1,72 @@
+use json_typegen_shared::{Options, OutputMode, codegen};
+
+fn code_output_test(name: &str, input: &str, expected: &str) {
+    let mut options = Options::default();
+    options.output_mode = OutputMode::ZodSchema;
+    let res = codegen(name, input, options);
+    let output = res.unwrap();
+    let expected = &expected[1..];
+    assert_eq!(
+        output, expected,
+        "\n\nUnexpected output code:\n  input: {}\n  output:\n{}\n  expected: {}",
+        input, output, expected
+    );
+}
+
+#[test]
+fn point() {
+    code_output_test(
+        "Point",
+        r##"
+            {
+                "x": 2,
+                "y": 3
+            }
+        "##,
+        r##"
+export const pointSchema = z.object({
+    x: z.number(),
+    y: z.number(),
+});
+
+export type Point = z.infer<typeof pointSchema>;
+"##,
+    );
+}
+
+#[test]
+fn hoisted_and_deduplicated_objects() {
+    code_output_test(
+        "Order",
+        r##"
+            {
+                "billing": { "street": "Main Street 1", "city": "Oslo" },
+                "shipping": { "street": "Side Street 2", "city": "Bergen" },
+                "items": [{ "sku": "A-1", "quantity": 2 }]
+            }
+        "##,
+        r##"
+export const billingSchema = z.object({
+    street: z.string(),
+    city: z.string(),
+});
+
+export type Billing = z.infer<typeof billingSchema>;
+
+export const itemSchema = z.object({
+    sku: z.string(),
+    quantity: z.number(),
+});
+
+export type Item = z.infer<typeof itemSchema>;
+
+export const orderSchema = z.object({
+    billing: billingSchema,
+    shipping: billingSchema,
+    items: itemSchema.array(),
+});
+
+export type Order = z.infer<typeof orderSchema>;
+"##,
+    );
+}
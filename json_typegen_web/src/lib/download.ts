@@ -5,9 +5,29 @@ function createFilename(typename: string, output_mode: string): string {
     "typescript/typealias": "ts",
     "kotlin/jackson": "kt",
     "kotlin/kotlinx": "kt",
+    "kotlin/moshi": "kt",
+    "kotlin/gson": "kt",
     python: "py",
+    "python/pydantic_v1": "py",
+    "python/dataclass": "py",
+    "python/typeddict": "py",
+    "python/attrs": "py",
+    "python/msgspec": "py",
     json_schema: "json",
+    zod: "ts",
+    valibot: "ts",
+    io_ts: "ts",
+    arktype: "ts",
+    effect_schema: "ts",
+    protobuf: "proto",
+    graphql: "graphql",
+    avro: "avsc",
+    sql: "sql",
     shape: "json",
+    shape_json: "json",
+    mock: "json",
+    markdown: "md",
+    html: "html",
   };
   return typename + "." + extensions[output_mode];
 }